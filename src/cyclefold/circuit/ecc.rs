@@ -0,0 +1,148 @@
+//! This module implements the CycleFold circuit, which runs on the secondary curve and performs the
+//! elliptic curve operations needed to fold the commitments of the primary circuit's instances.
+//!
+//! Given commitments `W_1`, `W_2`, `E_1`, `T` on the primary curve and a challenge `r`,
+//! the circuit computes `W = W_1 + r * W_2` and `E = E_1 + r * T` and outputs
+//! the coordinates of `W_1`, `W_2`, `W`, `E_1`, `T`, `E` followed by `r`.
+//! The point at infinity is output as the coordinates (0, 0).
+use crate::{
+  constants::NUM_CHALLENGE_BITS,
+  frontend::{num::AllocatedNum, AllocatedBit, ConstraintSystem, SynthesisError},
+  gadgets::{
+    ecc::AllocatedPoint,
+    utils::{le_bits_to_num, select_zero_or_num2},
+  },
+  traits::{commitment::CommitmentTrait, Engine},
+  Commitment,
+};
+use ff::PrimeFieldBits;
+
+/// The number of public IO of the CycleFold circuit
+pub(crate) const NUM_IO_CYCLEFOLD: usize = 13;
+
+/// The inputs of the CycleFold circuit
+#[derive(Clone, Debug)]
+pub struct CycleFoldInputs<E: Engine> {
+  comm_W_1: Commitment<E>,
+  comm_W_2: Commitment<E>,
+  comm_E_1: Commitment<E>,
+  comm_T: Commitment<E>,
+  r: E::Scalar,
+}
+
+impl<E: Engine> CycleFoldInputs<E> {
+  /// Create new inputs for the CycleFold circuit
+  pub fn new(
+    comm_W_1: Commitment<E>,
+    comm_W_2: Commitment<E>,
+    comm_E_1: Commitment<E>,
+    comm_T: Commitment<E>,
+    r: E::Scalar,
+  ) -> Self {
+    Self {
+      comm_W_1,
+      comm_W_2,
+      comm_E_1,
+      comm_T,
+      r,
+    }
+  }
+}
+
+/// The CycleFold circuit, which is defined over the base field of `E`
+pub struct CycleFoldCircuit<E: Engine> {
+  inputs: Option<CycleFoldInputs<E>>,
+}
+
+impl<E: Engine> CycleFoldCircuit<E> {
+  /// Create a new CycleFold circuit for the provided inputs
+  pub const fn new(inputs: Option<CycleFoldInputs<E>>) -> Self {
+    Self { inputs }
+  }
+
+  /// synthesize circuit giving constraint system
+  pub fn synthesize<CS: ConstraintSystem<E::Base>>(
+    self,
+    cs: &mut CS,
+  ) -> Result<(), SynthesisError> {
+    let inputs = self.inputs.as_ref();
+
+    // Allocate the bits of r, which also bounds r to NUM_CHALLENGE_BITS
+    let r_bits = inputs.map(|inputs| inputs.r.to_le_bits()).map(|bits| {
+      bits
+        .into_iter()
+        .take(NUM_CHALLENGE_BITS)
+        .collect::<Vec<_>>()
+    });
+    let r_bits = (0..NUM_CHALLENGE_BITS)
+      .map(|i| {
+        AllocatedBit::alloc(
+          cs.namespace(|| format!("r bit {i}")),
+          r_bits.as_ref().map(|bits| bits[i]),
+        )
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    let r = le_bits_to_num(cs.namespace(|| "r"), &r_bits)?;
+
+    let W_1 = alloc_point::<E, _>(cs.namespace(|| "W_1"), inputs.map(|i| i.comm_W_1))?;
+    let W_2 = alloc_point::<E, _>(cs.namespace(|| "W_2"), inputs.map(|i| i.comm_W_2))?;
+    let E_1 = alloc_point::<E, _>(cs.namespace(|| "E_1"), inputs.map(|i| i.comm_E_1))?;
+    let T = alloc_point::<E, _>(cs.namespace(|| "T"), inputs.map(|i| i.comm_T))?;
+
+    // W = W_1 + r * W_2
+    let rW_2 = W_2.scalar_mul(cs.namespace(|| "r * W_2"), &r_bits)?;
+    let W = W_1.add(cs.namespace(|| "W_1 + r * W_2"), &rW_2)?;
+
+    // E = E_1 + r * T
+    let rT = T.scalar_mul(cs.namespace(|| "r * T"), &r_bits)?;
+    let E = E_1.add(cs.namespace(|| "E_1 + r * T"), &rT)?;
+
+    for (name, p) in [
+      ("W_1", &W_1),
+      ("W_2", &W_2),
+      ("W", &W),
+      ("E_1", &E_1),
+      ("T", &T),
+      ("E", &E),
+    ] {
+      inputize_point(cs.namespace(|| format!("output {name}")), p)?;
+    }
+    r.inputize(cs.namespace(|| "output r"))?;
+
+    Ok(())
+  }
+}
+
+/// Allocates a point and checks that it is on the curve or that it is the point at infinity
+/// with coordinates (0, 0). Since (0, 0) is not on any of the supported curves,
+/// the coordinates of the point determine whether it is the point at infinity.
+fn alloc_point<E: Engine, CS: ConstraintSystem<E::Base>>(
+  mut cs: CS,
+  comm: Option<Commitment<E>>,
+) -> Result<AllocatedPoint<E>, SynthesisError> {
+  let p = AllocatedPoint::alloc(
+    cs.namespace(|| "alloc"),
+    comm.map(|comm| comm.to_coordinates()),
+  )?;
+  p.check_on_curve(cs.namespace(|| "check on curve"))?;
+  for (name, c) in [("x", &p.x), ("y", &p.y)] {
+    cs.enforce(
+      || format!("is_infinity * {name} = 0"),
+      |lc| lc + p.is_infinity.get_variable(),
+      |lc| lc + c.get_variable(),
+      |lc| lc,
+    );
+  }
+  Ok(p)
+}
+
+/// Outputs the coordinates of a point, using (0, 0) for the point at infinity
+fn inputize_point<E: Engine, CS: ConstraintSystem<E::Base>>(
+  mut cs: CS,
+  p: &AllocatedPoint<E>,
+) -> Result<(), SynthesisError> {
+  let x: AllocatedNum<E::Base> = select_zero_or_num2(cs.namespace(|| "x"), &p.x, &p.is_infinity)?;
+  let y = select_zero_or_num2(cs.namespace(|| "y"), &p.y, &p.is_infinity)?;
+  x.inputize(cs.namespace(|| "output x"))?;
+  y.inputize(cs.namespace(|| "output y"))
+}
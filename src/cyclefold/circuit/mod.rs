@@ -0,0 +1,448 @@
+//! This module implements the circuits of the CycleFold variant of Nova.
+//!
+//! The augmented circuit runs on the primary curve and folds the last instance of itself into
+//! its running instance. It folds the scalars of the instances natively, while the commitments,
+//! which live on the primary curve, are folded by the CycleFold circuit on the secondary curve.
+//! The augmented circuit then folds the instance of the CycleFold circuit into a running
+//! CycleFold instance, whose commitments are native to it. Its single output is the hash
+//! H(params, i, z0, zi, U, U_cyclefold, ri).
+use crate::{
+  constants::{BN_LIMB_WIDTH, BN_N_LIMBS, NUM_CHALLENGE_BITS, NUM_HASH_BITS},
+  frontend::{
+    num::AllocatedNum, AllocatedBit, Assignment, Boolean, ConstraintSystem, SynthesisError,
+  },
+  gadgets::{
+    ecc::AllocatedPoint,
+    nonnative::{bignat::BigNat, util::Num},
+    utils::{alloc_num_equals, alloc_zero, conditionally_select_vec, le_bits_to_num},
+  },
+  r1cs::{R1CSInstance, RelaxedR1CSInstance},
+  traits::{
+    circuit::StepCircuit, commitment::CommitmentTrait, Engine, ROCircuitTrait, ROConstantsCircuit,
+  },
+  Commitment,
+};
+use ff::Field;
+use serde::{Deserialize, Serialize};
+
+pub(crate) mod ecc;
+mod r1cs;
+
+use ecc::NUM_IO_CYCLEFOLD;
+use r1cs::{
+  AllocatedCycleFoldInstance, AllocatedNonnativePoint, AllocatedNonnativeR1CSInstance,
+  AllocatedNonnativeRelaxedR1CSInstance, AllocatedRelaxedCycleFoldInstance,
+};
+
+/// The number of public IO of the augmented circuit
+pub(crate) const NUM_IO_PRIMARY: usize = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct CycleFoldAugmentedCircuitInputs<E1, E2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+{
+  pp_digest: E1::Scalar,
+  i: E1::Scalar,
+  z0: Vec<E1::Scalar>,
+  zi: Option<Vec<E1::Scalar>>,
+  U: Option<RelaxedR1CSInstance<E1>>,
+  ri: Option<E1::Scalar>,
+  r_next: E1::Scalar,
+  u: Option<R1CSInstance<E1>>,
+  T: Option<Commitment<E1>>,
+  U_fold: Option<RelaxedR1CSInstance<E1>>,
+  U_cyclefold: Option<RelaxedR1CSInstance<E2>>,
+  u_cyclefold: Option<R1CSInstance<E2>>,
+  T_cyclefold: Option<Commitment<E2>>,
+}
+
+impl<E1, E2> CycleFoldAugmentedCircuitInputs<E1, E2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+{
+  /// Create new inputs/witness for the augmented circuit.
+  /// `U_fold` is the result of folding `u` into `U`, whose commitments the circuit takes as advice,
+  /// and `u_cyclefold` is the instance of the CycleFold circuit that checks them.
+  pub fn new(
+    pp_digest: E1::Scalar,
+    i: E1::Scalar,
+    z0: Vec<E1::Scalar>,
+    zi: Option<Vec<E1::Scalar>>,
+    U: Option<RelaxedR1CSInstance<E1>>,
+    ri: Option<E1::Scalar>,
+    r_next: E1::Scalar,
+    u: Option<R1CSInstance<E1>>,
+    T: Option<Commitment<E1>>,
+    U_fold: Option<RelaxedR1CSInstance<E1>>,
+    U_cyclefold: Option<RelaxedR1CSInstance<E2>>,
+    u_cyclefold: Option<R1CSInstance<E2>>,
+    T_cyclefold: Option<Commitment<E2>>,
+  ) -> Self {
+    Self {
+      pp_digest,
+      i,
+      z0,
+      zi,
+      U,
+      ri,
+      r_next,
+      u,
+      T,
+      U_fold,
+      U_cyclefold,
+      u_cyclefold,
+      T_cyclefold,
+    }
+  }
+}
+
+/// The augmented circuit F' of the CycleFold variant of Nova, which includes a step circuit F
+/// and the circuit for the verifier of the folding scheme
+pub struct CycleFoldAugmentedCircuit<'a, E1, E2, SC>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  SC: StepCircuit<E1::Scalar>,
+{
+  ro_consts: ROConstantsCircuit<E2>,
+  inputs: Option<CycleFoldAugmentedCircuitInputs<E1, E2>>,
+  step_circuit: &'a SC,
+}
+
+impl<'a, E1, E2, SC> CycleFoldAugmentedCircuit<'a, E1, E2, SC>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  SC: StepCircuit<E1::Scalar>,
+{
+  /// Create a new verification circuit for the input relaxed r1cs instances
+  pub const fn new(
+    inputs: Option<CycleFoldAugmentedCircuitInputs<E1, E2>>,
+    step_circuit: &'a SC,
+    ro_consts: ROConstantsCircuit<E2>,
+  ) -> Self {
+    Self {
+      inputs,
+      step_circuit,
+      ro_consts,
+    }
+  }
+
+  /// Computes H(params, i, z0, zi, U, U_cyclefold, ri)
+  fn synthesize_hash<CS: ConstraintSystem<E1::Scalar>>(
+    &self,
+    mut cs: CS,
+    pp_digest: &AllocatedNum<E1::Scalar>,
+    i: &AllocatedNum<E1::Scalar>,
+    z_0: &[AllocatedNum<E1::Scalar>],
+    z_i: &[AllocatedNum<E1::Scalar>],
+    U: &AllocatedNonnativeRelaxedR1CSInstance<E1>,
+    U_cyclefold: &AllocatedRelaxedCycleFoldInstance<E2>,
+    r_i: &AllocatedNum<E1::Scalar>,
+  ) -> Result<AllocatedNum<E1::Scalar>, SynthesisError> {
+    let mut ro = E2::ROCircuit::new(self.ro_consts.clone());
+    ro.absorb(pp_digest);
+    ro.absorb(i);
+    for e in z_0 {
+      ro.absorb(e);
+    }
+    for e in z_i {
+      ro.absorb(e);
+    }
+    U.absorb_in_ro(cs.namespace(|| "absorb U"), &mut ro)?;
+    U_cyclefold.absorb_in_ro(cs.namespace(|| "absorb U_cyclefold"), &mut ro)?;
+    ro.absorb(r_i);
+
+    let hash_bits = ro.squeeze(cs.namespace(|| "hash"), NUM_HASH_BITS)?;
+    le_bits_to_num(cs.namespace(|| "bits to hash"), &hash_bits)
+  }
+
+  /// synthesize circuit giving constraint system
+  pub fn synthesize<CS: ConstraintSystem<E1::Scalar>>(
+    self,
+    cs: &mut CS,
+  ) -> Result<Vec<AllocatedNum<E1::Scalar>>, SynthesisError> {
+    let arity = self.step_circuit.arity();
+    let inputs = self.inputs.as_ref();
+
+    // Allocate all witnesses
+    let pp_digest = AllocatedNum::alloc(cs.namespace(|| "pp_digest"), || {
+      Ok(self.inputs.get()?.pp_digest)
+    })?;
+    let i = AllocatedNum::alloc(cs.namespace(|| "i"), || Ok(self.inputs.get()?.i))?;
+    let z_0 = (0..arity)
+      .map(|i| {
+        AllocatedNum::alloc(cs.namespace(|| format!("z0_{i}")), || {
+          Ok(self.inputs.get()?.z0[i])
+        })
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    let zero = vec![E1::Scalar::ZERO; arity];
+    let z_i = (0..arity)
+      .map(|i| {
+        AllocatedNum::alloc(cs.namespace(|| format!("zi_{i}")), || {
+          Ok(self.inputs.get()?.zi.as_ref().unwrap_or(&zero)[i])
+        })
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    let U = AllocatedNonnativeRelaxedR1CSInstance::alloc(
+      cs.namespace(|| "allocate U"),
+      inputs.and_then(|inputs| inputs.U.as_ref()),
+      NUM_IO_PRIMARY,
+    )?;
+    let r_i = AllocatedNum::alloc(cs.namespace(|| "ri"), || {
+      Ok(self.inputs.get()?.ri.unwrap_or(E1::Scalar::ZERO))
+    })?;
+    let r_next = AllocatedNum::alloc(cs.namespace(|| "r_i+1"), || Ok(self.inputs.get()?.r_next))?;
+    let u = AllocatedNonnativeR1CSInstance::alloc(
+      cs.namespace(|| "allocate instance u to fold"),
+      inputs.and_then(|inputs| inputs.u.as_ref()),
+      NUM_IO_PRIMARY,
+    )?;
+    let T = AllocatedNonnativePoint::alloc(
+      cs.namespace(|| "allocate T"),
+      inputs.and_then(|inputs| inputs.T.map(|T| T.to_coordinates())),
+    )?;
+    let W_fold = AllocatedNonnativePoint::alloc(
+      cs.namespace(|| "allocate W_fold"),
+      inputs.and_then(|inputs| inputs.U_fold.as_ref().map(|U| U.comm_W.to_coordinates())),
+    )?;
+    let E_fold = AllocatedNonnativePoint::alloc(
+      cs.namespace(|| "allocate E_fold"),
+      inputs.and_then(|inputs| inputs.U_fold.as_ref().map(|U| U.comm_E.to_coordinates())),
+    )?;
+    let U_cyclefold = AllocatedRelaxedCycleFoldInstance::alloc(
+      cs.namespace(|| "allocate U_cyclefold"),
+      inputs.and_then(|inputs| inputs.U_cyclefold.as_ref()),
+      NUM_IO_CYCLEFOLD,
+    )?;
+    let comm_W_cyclefold = AllocatedPoint::alloc(
+      cs.namespace(|| "allocate u_cyclefold.comm_W"),
+      inputs.and_then(|inputs| {
+        inputs
+          .u_cyclefold
+          .as_ref()
+          .map(|u| u.comm_W.to_coordinates())
+      }),
+    )?;
+    comm_W_cyclefold.check_on_curve(cs.namespace(|| "check u_cyclefold.comm_W on curve"))?;
+    let T_cyclefold = AllocatedPoint::alloc(
+      cs.namespace(|| "allocate T_cyclefold"),
+      inputs.and_then(|inputs| inputs.T_cyclefold.map(|T| T.to_coordinates())),
+    )?;
+    T_cyclefold.check_on_curve(cs.namespace(|| "check T_cyclefold on curve"))?;
+
+    // Compute variable indicating if this is the base case
+    let zero = alloc_zero(cs.namespace(|| "zero"));
+    let is_base_case = alloc_num_equals(cs.namespace(|| "Check if base case"), &i, &zero)?;
+
+    // Check that u.X[0] = H(params, i, z0, zi, U, U_cyclefold, ri) unless this is the base case
+    let hash = self.synthesize_hash(
+      cs.namespace(|| "synthesize input hash"),
+      &pp_digest,
+      &i,
+      &z_0,
+      &z_i,
+      &U,
+      &U_cyclefold,
+      &r_i,
+    )?;
+    let check_non_base_pass = alloc_num_equals(
+      cs.namespace(|| "check consistency of u.X[0] with H(params, i, z0, zi, U, U_cyclefold, ri)"),
+      &u.X[0],
+      &hash,
+    )?;
+    let should_be_false = AllocatedBit::nor(
+      cs.namespace(|| "check_non_base_pass nor base_case"),
+      &check_non_base_pass,
+      &is_base_case,
+    )?;
+    cs.enforce(
+      || "check_non_base_pass nor base_case = false",
+      |lc| lc + should_be_false.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc,
+    );
+
+    // Compute the challenge r = H(params, u, T) and fold the scalars of u into U
+    let mut ro = E2::ROCircuit::new(self.ro_consts.clone());
+    ro.absorb(&pp_digest);
+    u.absorb_in_ro(cs.namespace(|| "absorb u"), &mut ro)?;
+    T.absorb_in_ro(cs.namespace(|| "absorb T"), &mut ro)?;
+    let r_bits = ro.squeeze(cs.namespace(|| "r bits"), NUM_CHALLENGE_BITS)?;
+    let r = le_bits_to_num(cs.namespace(|| "r"), &r_bits)?;
+    let U_fold = U.fold_with_r1cs(
+      cs.namespace(|| "fold u into U"),
+      &u,
+      &r,
+      W_fold.clone(),
+      E_fold.clone(),
+    )?;
+
+    // The instance of the CycleFold circuit that checks W_fold = U.W + r * u.W and E_fold = U.E + r * T
+    let r_bn = BigNat::from_num(
+      cs.namespace(|| "allocate r_bn"),
+      &Num::from(r),
+      BN_LIMB_WIDTH,
+      BN_N_LIMBS,
+    )?;
+    let X_cyclefold = [&U.W, &u.comm_W, &W_fold, &U.E, &T, &E_fold]
+      .into_iter()
+      .flat_map(|p| [p.x.clone(), p.y.clone()])
+      .chain([r_bn])
+      .collect::<Vec<_>>();
+    let u_cyclefold = AllocatedCycleFoldInstance {
+      comm_W: comm_W_cyclefold,
+      X: X_cyclefold,
+    };
+    let U_cyclefold_fold = U_cyclefold.fold_with_cyclefold_instance(
+      cs.namespace(|| "fold u_cyclefold into U_cyclefold"),
+      &pp_digest,
+      &u_cyclefold,
+      &T_cyclefold,
+      self.ro_consts.clone(),
+    )?;
+
+    // In the base case, both running instances are the default instances
+    let U_base = AllocatedNonnativeRelaxedR1CSInstance::default(
+      cs.namespace(|| "allocate U_base"),
+      NUM_IO_PRIMARY,
+    )?;
+    let U_cyclefold_base = AllocatedRelaxedCycleFoldInstance::default(
+      cs.namespace(|| "allocate U_cyclefold_base"),
+      NUM_IO_CYCLEFOLD,
+    )?;
+    let U_new = U_base.conditionally_select(
+      cs.namespace(|| "compute U_new"),
+      &U_fold,
+      &Boolean::from(is_base_case.clone()),
+    )?;
+    let U_cyclefold_new = U_cyclefold_base.conditionally_select(
+      cs.namespace(|| "compute U_cyclefold_new"),
+      &U_cyclefold_fold,
+      &Boolean::from(is_base_case.clone()),
+    )?;
+
+    // Compute i + 1
+    let i_new = AllocatedNum::alloc(cs.namespace(|| "i + 1"), || {
+      Ok(*i.get_value().get()? + E1::Scalar::ONE)
+    })?;
+    cs.enforce(
+      || "check i + 1",
+      |lc| lc,
+      |lc| lc,
+      |lc| lc + i_new.get_variable() - CS::one() - i.get_variable(),
+    );
+
+    // Compute z_{i+1}
+    let z_input = conditionally_select_vec(
+      cs.namespace(|| "select input to F"),
+      &z_0,
+      &z_i,
+      &Boolean::from(is_base_case),
+    )?;
+
+    let z_next = self
+      .step_circuit
+      .synthesize(&mut cs.namespace(|| "F"), &z_input)?;
+
+    if z_next.len() != arity {
+      return Err(SynthesisError::IncompatibleLengthVector(
+        "z_next".to_string(),
+      ));
+    }
+
+    // Compute the new hash H(params, i+1, z0, z_{i+1}, U_new, U_cyclefold_new, r_next)
+    let hash = self.synthesize_hash(
+      cs.namespace(|| "synthesize output hash"),
+      &pp_digest,
+      &i_new,
+      &z_0,
+      &z_next,
+      &U_new,
+      &U_cyclefold_new,
+      &r_next,
+    )?;
+    hash.inputize(cs.namespace(|| "output new hash of this circuit"))?;
+
+    Ok(z_next)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    cyclefold::circuit::ecc::CycleFoldCircuit,
+    frontend::{
+      r1cs::{NovaShape, NovaWitness},
+      solver::SatisfyingAssignment,
+      test_shape_cs::TestShapeCS,
+    },
+    provider::{
+      Bn256EngineKZG, GrumpkinEngine, PallasEngine, Secp256k1Engine, Secq256k1Engine, VestaEngine,
+    },
+    traits::{circuit::TrivialCircuit, snark::default_ck_hint},
+  };
+
+  fn test_recursive_circuit_with<E1, E2>(
+    num_constraints_primary: usize,
+    num_constraints_cyclefold: usize,
+  ) where
+    E1: Engine<Base = <E2 as Engine>::Scalar>,
+    E2: Engine<Base = <E1 as Engine>::Scalar>,
+  {
+    let ro_consts = ROConstantsCircuit::<E2>::default();
+
+    let tc = TrivialCircuit::default();
+    // Initialize the shape and ck for the primary
+    let circuit: CycleFoldAugmentedCircuit<'_, E1, E2, TrivialCircuit<E1::Scalar>> =
+      CycleFoldAugmentedCircuit::new(None, &tc, ro_consts.clone());
+    let mut cs: TestShapeCS<E1> = TestShapeCS::new();
+    let _ = circuit.synthesize(&mut cs);
+    let (shape, ck) = cs.r1cs_shape(&*default_ck_hint());
+    assert_eq!(cs.num_constraints(), num_constraints_primary);
+
+    // Initialize the shape for the CycleFold circuit
+    let circuit_cyclefold: CycleFoldCircuit<E1> = CycleFoldCircuit::new(None);
+    let mut cs: TestShapeCS<E2> = TestShapeCS::new();
+    let _ = circuit_cyclefold.synthesize(&mut cs);
+    assert_eq!(cs.num_constraints(), num_constraints_cyclefold);
+
+    // Execute the base case for the primary
+    let zero = E1::Scalar::ZERO;
+    let mut cs = SatisfyingAssignment::<E1>::new();
+    let inputs: CycleFoldAugmentedCircuitInputs<E1, E2> = CycleFoldAugmentedCircuitInputs::new(
+      zero, // pass zero for testing
+      zero,
+      vec![zero],
+      None,
+      None,
+      None,
+      zero,
+      None,
+      None,
+      None,
+      None,
+      None,
+      None,
+    );
+    let circuit: CycleFoldAugmentedCircuit<'_, E1, E2, TrivialCircuit<E1::Scalar>> =
+      CycleFoldAugmentedCircuit::new(Some(inputs), &tc, ro_consts);
+    let _ = circuit.synthesize(&mut cs);
+    let (inst, witness) = cs.r1cs_instance_and_witness(&shape, &ck).unwrap();
+    // Make sure that this is satisfiable
+    assert!(shape.is_sat(&ck, &inst, &witness).is_ok());
+  }
+
+  #[test]
+  fn test_recursive_circuit() {
+    test_recursive_circuit_with::<PallasEngine, VestaEngine>(38997, 2624);
+    test_recursive_circuit_with::<Bn256EngineKZG, GrumpkinEngine>(39221, 2624);
+    test_recursive_circuit_with::<Secp256k1Engine, Secq256k1Engine>(39593, 2624);
+  }
+}
@@ -0,0 +1,539 @@
+//! This module implements the gadgets for the instances handled by the primary circuit of CycleFold.
+//!
+//! Instances of the primary circuit carry commitments on the primary curve, whose coordinates are
+//! non-native to the primary circuit, so they are allocated as `BigNat`s and never operated on:
+//! the elliptic curve operations on them are delegated to the CycleFold circuit.
+//! Instances of the CycleFold circuit carry commitments on the secondary curve, which are native
+//! to the primary circuit, and public IO that is non-native, so they are folded as in Nova.
+use crate::{
+  constants::{BN_LIMB_WIDTH, BN_N_LIMBS, NUM_CHALLENGE_BITS},
+  frontend::{num::AllocatedNum, Assignment, Boolean, ConstraintSystem, SynthesisError},
+  gadgets::{
+    ecc::AllocatedPoint,
+    nonnative::{
+      bignat::BigNat,
+      util::{f_to_nat, Num},
+    },
+    utils::{
+      alloc_bignat_constant, alloc_zero, conditionally_select, conditionally_select_bignat,
+      le_bits_to_num,
+    },
+  },
+  r1cs::{R1CSInstance, RelaxedR1CSInstance},
+  traits::{commitment::CommitmentTrait, Engine, Group, ROCircuitTrait, ROConstantsCircuit},
+};
+use ff::Field;
+use num_bigint::BigInt;
+
+/// A point on the curve of `E` allocated in a circuit over `E::Scalar`.
+/// The point at infinity is represented by the coordinates (0, 0).
+#[derive(Clone)]
+pub struct AllocatedNonnativePoint<E: Engine> {
+  pub(crate) x: BigNat<E::Scalar>,
+  pub(crate) y: BigNat<E::Scalar>,
+}
+
+impl<E: Engine> AllocatedNonnativePoint<E> {
+  /// Allocates a point with the provided coordinates, checking that they are well-formed `BigNat`s.
+  /// If coords = None, it allocates the point at infinity
+  pub fn alloc<CS: ConstraintSystem<E::Scalar>>(
+    mut cs: CS,
+    coords: Option<(E::Base, E::Base, bool)>,
+  ) -> Result<Self, SynthesisError> {
+    let x = alloc_coordinate::<E, _>(cs.namespace(|| "x"), coords.map(|c| c.0))?;
+    let y = alloc_coordinate::<E, _>(cs.namespace(|| "y"), coords.map(|c| c.1))?;
+
+    Ok(Self { x, y })
+  }
+
+  /// Allocates the point at infinity as a constant
+  pub fn default<CS: ConstraintSystem<E::Scalar>>(mut cs: CS) -> Result<Self, SynthesisError> {
+    let x = alloc_bignat_constant(
+      cs.namespace(|| "x"),
+      &BigInt::from(0),
+      BN_LIMB_WIDTH,
+      BN_N_LIMBS,
+    )?;
+    let y = x.clone();
+    Ok(Self { x, y })
+  }
+
+  /// Absorbs the limbs of the coordinates in the RO
+  pub fn absorb_in_ro<CS, RO>(&self, mut cs: CS, ro: &mut RO) -> Result<(), SynthesisError>
+  where
+    CS: ConstraintSystem<E::Scalar>,
+    RO: ROCircuitTrait<E::Scalar>,
+  {
+    absorb_bignat_in_ro(cs.namespace(|| "x"), &self.x, ro)?;
+    absorb_bignat_in_ro(cs.namespace(|| "y"), &self.y, ro)
+  }
+
+  /// If the condition is true then returns this otherwise it returns the other
+  pub fn conditionally_select<CS: ConstraintSystem<E::Scalar>>(
+    &self,
+    mut cs: CS,
+    other: &Self,
+    condition: &Boolean,
+  ) -> Result<Self, SynthesisError> {
+    let x = conditionally_select_bignat(cs.namespace(|| "x"), &self.x, &other.x, condition)?;
+    let y = conditionally_select_bignat(cs.namespace(|| "y"), &self.y, &other.y, condition)?;
+    Ok(Self { x, y })
+  }
+}
+
+/// An allocated R1CS instance of the primary circuit
+#[derive(Clone)]
+pub struct AllocatedNonnativeR1CSInstance<E: Engine> {
+  pub(crate) comm_W: AllocatedNonnativePoint<E>,
+  pub(crate) X: Vec<AllocatedNum<E::Scalar>>,
+}
+
+impl<E: Engine> AllocatedNonnativeR1CSInstance<E> {
+  /// Takes the r1cs instance and creates a new allocated r1cs instance with `num_io` public IO
+  pub fn alloc<CS: ConstraintSystem<E::Scalar>>(
+    mut cs: CS,
+    u: Option<&R1CSInstance<E>>,
+    num_io: usize,
+  ) -> Result<Self, SynthesisError> {
+    let comm_W = AllocatedNonnativePoint::alloc(
+      cs.namespace(|| "allocate comm_W"),
+      u.map(|u| u.comm_W.to_coordinates()),
+    )?;
+
+    let X = (0..num_io)
+      .map(|i| {
+        AllocatedNum::alloc(cs.namespace(|| format!("allocate X[{i}]")), || {
+          Ok(u.map_or(E::Scalar::ZERO, |u| u.X[i]))
+        })
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Self { comm_W, X })
+  }
+
+  /// Absorb the provided instance in the RO
+  pub fn absorb_in_ro<CS, RO>(&self, mut cs: CS, ro: &mut RO) -> Result<(), SynthesisError>
+  where
+    CS: ConstraintSystem<E::Scalar>,
+    RO: ROCircuitTrait<E::Scalar>,
+  {
+    self
+      .comm_W
+      .absorb_in_ro(cs.namespace(|| "absorb comm_W"), ro)?;
+    for x in &self.X {
+      ro.absorb(x);
+    }
+    Ok(())
+  }
+}
+
+/// An allocated relaxed R1CS instance of the primary circuit
+#[derive(Clone)]
+pub struct AllocatedNonnativeRelaxedR1CSInstance<E: Engine> {
+  pub(crate) W: AllocatedNonnativePoint<E>,
+  pub(crate) E: AllocatedNonnativePoint<E>,
+  pub(crate) u: AllocatedNum<E::Scalar>,
+  pub(crate) X: Vec<AllocatedNum<E::Scalar>>,
+}
+
+impl<E: Engine> AllocatedNonnativeRelaxedR1CSInstance<E> {
+  /// Allocates the given `RelaxedR1CSInstance` with `num_io` public IO as a witness of the circuit
+  pub fn alloc<CS: ConstraintSystem<E::Scalar>>(
+    mut cs: CS,
+    inst: Option<&RelaxedR1CSInstance<E>>,
+    num_io: usize,
+  ) -> Result<Self, SynthesisError> {
+    let W = AllocatedNonnativePoint::alloc(
+      cs.namespace(|| "allocate W"),
+      inst.map(|inst| inst.comm_W.to_coordinates()),
+    )?;
+
+    let E = AllocatedNonnativePoint::alloc(
+      cs.namespace(|| "allocate E"),
+      inst.map(|inst| inst.comm_E.to_coordinates()),
+    )?;
+
+    let u = AllocatedNum::alloc(cs.namespace(|| "allocate u"), || {
+      Ok(inst.map_or(E::Scalar::ZERO, |inst| inst.u))
+    })?;
+
+    let X = (0..num_io)
+      .map(|i| {
+        AllocatedNum::alloc(cs.namespace(|| format!("allocate X[{i}]")), || {
+          Ok(inst.map_or(E::Scalar::ZERO, |inst| inst.X[i]))
+        })
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Self { W, E, u, X })
+  }
+
+  /// Allocates the default `RelaxedR1CSInstance` with `num_io` public IO as constants of the circuit.
+  /// W = E = 0, u = 0, X = 0
+  pub fn default<CS: ConstraintSystem<E::Scalar>>(
+    mut cs: CS,
+    num_io: usize,
+  ) -> Result<Self, SynthesisError> {
+    let W = AllocatedNonnativePoint::default(cs.namespace(|| "allocate W"))?;
+    let E = W.clone();
+    let u = alloc_zero(cs.namespace(|| "allocate u"));
+    let X = vec![u.clone(); num_io];
+    Ok(Self { W, E, u, X })
+  }
+
+  /// Absorb the provided instance in the RO
+  pub fn absorb_in_ro<CS, RO>(&self, mut cs: CS, ro: &mut RO) -> Result<(), SynthesisError>
+  where
+    CS: ConstraintSystem<E::Scalar>,
+    RO: ROCircuitTrait<E::Scalar>,
+  {
+    self.W.absorb_in_ro(cs.namespace(|| "absorb W"), ro)?;
+    self.E.absorb_in_ro(cs.namespace(|| "absorb E"), ro)?;
+    ro.absorb(&self.u);
+    for x in &self.X {
+      ro.absorb(x);
+    }
+    Ok(())
+  }
+
+  /// Folds self with an r1cs instance using the challenge `r` and returns the result.
+  /// Only the scalars are folded in the circuit: the folded commitments `W` and `E` are supplied
+  /// by the caller, who must check them against a CycleFold instance.
+  pub fn fold_with_r1cs<CS: ConstraintSystem<E::Scalar>>(
+    &self,
+    mut cs: CS,
+    u: &AllocatedNonnativeR1CSInstance<E>,
+    r: &AllocatedNum<E::Scalar>,
+    W: AllocatedNonnativePoint<E>,
+    E: AllocatedNonnativePoint<E>,
+  ) -> Result<Self, SynthesisError> {
+    // u_fold = self.u + r
+    let u_fold = AllocatedNum::alloc(cs.namespace(|| "u_fold"), || {
+      Ok(*self.u.get_value().get()? + *r.get_value().get()?)
+    })?;
+    cs.enforce(
+      || "check u_fold",
+      |lc| lc,
+      |lc| lc,
+      |lc| lc + u_fold.get_variable() - self.u.get_variable() - r.get_variable(),
+    );
+
+    // X_fold = self.X + r * u.X
+    let X_fold = self
+      .X
+      .iter()
+      .zip(u.X.iter())
+      .enumerate()
+      .map(|(i, (X_r, x))| {
+        let X_fold = AllocatedNum::alloc(cs.namespace(|| format!("X_fold[{i}]")), || {
+          Ok(*X_r.get_value().get()? + *r.get_value().get()? * *x.get_value().get()?)
+        })?;
+        cs.enforce(
+          || format!("check X_fold[{i}]"),
+          |lc| lc + r.get_variable(),
+          |lc| lc + x.get_variable(),
+          |lc| lc + X_fold.get_variable() - X_r.get_variable(),
+        );
+        Ok(X_fold)
+      })
+      .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+    Ok(Self {
+      W,
+      E,
+      u: u_fold,
+      X: X_fold,
+    })
+  }
+
+  /// If the condition is true then returns this otherwise it returns the other
+  pub fn conditionally_select<CS: ConstraintSystem<E::Scalar>>(
+    &self,
+    mut cs: CS,
+    other: &Self,
+    condition: &Boolean,
+  ) -> Result<Self, SynthesisError> {
+    let W = self
+      .W
+      .conditionally_select(cs.namespace(|| "W"), &other.W, condition)?;
+    let E = self
+      .E
+      .conditionally_select(cs.namespace(|| "E"), &other.E, condition)?;
+    let u = conditionally_select(cs.namespace(|| "u"), &self.u, &other.u, condition)?;
+    let X = self
+      .X
+      .iter()
+      .zip(other.X.iter())
+      .enumerate()
+      .map(|(i, (a, b))| conditionally_select(cs.namespace(|| format!("X[{i}]")), a, b, condition))
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok(Self { W, E, u, X })
+  }
+}
+
+/// An allocated R1CS instance of the CycleFold circuit, whose public IO is non-native
+#[derive(Clone)]
+pub struct AllocatedCycleFoldInstance<E: Engine> {
+  pub(crate) comm_W: AllocatedPoint<E>,
+  pub(crate) X: Vec<BigNat<E::Base>>,
+}
+
+impl<E: Engine> AllocatedCycleFoldInstance<E> {
+  /// Absorb the provided instance in the RO
+  pub fn absorb_in_ro<CS: ConstraintSystem<E::Base>>(
+    &self,
+    mut cs: CS,
+    ro: &mut E::ROCircuit,
+  ) -> Result<(), SynthesisError> {
+    ro.absorb(&self.comm_W.x);
+    ro.absorb(&self.comm_W.y);
+    ro.absorb(&self.comm_W.is_infinity);
+    for (i, x) in self.X.iter().enumerate() {
+      absorb_bignat_in_ro(cs.namespace(|| format!("absorb X[{i}]")), x, ro)?;
+    }
+    Ok(())
+  }
+}
+
+/// An allocated relaxed R1CS instance of the CycleFold circuit, whose `u` and public IO are non-native
+#[derive(Clone)]
+pub struct AllocatedRelaxedCycleFoldInstance<E: Engine> {
+  pub(crate) W: AllocatedPoint<E>,
+  pub(crate) E: AllocatedPoint<E>,
+  pub(crate) u: BigNat<E::Base>,
+  pub(crate) X: Vec<BigNat<E::Base>>,
+}
+
+impl<E: Engine> AllocatedRelaxedCycleFoldInstance<E> {
+  /// Allocates the given `RelaxedR1CSInstance` with `num_io` public IO as a witness of the circuit
+  pub fn alloc<CS: ConstraintSystem<E::Base>>(
+    mut cs: CS,
+    inst: Option<&RelaxedR1CSInstance<E>>,
+    num_io: usize,
+  ) -> Result<Self, SynthesisError> {
+    // As in Nova, we do not need to check that W or E are well-formed (e.g., on the curve)
+    // since the primary circuit checks that the running instance hashes to its input
+    let W = AllocatedPoint::alloc(
+      cs.namespace(|| "allocate W"),
+      inst.map(|inst| inst.comm_W.to_coordinates()),
+    )?;
+
+    let E = AllocatedPoint::alloc(
+      cs.namespace(|| "allocate E"),
+      inst.map(|inst| inst.comm_E.to_coordinates()),
+    )?;
+
+    let u = BigNat::alloc_from_nat(
+      cs.namespace(|| "allocate u"),
+      || Ok(f_to_nat(&inst.map_or(E::Scalar::ZERO, |inst| inst.u))),
+      BN_LIMB_WIDTH,
+      BN_N_LIMBS,
+    )?;
+
+    let X = (0..num_io)
+      .map(|i| {
+        BigNat::alloc_from_nat(
+          cs.namespace(|| format!("allocate X[{i}]")),
+          || Ok(f_to_nat(&inst.map_or(E::Scalar::ZERO, |inst| inst.X[i]))),
+          BN_LIMB_WIDTH,
+          BN_N_LIMBS,
+        )
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Self { W, E, u, X })
+  }
+
+  /// Allocates the default `RelaxedR1CSInstance` with `num_io` public IO as constants of the circuit.
+  /// W = E = 0, u = 0, X = 0
+  pub fn default<CS: ConstraintSystem<E::Base>>(
+    mut cs: CS,
+    num_io: usize,
+  ) -> Result<Self, SynthesisError> {
+    let W = AllocatedPoint::default(cs.namespace(|| "allocate W"))?;
+    let E = W.clone();
+
+    let u = alloc_bignat_constant(
+      cs.namespace(|| "allocate u_default"),
+      &BigInt::from(0),
+      BN_LIMB_WIDTH,
+      BN_N_LIMBS,
+    )?;
+    let X = vec![u.clone(); num_io];
+
+    Ok(Self { W, E, u, X })
+  }
+
+  /// Absorb the provided instance in the RO.
+  /// Both `u` and `X` are absorbed as limbs, so the hash binds their non-native values.
+  pub fn absorb_in_ro<CS: ConstraintSystem<E::Base>>(
+    &self,
+    mut cs: CS,
+    ro: &mut E::ROCircuit,
+  ) -> Result<(), SynthesisError> {
+    ro.absorb(&self.W.x);
+    ro.absorb(&self.W.y);
+    ro.absorb(&self.W.is_infinity);
+    ro.absorb(&self.E.x);
+    ro.absorb(&self.E.y);
+    ro.absorb(&self.E.is_infinity);
+
+    for (i, n) in [&self.u].into_iter().chain(self.X.iter()).enumerate() {
+      absorb_bignat_in_ro(
+        cs.namespace(|| format!("absorb non-native element {i}")),
+        n,
+        ro,
+      )?;
+    }
+
+    Ok(())
+  }
+
+  /// Folds self with an instance of the CycleFold circuit and returns the result.
+  /// As in Nova, the challenge does not absorb `self`, which must be bound to `u` by the caller.
+  pub fn fold_with_cyclefold_instance<CS: ConstraintSystem<E::Base>>(
+    &self,
+    mut cs: CS,
+    params: &AllocatedNum<E::Base>,
+    u: &AllocatedCycleFoldInstance<E>,
+    T: &AllocatedPoint<E>,
+    ro_consts: ROConstantsCircuit<E>,
+  ) -> Result<Self, SynthesisError> {
+    // Compute r:
+    let mut ro = E::ROCircuit::new(ro_consts);
+    ro.absorb(params);
+    u.absorb_in_ro(cs.namespace(|| "absorb u"), &mut ro)?;
+    ro.absorb(&T.x);
+    ro.absorb(&T.y);
+    ro.absorb(&T.is_infinity);
+    let r_bits = ro.squeeze(cs.namespace(|| "r bits"), NUM_CHALLENGE_BITS)?;
+    let r = le_bits_to_num(cs.namespace(|| "r"), &r_bits)?;
+
+    // W_fold = self.W + r * u.W
+    let rW = u.comm_W.scalar_mul(cs.namespace(|| "r * u.W"), &r_bits)?;
+    let W_fold = self.W.add(cs.namespace(|| "self.W + r * u.W"), &rW)?;
+
+    // E_fold = self.E + r * T
+    let rT = T.scalar_mul(cs.namespace(|| "r * T"), &r_bits)?;
+    let E_fold = self.E.add(cs.namespace(|| "self.E + r * T"), &rT)?;
+
+    let r_bn = BigNat::from_num(
+      cs.namespace(|| "allocate r_bn"),
+      &Num::from(r),
+      BN_LIMB_WIDTH,
+      BN_N_LIMBS,
+    )?;
+    let m_bn = alloc_bignat_constant(
+      cs.namespace(|| "alloc m"),
+      &E::GE::group_params().2,
+      BN_LIMB_WIDTH,
+      BN_N_LIMBS,
+    )?;
+
+    // u_fold = self.u + r
+    let u_fold = self
+      .u
+      .add(&r_bn)?
+      .red_mod(cs.namespace(|| "reduce folded u"), &m_bn)?;
+
+    // X_fold = self.X + r * u.X
+    let X_fold = self
+      .X
+      .iter()
+      .zip(u.X.iter())
+      .enumerate()
+      .map(|(i, (X_r, x))| {
+        let (_, r_x) = x.mult_mod(cs.namespace(|| format!("r * X[{i}]")), &r_bn, &m_bn)?;
+        X_r
+          .add(&r_x)?
+          .red_mod(cs.namespace(|| format!("reduce folded X[{i}]")), &m_bn)
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Self {
+      W: W_fold,
+      E: E_fold,
+      u: u_fold,
+      X: X_fold,
+    })
+  }
+
+  /// If the condition is true then returns this otherwise it returns the other
+  pub fn conditionally_select<CS: ConstraintSystem<E::Base>>(
+    &self,
+    mut cs: CS,
+    other: &Self,
+    condition: &Boolean,
+  ) -> Result<Self, SynthesisError> {
+    let W = AllocatedPoint::conditionally_select(
+      cs.namespace(|| "W = cond ? self.W : other.W"),
+      &self.W,
+      &other.W,
+      condition,
+    )?;
+
+    let E = AllocatedPoint::conditionally_select(
+      cs.namespace(|| "E = cond ? self.E : other.E"),
+      &self.E,
+      &other.E,
+      condition,
+    )?;
+
+    let u = conditionally_select_bignat(
+      cs.namespace(|| "u = cond ? self.u : other.u"),
+      &self.u,
+      &other.u,
+      condition,
+    )?;
+
+    let X = self
+      .X
+      .iter()
+      .zip(other.X.iter())
+      .enumerate()
+      .map(|(i, (a, b))| {
+        conditionally_select_bignat(
+          cs.namespace(|| format!("X[{i}] = cond ? self.X[{i}] : other.X[{i}]")),
+          a,
+          b,
+          condition,
+        )
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Self { W, E, u, X })
+  }
+}
+
+/// Allocates a coordinate of a point on the curve of `E` as a well-formed `BigNat`
+fn alloc_coordinate<E: Engine, CS: ConstraintSystem<E::Scalar>>(
+  mut cs: CS,
+  c: Option<E::Base>,
+) -> Result<BigNat<E::Scalar>, SynthesisError> {
+  let c = BigNat::alloc_from_nat(
+    cs.namespace(|| "alloc"),
+    || Ok(f_to_nat(&c.unwrap_or(E::Base::ZERO))),
+    BN_LIMB_WIDTH,
+    BN_N_LIMBS,
+  )?;
+  c.assert_well_formed(cs.namespace(|| "check well formed"))?;
+  Ok(c)
+}
+
+/// Absorbs the limbs of a `BigNat` in the RO
+fn absorb_bignat_in_ro<F, CS, RO>(
+  mut cs: CS,
+  n: &BigNat<F>,
+  ro: &mut RO,
+) -> Result<(), SynthesisError>
+where
+  F: ff::PrimeField,
+  CS: ConstraintSystem<F>,
+  RO: ROCircuitTrait<F>,
+{
+  for (i, limb) in n.as_limbs().iter().enumerate() {
+    let limb = limb.as_allocated_num(cs.namespace(|| format!("convert limb {i} to num")))?;
+    ro.absorb(&limb);
+  }
+  Ok(())
+}
@@ -0,0 +1,1026 @@
+//! This module implements a variant of Nova's IVC scheme based on CycleFold.
+//!
+//! In Nova, the secondary circuit is a full augmented circuit that folds instances of the primary
+//! circuit, including their non-native public IO. With CycleFold, the secondary circuit only
+//! performs the scalar multiplications needed to fold the commitments of the primary circuit's
+//! instances, and the primary circuit folds the instances of that small circuit.
+//! This reduces the recursion overhead and makes the secondary proof cheaper to compress.
+//!
+//! The primary circuit folds the last instance of itself into its running instance in every step,
+//! taking the folded commitments as advice. They are checked by an instance of the CycleFold
+//! circuit, which the primary circuit folds into a running CycleFold instance in the same step.
+
+use crate::{
+  constants::NUM_HASH_BITS,
+  digest::{DigestComputer, SimpleDigestible},
+  errors::NovaError,
+  frontend::{
+    r1cs::{NovaShape, NovaWitness},
+    shape_cs::ShapeCS,
+    solver::SatisfyingAssignment,
+    ConstraintSystem, SynthesisError,
+  },
+  gadgets::utils::scalar_as_base,
+  nova::nifs::{NIFSRelaxed, NIFS},
+  r1cs::{
+    CommitmentKeyHint, R1CSInstance, R1CSShape, R1CSWitness, RelaxedR1CSInstance,
+    RelaxedR1CSWitness,
+  },
+  traits::{
+    circuit::StepCircuit, commitment::CommitmentEngineTrait, snark::RelaxedR1CSSNARKTrait, Engine,
+    ROConstants, ROConstantsCircuit, ROTrait,
+  },
+  CommitmentKey, DerandKey,
+};
+use core::marker::PhantomData;
+use ff::Field;
+use once_cell::sync::OnceCell;
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+
+mod circuit;
+mod nifs;
+
+use circuit::{
+  ecc::{CycleFoldCircuit, CycleFoldInputs, NUM_IO_CYCLEFOLD},
+  CycleFoldAugmentedCircuit, CycleFoldAugmentedCircuitInputs, NUM_IO_PRIMARY,
+};
+use nifs::{absorb_cyclefold_relaxed_instance, absorb_primary_relaxed_instance};
+
+/// A type that holds public parameters of the CycleFold variant of Nova
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct PublicParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  F_arity: usize,
+
+  ro_consts_primary: ROConstants<E1>,
+  ro_consts_secondary: ROConstants<E2>,
+  ro_consts_circuit_primary: ROConstantsCircuit<E2>,
+
+  ck_primary: CommitmentKey<E1>,
+  r1cs_shape_primary: R1CSShape<E1>,
+
+  ck_cyclefold: CommitmentKey<E2>,
+  r1cs_shape_cyclefold: R1CSShape<E2>,
+
+  #[serde(skip, default = "OnceCell::new")]
+  digest: OnceCell<E1::Scalar>,
+  _p: PhantomData<C>,
+}
+
+impl<E1, E2, C> SimpleDigestible for PublicParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+}
+
+impl<E1, E2, C> PublicParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Creates a new `PublicParams` for a circuit `C`.
+  ///
+  /// The commitment key hints are used as in `nova::PublicParams::setup`:
+  /// `ck_hint1` is for the primary circuit and `ck_hint2` is for the CycleFold circuit.
+  pub fn setup(
+    c: &C,
+    ck_hint1: &CommitmentKeyHint<E1>,
+    ck_hint2: &CommitmentKeyHint<E2>,
+  ) -> Result<Self, NovaError> {
+    let ro_consts_primary: ROConstants<E1> = ROConstants::<E1>::default();
+    let ro_consts_secondary: ROConstants<E2> = ROConstants::<E2>::default();
+
+    let F_arity = c.arity();
+
+    // ro_consts_circuit_primary are parameterized by E2 because the type alias uses E2::Base = E1::Scalar
+    let ro_consts_circuit_primary: ROConstantsCircuit<E2> = ROConstantsCircuit::<E2>::default();
+
+    // Initialize ck for the primary
+    let circuit_primary: CycleFoldAugmentedCircuit<'_, E1, E2, C> =
+      CycleFoldAugmentedCircuit::new(None, c, ro_consts_circuit_primary.clone());
+    let mut cs: ShapeCS<E1> = ShapeCS::new();
+    let _ = circuit_primary.synthesize(&mut cs);
+    let (r1cs_shape_primary, ck_primary) = cs.r1cs_shape(ck_hint1);
+
+    // Initialize ck for the CycleFold circuit
+    let circuit_cyclefold: CycleFoldCircuit<E1> = CycleFoldCircuit::new(None);
+    let mut cs: ShapeCS<E2> = ShapeCS::new();
+    let _ = circuit_cyclefold.synthesize(&mut cs);
+    let (r1cs_shape_cyclefold, ck_cyclefold) = cs.r1cs_shape(ck_hint2);
+
+    if r1cs_shape_primary.num_io != NUM_IO_PRIMARY
+      || r1cs_shape_cyclefold.num_io != NUM_IO_CYCLEFOLD
+    {
+      return Err(NovaError::InvalidStepCircuitIO);
+    }
+
+    let pp = PublicParams {
+      F_arity,
+
+      ro_consts_primary,
+      ro_consts_secondary,
+      ro_consts_circuit_primary,
+
+      ck_primary,
+      r1cs_shape_primary,
+
+      ck_cyclefold,
+      r1cs_shape_cyclefold,
+
+      digest: OnceCell::new(),
+      _p: Default::default(),
+    };
+
+    // call pp.digest() so the digest is computed here rather than in RecursiveSNARK methods
+    let _ = pp.digest();
+
+    Ok(pp)
+  }
+
+  /// Retrieve the digest of the public parameters.
+  pub fn digest(&self) -> E1::Scalar {
+    self
+      .digest
+      .get_or_try_init(|| DigestComputer::new(self).digest())
+      .cloned()
+      .expect("Failure in retrieving digest")
+  }
+
+  /// Returns the number of constraints in the primary and CycleFold circuits
+  pub const fn num_constraints(&self) -> (usize, usize) {
+    (
+      self.r1cs_shape_primary.num_cons,
+      self.r1cs_shape_cyclefold.num_cons,
+    )
+  }
+
+  /// Returns the number of variables in the primary and CycleFold circuits
+  pub const fn num_variables(&self) -> (usize, usize) {
+    (
+      self.r1cs_shape_primary.num_vars,
+      self.r1cs_shape_cyclefold.num_vars,
+    )
+  }
+}
+
+/// Computes the hash H(params, i, z0, zi, U, U_cyclefold, ri) that the primary circuit outputs
+fn hash_state<E1, E2>(
+  ro_consts: &ROConstants<E2>,
+  pp_digest: E1::Scalar,
+  i: usize,
+  z0: &[E1::Scalar],
+  zi: &[E1::Scalar],
+  U: &RelaxedR1CSInstance<E1>,
+  U_cyclefold: &RelaxedR1CSInstance<E2>,
+  ri: E1::Scalar,
+) -> E1::Scalar
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+{
+  let mut hasher = <E2 as Engine>::RO::new(ro_consts.clone());
+  hasher.absorb(pp_digest);
+  hasher.absorb(E1::Scalar::from(i as u64));
+  for e in z0.iter().chain(zi) {
+    hasher.absorb(*e);
+  }
+  absorb_primary_relaxed_instance::<E1, E2>(U, &mut hasher);
+  absorb_cyclefold_relaxed_instance(U_cyclefold, &mut hasher);
+  hasher.absorb(ri);
+  hasher.squeeze(NUM_HASH_BITS)
+}
+
+/// A SNARK that proves the correct execution of an incremental computation
+/// using the CycleFold variant of Nova
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct RecursiveSNARK<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  z0: Vec<E1::Scalar>,
+
+  r_W_primary: RelaxedR1CSWitness<E1>,
+  r_U_primary: RelaxedR1CSInstance<E1>,
+  l_w_primary: R1CSWitness<E1>,
+  l_u_primary: R1CSInstance<E1>,
+  ri_primary: E1::Scalar,
+
+  r_W_cyclefold: RelaxedR1CSWitness<E2>,
+  r_U_cyclefold: RelaxedR1CSInstance<E2>,
+
+  i: usize,
+
+  zi: Vec<E1::Scalar>,
+
+  _p: PhantomData<C>,
+}
+
+impl<E1, E2, C> RecursiveSNARK<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Create new instance of recursive SNARK
+  pub fn new(pp: &PublicParams<E1, E2, C>, c: &C, z0: &[E1::Scalar]) -> Result<Self, NovaError> {
+    if z0.len() != pp.F_arity {
+      return Err(NovaError::InvalidInitialInputLength);
+    }
+
+    let ri_primary = E1::Scalar::random(&mut OsRng);
+
+    // base case for the primary
+    let mut cs_primary = SatisfyingAssignment::<E1>::new();
+    let inputs_primary: CycleFoldAugmentedCircuitInputs<E1, E2> =
+      CycleFoldAugmentedCircuitInputs::new(
+        pp.digest(),
+        E1::Scalar::ZERO,
+        z0.to_vec(),
+        None,
+        None,
+        None,
+        ri_primary, // "r next"
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+      );
+
+    let circuit_primary: CycleFoldAugmentedCircuit<'_, E1, E2, C> = CycleFoldAugmentedCircuit::new(
+      Some(inputs_primary),
+      c,
+      pp.ro_consts_circuit_primary.clone(),
+    );
+    let zi_primary = circuit_primary.synthesize(&mut cs_primary)?;
+    let (l_u_primary, l_w_primary) =
+      cs_primary.r1cs_instance_and_witness(&pp.r1cs_shape_primary, &pp.ck_primary)?;
+
+    // the running instances start as the default instances
+    let r_W_primary = RelaxedR1CSWitness::default(&pp.r1cs_shape_primary);
+    let r_U_primary = RelaxedR1CSInstance::default(&pp.ck_primary, &pp.r1cs_shape_primary);
+    let r_W_cyclefold = RelaxedR1CSWitness::default(&pp.r1cs_shape_cyclefold);
+    let r_U_cyclefold = RelaxedR1CSInstance::default(&pp.ck_cyclefold, &pp.r1cs_shape_cyclefold);
+
+    if zi_primary.len() != pp.F_arity {
+      return Err(NovaError::InvalidStepOutputLength);
+    }
+
+    let zi_primary = zi_primary
+      .iter()
+      .map(|v| v.get_value().ok_or(SynthesisError::AssignmentMissing))
+      .collect::<Result<Vec<<E1 as Engine>::Scalar>, _>>()?;
+
+    Ok(Self {
+      z0: z0.to_vec(),
+
+      r_W_primary,
+      r_U_primary,
+      l_w_primary,
+      l_u_primary,
+      ri_primary,
+
+      r_W_cyclefold,
+      r_U_cyclefold,
+
+      i: 0,
+
+      zi: zi_primary,
+
+      _p: Default::default(),
+    })
+  }
+
+  /// Updates the provided `RecursiveSNARK` by executing a step of the incremental computation
+  pub fn prove_step(&mut self, pp: &PublicParams<E1, E2, C>, c: &C) -> Result<(), NovaError> {
+    // first step was already done in the constructor
+    if self.i == 0 {
+      self.i = 1;
+      return Ok(());
+    }
+
+    // fold the last instance of the primary circuit into its running instance
+    let (comm_T, (r_U_primary, r_W_primary), r) = nifs::prove_primary::<E1, E2>(
+      &pp.ck_primary,
+      &pp.ro_consts_secondary,
+      &pp.digest(),
+      &pp.r1cs_shape_primary,
+      &self.r_U_primary,
+      &self.r_W_primary,
+      &self.l_u_primary,
+      &self.l_w_primary,
+    )?;
+
+    // prove the folding of the commitments with the CycleFold circuit
+    let mut cs_cyclefold = SatisfyingAssignment::<E2>::new();
+    let inputs_cyclefold = CycleFoldInputs::new(
+      self.r_U_primary.comm_W,
+      self.l_u_primary.comm_W,
+      self.r_U_primary.comm_E,
+      comm_T,
+      r,
+    );
+    let circuit_cyclefold: CycleFoldCircuit<E1> = CycleFoldCircuit::new(Some(inputs_cyclefold));
+    circuit_cyclefold.synthesize(&mut cs_cyclefold)?;
+    let (l_u_cyclefold, l_w_cyclefold) = cs_cyclefold
+      .r1cs_instance_and_witness(&pp.r1cs_shape_cyclefold, &pp.ck_cyclefold)
+      .map_err(|_e| NovaError::UnSat {
+        reason: "Unable to generate a satisfying witness for the CycleFold circuit".to_string(),
+      })?;
+
+    // fold the instance of the CycleFold circuit into the running CycleFold instance
+    let (comm_T_cyclefold, (r_U_cyclefold, r_W_cyclefold)) = nifs::prove_cyclefold::<E1, E2>(
+      &pp.ck_cyclefold,
+      &pp.ro_consts_secondary,
+      &pp.digest(),
+      &pp.r1cs_shape_cyclefold,
+      &self.r_U_cyclefold,
+      &self.r_W_cyclefold,
+      &l_u_cyclefold,
+      &l_w_cyclefold,
+    )?;
+
+    let r_next_primary = E1::Scalar::random(&mut OsRng);
+
+    let mut cs_primary = SatisfyingAssignment::<E1>::new();
+    let inputs_primary: CycleFoldAugmentedCircuitInputs<E1, E2> =
+      CycleFoldAugmentedCircuitInputs::new(
+        pp.digest(),
+        E1::Scalar::from(self.i as u64),
+        self.z0.to_vec(),
+        Some(self.zi.clone()),
+        Some(self.r_U_primary.clone()),
+        Some(self.ri_primary),
+        r_next_primary,
+        Some(self.l_u_primary.clone()),
+        Some(comm_T),
+        Some(r_U_primary.clone()),
+        Some(self.r_U_cyclefold.clone()),
+        Some(l_u_cyclefold),
+        Some(comm_T_cyclefold),
+      );
+
+    let circuit_primary: CycleFoldAugmentedCircuit<'_, E1, E2, C> = CycleFoldAugmentedCircuit::new(
+      Some(inputs_primary),
+      c,
+      pp.ro_consts_circuit_primary.clone(),
+    );
+    let zi_primary = circuit_primary.synthesize(&mut cs_primary)?;
+
+    let (l_u_primary, l_w_primary) = cs_primary
+      .r1cs_instance_and_witness(&pp.r1cs_shape_primary, &pp.ck_primary)
+      .map_err(|_e| NovaError::UnSat {
+        reason: "Unable to generate a satisfying witness on the primary curve".to_string(),
+      })?;
+
+    // update the running instances and witnesses
+    self.zi = zi_primary
+      .iter()
+      .map(|v| v.get_value().ok_or(SynthesisError::AssignmentMissing))
+      .collect::<Result<Vec<<E1 as Engine>::Scalar>, _>>()?;
+
+    self.l_u_primary = l_u_primary;
+    self.l_w_primary = l_w_primary;
+
+    self.r_U_primary = r_U_primary;
+    self.r_W_primary = r_W_primary;
+
+    self.r_U_cyclefold = r_U_cyclefold;
+    self.r_W_cyclefold = r_W_cyclefold;
+
+    self.ri_primary = r_next_primary;
+
+    self.i += 1;
+
+    Ok(())
+  }
+
+  /// Verify the correctness of the `RecursiveSNARK`
+  pub fn verify(
+    &self,
+    pp: &PublicParams<E1, E2, C>,
+    num_steps: usize,
+    z0: &[E1::Scalar],
+  ) -> Result<Vec<E1::Scalar>, NovaError> {
+    // number of steps cannot be zero
+    let is_num_steps_zero = num_steps == 0;
+
+    // check if the provided proof has executed num_steps
+    let is_num_steps_not_match = self.i != num_steps;
+
+    // check if the initial inputs match
+    let is_inputs_not_match = self.z0 != z0;
+
+    // check if the (relaxed) R1CS instances have the expected number of public outputs
+    let is_instance_io_not_match = self.l_u_primary.X.len() != NUM_IO_PRIMARY
+      || self.r_U_primary.X.len() != NUM_IO_PRIMARY
+      || self.r_U_cyclefold.X.len() != NUM_IO_CYCLEFOLD;
+
+    if is_num_steps_zero
+      || is_num_steps_not_match
+      || is_inputs_not_match
+      || is_instance_io_not_match
+    {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Invalid number of steps or inputs".to_string(),
+      });
+    }
+
+    // check if the output hash in the R1CS instance points to the right running instances
+    let hash = hash_state(
+      &pp.ro_consts_secondary,
+      pp.digest(),
+      num_steps,
+      z0,
+      &self.zi,
+      &self.r_U_primary,
+      &self.r_U_cyclefold,
+      self.ri_primary,
+    );
+
+    if hash != self.l_u_primary.X[0] {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Invalid output hash in R1CS instances".to_string(),
+      });
+    }
+
+    // check the satisfiability of the provided instances
+    let (res_r_primary, (res_l_primary, res_r_cyclefold)) = rayon::join(
+      || {
+        pp.r1cs_shape_primary
+          .is_sat_relaxed(&pp.ck_primary, &self.r_U_primary, &self.r_W_primary)
+      },
+      || {
+        rayon::join(
+          || {
+            pp.r1cs_shape_primary
+              .is_sat(&pp.ck_primary, &self.l_u_primary, &self.l_w_primary)
+          },
+          || {
+            pp.r1cs_shape_cyclefold.is_sat_relaxed(
+              &pp.ck_cyclefold,
+              &self.r_U_cyclefold,
+              &self.r_W_cyclefold,
+            )
+          },
+        )
+      },
+    );
+
+    // check the returned res objects
+    res_r_primary?;
+    res_l_primary?;
+    res_r_cyclefold?;
+
+    Ok(self.zi.clone())
+  }
+
+  /// Get the outputs after the last step of computation.
+  pub fn outputs(&self) -> &[E1::Scalar] {
+    &self.zi
+  }
+
+  /// The number of steps which have been executed thus far.
+  pub fn num_steps(&self) -> usize {
+    self.i
+  }
+}
+
+/// A type that holds the prover key for `CompressedSNARK`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ProverKey<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  pk_primary: S1::ProverKey,
+  pk_cyclefold: S2::ProverKey,
+  _p: PhantomData<C>,
+}
+
+/// A type that holds the verifier key for `CompressedSNARK`
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct VerifierKey<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  F_arity: usize,
+  ro_consts_primary: ROConstants<E1>,
+  ro_consts_secondary: ROConstants<E2>,
+  pp_digest: E1::Scalar,
+  vk_primary: S1::VerifierKey,
+  vk_cyclefold: S2::VerifierKey,
+  dk_primary: DerandKey<E1>,
+  dk_cyclefold: DerandKey<E2>,
+  _p: PhantomData<C>,
+}
+
+/// A SNARK that proves the knowledge of a valid `RecursiveSNARK`
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct CompressedSNARK<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  r_U_primary: RelaxedR1CSInstance<E1>,
+  ri_primary: E1::Scalar,
+  l_u_primary: R1CSInstance<E1>,
+  nifs_Uf_primary: NIFS<E1>,
+
+  l_ur_primary: RelaxedR1CSInstance<E1>,
+  nifs_Un_primary: NIFSRelaxed<E1>,
+
+  r_U_cyclefold: RelaxedR1CSInstance<E2>,
+  l_ur_cyclefold: RelaxedR1CSInstance<E2>,
+  nifs_Un_cyclefold: NIFSRelaxed<E2>,
+
+  wit_blind_r_Wn_primary: E1::Scalar,
+  err_blind_r_Wn_primary: E1::Scalar,
+  wit_blind_r_Wn_cyclefold: E2::Scalar,
+  err_blind_r_Wn_cyclefold: E2::Scalar,
+
+  snark_primary: S1,
+  snark_cyclefold: S2,
+
+  zn: Vec<E1::Scalar>,
+
+  _p: PhantomData<C>,
+}
+
+impl<E1, E2, C, S1, S2> CompressedSNARK<E1, E2, C, S1, S2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S1: RelaxedR1CSSNARKTrait<E1>,
+  S2: RelaxedR1CSSNARKTrait<E2>,
+{
+  /// Creates prover and verifier keys for `CompressedSNARK`
+  pub fn setup(
+    pp: &PublicParams<E1, E2, C>,
+  ) -> Result<(ProverKey<E1, E2, C, S1, S2>, VerifierKey<E1, E2, C, S1, S2>), NovaError> {
+    let (pk_primary, vk_primary) = S1::setup(&pp.ck_primary, &pp.r1cs_shape_primary)?;
+    let (pk_cyclefold, vk_cyclefold) = S2::setup(&pp.ck_cyclefold, &pp.r1cs_shape_cyclefold)?;
+
+    let pk = ProverKey {
+      pk_primary,
+      pk_cyclefold,
+      _p: Default::default(),
+    };
+
+    let vk = VerifierKey {
+      F_arity: pp.F_arity,
+      ro_consts_primary: pp.ro_consts_primary.clone(),
+      ro_consts_secondary: pp.ro_consts_secondary.clone(),
+      pp_digest: pp.digest(),
+      vk_primary,
+      vk_cyclefold,
+      dk_primary: E1::CE::derand_key(&pp.ck_primary),
+      dk_cyclefold: E2::CE::derand_key(&pp.ck_cyclefold),
+      _p: Default::default(),
+    };
+
+    Ok((pk, vk))
+  }
+
+  /// Create a new `CompressedSNARK` (provides zero-knowledge)
+  pub fn prove(
+    pp: &PublicParams<E1, E2, C>,
+    pk: &ProverKey<E1, E2, C, S1, S2>,
+    recursive_snark: &RecursiveSNARK<E1, E2, C>,
+  ) -> Result<Self, NovaError> {
+    // prove three foldings
+
+    // fold primary U/W with primary u/w to get Uf/Wf
+    let (nifs_Uf_primary, (r_Uf_primary, r_Wf_primary)) = NIFS::prove(
+      &pp.ck_primary,
+      &pp.ro_consts_primary,
+      &pp.digest(),
+      &pp.r1cs_shape_primary,
+      &recursive_snark.r_U_primary,
+      &recursive_snark.r_W_primary,
+      &recursive_snark.l_u_primary,
+      &recursive_snark.l_w_primary,
+    )?;
+
+    // fold Uf/Wf with random inst/wit to get U1/W1
+    let (l_ur_primary, l_wr_primary) = pp
+      .r1cs_shape_primary
+      .sample_random_instance_witness(&pp.ck_primary)?;
+
+    let (nifs_Un_primary, (r_Un_primary, r_Wn_primary)) = NIFSRelaxed::prove(
+      &pp.ck_primary,
+      &pp.ro_consts_primary,
+      &pp.digest(),
+      &pp.r1cs_shape_primary,
+      &r_Uf_primary,
+      &r_Wf_primary,
+      &l_ur_primary,
+      &l_wr_primary,
+    )?;
+
+    // fold CycleFold U/W with random inst/wit to get U2/W2
+    let (l_ur_cyclefold, l_wr_cyclefold) = pp
+      .r1cs_shape_cyclefold
+      .sample_random_instance_witness(&pp.ck_cyclefold)?;
+
+    let (nifs_Un_cyclefold, (r_Un_cyclefold, r_Wn_cyclefold)) = NIFSRelaxed::prove(
+      &pp.ck_cyclefold,
+      &pp.ro_consts_secondary,
+      &scalar_as_base::<E1>(pp.digest()),
+      &pp.r1cs_shape_cyclefold,
+      &recursive_snark.r_U_cyclefold,
+      &recursive_snark.r_W_cyclefold,
+      &l_ur_cyclefold,
+      &l_wr_cyclefold,
+    )?;
+
+    // derandomize/unblind commitments
+    let (derandom_r_Wn_primary, wit_blind_r_Wn_primary, err_blind_r_Wn_primary) =
+      r_Wn_primary.derandomize();
+    let derandom_r_Un_primary = r_Un_primary.derandomize(
+      &E1::CE::derand_key(&pp.ck_primary),
+      &wit_blind_r_Wn_primary,
+      &err_blind_r_Wn_primary,
+    );
+
+    let (derandom_r_Wn_cyclefold, wit_blind_r_Wn_cyclefold, err_blind_r_Wn_cyclefold) =
+      r_Wn_cyclefold.derandomize();
+    let derandom_r_Un_cyclefold = r_Un_cyclefold.derandomize(
+      &E2::CE::derand_key(&pp.ck_cyclefold),
+      &wit_blind_r_Wn_cyclefold,
+      &err_blind_r_Wn_cyclefold,
+    );
+
+    // create SNARKs proving the knowledge of Wn primary/CycleFold
+    let (snark_primary, snark_cyclefold) = rayon::join(
+      || {
+        S1::prove(
+          &pp.ck_primary,
+          &pk.pk_primary,
+          &pp.r1cs_shape_primary,
+          &derandom_r_Un_primary,
+          &derandom_r_Wn_primary,
+        )
+      },
+      || {
+        S2::prove(
+          &pp.ck_cyclefold,
+          &pk.pk_cyclefold,
+          &pp.r1cs_shape_cyclefold,
+          &derandom_r_Un_cyclefold,
+          &derandom_r_Wn_cyclefold,
+        )
+      },
+    );
+
+    Ok(Self {
+      r_U_primary: recursive_snark.r_U_primary.clone(),
+      ri_primary: recursive_snark.ri_primary,
+      l_u_primary: recursive_snark.l_u_primary.clone(),
+      nifs_Uf_primary,
+
+      l_ur_primary,
+      nifs_Un_primary,
+
+      r_U_cyclefold: recursive_snark.r_U_cyclefold.clone(),
+      l_ur_cyclefold,
+      nifs_Un_cyclefold,
+
+      wit_blind_r_Wn_primary,
+      err_blind_r_Wn_primary,
+      wit_blind_r_Wn_cyclefold,
+      err_blind_r_Wn_cyclefold,
+
+      snark_primary: snark_primary?,
+      snark_cyclefold: snark_cyclefold?,
+
+      zn: recursive_snark.zi.clone(),
+
+      _p: Default::default(),
+    })
+  }
+
+  /// Verify the correctness of the `CompressedSNARK` (provides zero-knowledge)
+  pub fn verify(
+    &self,
+    vk: &VerifierKey<E1, E2, C, S1, S2>,
+    num_steps: usize,
+    z0: &[E1::Scalar],
+  ) -> Result<Vec<E1::Scalar>, NovaError> {
+    // the number of steps cannot be zero
+    if num_steps == 0 {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Number of steps cannot be zero".to_string(),
+      });
+    }
+
+    // check the arity of the inputs and outputs
+    if z0.len() != vk.F_arity || self.zn.len() != vk.F_arity {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Invalid number of inputs or outputs".to_string(),
+      });
+    }
+
+    // check if the (relaxed) R1CS instances have the expected number of public outputs
+    if self.l_u_primary.X.len() != NUM_IO_PRIMARY
+      || self.r_U_primary.X.len() != NUM_IO_PRIMARY
+      || self.l_ur_primary.X.len() != NUM_IO_PRIMARY
+      || self.r_U_cyclefold.X.len() != NUM_IO_CYCLEFOLD
+      || self.l_ur_cyclefold.X.len() != NUM_IO_CYCLEFOLD
+    {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Invalid number of outputs in R1CS instances".to_string(),
+      });
+    }
+
+    // check if the output hash in the R1CS instance points to the right running instances
+    let hash = hash_state(
+      &vk.ro_consts_secondary,
+      vk.pp_digest,
+      num_steps,
+      z0,
+      &self.zn,
+      &self.r_U_primary,
+      &self.r_U_cyclefold,
+      self.ri_primary,
+    );
+
+    if hash != self.l_u_primary.X[0] {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Invalid output hash in R1CS instances".to_string(),
+      });
+    }
+
+    // fold primary U/W with primary u/w to get Uf/Wf
+    let r_Uf_primary = self.nifs_Uf_primary.verify(
+      &vk.ro_consts_primary,
+      &vk.pp_digest,
+      &self.r_U_primary,
+      &self.l_u_primary,
+    )?;
+
+    // fold Uf/Wf with random inst/wit to get U1/W1
+    let r_Un_primary = self.nifs_Un_primary.verify(
+      &vk.ro_consts_primary,
+      &vk.pp_digest,
+      &r_Uf_primary,
+      &self.l_ur_primary,
+    )?;
+
+    // fold CycleFold U/W with random inst/wit to get U2/W2
+    let r_Un_cyclefold = self.nifs_Un_cyclefold.verify(
+      &vk.ro_consts_secondary,
+      &scalar_as_base::<E1>(vk.pp_digest),
+      &self.r_U_cyclefold,
+      &self.l_ur_cyclefold,
+    )?;
+
+    // derandomize/unblind commitments
+    let derandom_r_Un_primary = r_Un_primary.derandomize(
+      &vk.dk_primary,
+      &self.wit_blind_r_Wn_primary,
+      &self.err_blind_r_Wn_primary,
+    );
+    let derandom_r_Un_cyclefold = r_Un_cyclefold.derandomize(
+      &vk.dk_cyclefold,
+      &self.wit_blind_r_Wn_cyclefold,
+      &self.err_blind_r_Wn_cyclefold,
+    );
+
+    // check the satisfiability of the folded instances using
+    // SNARKs proving the knowledge of their satisfying witnesses
+    let (res_primary, res_cyclefold) = rayon::join(
+      || {
+        self
+          .snark_primary
+          .verify(&vk.vk_primary, &derandom_r_Un_primary)
+      },
+      || {
+        self
+          .snark_cyclefold
+          .verify(&vk.vk_cyclefold, &derandom_r_Un_cyclefold)
+      },
+    );
+
+    res_primary?;
+    res_cyclefold?;
+
+    Ok(self.zn.clone())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    frontend::{num::AllocatedNum, ConstraintSystem, SynthesisError},
+    provider::{
+      Bn256EngineKZG, GrumpkinEngine, PallasEngine, Secp256k1Engine, Secq256k1Engine, VestaEngine,
+    },
+    traits::{circuit::TrivialCircuit, evaluation::EvaluationEngineTrait, snark::default_ck_hint},
+  };
+  use ff::PrimeField;
+
+  type EE<E> = crate::provider::ipa_pc::EvaluationEngine<E>;
+  type EEPrime<E> = crate::provider::hyperkzg::EvaluationEngine<E>;
+  type S<E, EE> = crate::spartan::snark::RelaxedR1CSSNARK<E, EE>;
+
+  #[derive(Clone, Debug, Default)]
+  struct CubicCircuit<F: PrimeField> {
+    _p: PhantomData<F>,
+  }
+
+  impl<F: PrimeField> StepCircuit<F> for CubicCircuit<F> {
+    fn arity(&self) -> usize {
+      1
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(
+      &self,
+      cs: &mut CS,
+      z: &[AllocatedNum<F>],
+    ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+      // Consider a cubic equation: `x^3 + x + 5 = y`, where `x` and `y` are respectively the input and output.
+      let x = &z[0];
+      let x_sq = x.square(cs.namespace(|| "x_sq"))?;
+      let x_cu = x_sq.mul(cs.namespace(|| "x_cu"), x)?;
+      let y = AllocatedNum::alloc(cs.namespace(|| "y"), || {
+        Ok(x_cu.get_value().unwrap() + x.get_value().unwrap() + F::from(5u64))
+      })?;
+
+      cs.enforce(
+        || "y = x^3 + x + 5",
+        |lc| {
+          lc + x_cu.get_variable()
+            + x.get_variable()
+            + CS::one()
+            + CS::one()
+            + CS::one()
+            + CS::one()
+            + CS::one()
+        },
+        |lc| lc + CS::one(),
+        |lc| lc + y.get_variable(),
+      );
+
+      Ok(vec![y])
+    }
+  }
+
+  impl<F: PrimeField> CubicCircuit<F> {
+    fn output(&self, z: &[F]) -> Vec<F> {
+      vec![z[0] * z[0] * z[0] + z[0] + F::from(5u64)]
+    }
+  }
+
+  fn test_ivc_trivial_with<E1, E2>()
+  where
+    E1: Engine<Base = <E2 as Engine>::Scalar>,
+    E2: Engine<Base = <E1 as Engine>::Scalar>,
+  {
+    let test_circuit = TrivialCircuit::<<E1 as Engine>::Scalar>::default();
+
+    // produce public parameters
+    let pp = PublicParams::<E1, E2, TrivialCircuit<<E1 as Engine>::Scalar>>::setup(
+      &test_circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+
+    // the CycleFold circuit is much smaller than the primary circuit
+    let (num_cons_primary, num_cons_cyclefold) = pp.num_constraints();
+    assert!(num_cons_cyclefold < num_cons_primary);
+
+    // produce a recursive SNARK
+    let mut recursive_snark =
+      RecursiveSNARK::new(&pp, &test_circuit, &[<E1 as Engine>::Scalar::ZERO]).unwrap();
+
+    let res = recursive_snark.prove_step(&pp, &test_circuit);
+    assert!(res.is_ok());
+
+    // verify the recursive SNARK
+    let res = recursive_snark.verify(&pp, 1, &[<E1 as Engine>::Scalar::ZERO]);
+    assert!(res.is_ok());
+  }
+
+  #[test]
+  fn test_ivc_trivial() {
+    test_ivc_trivial_with::<PallasEngine, VestaEngine>();
+    test_ivc_trivial_with::<Bn256EngineKZG, GrumpkinEngine>();
+    test_ivc_trivial_with::<Secp256k1Engine, Secq256k1Engine>();
+  }
+
+  fn test_ivc_nontrivial_with_compression_with<E1, E2, EE1, EE2>()
+  where
+    E1: Engine<Base = <E2 as Engine>::Scalar>,
+    E2: Engine<Base = <E1 as Engine>::Scalar>,
+    EE1: EvaluationEngineTrait<E1>,
+    EE2: EvaluationEngineTrait<E2>,
+  {
+    let circuit = CubicCircuit::default();
+
+    // produce public parameters
+    let pp = PublicParams::<E1, E2, CubicCircuit<<E1 as Engine>::Scalar>>::setup(
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+
+    let num_steps = 3;
+
+    // produce a recursive SNARK
+    let mut recursive_snark = RecursiveSNARK::<E1, E2, CubicCircuit<<E1 as Engine>::Scalar>>::new(
+      &pp,
+      &circuit,
+      &[<E1 as Engine>::Scalar::ZERO],
+    )
+    .unwrap();
+
+    for i in 0..num_steps {
+      let res = recursive_snark.prove_step(&pp, &circuit);
+      assert!(res.is_ok());
+
+      // verify the recursive snark at each step of recursion
+      let res = recursive_snark.verify(&pp, i + 1, &[<E1 as Engine>::Scalar::ZERO]);
+      assert!(res.is_ok());
+    }
+
+    // verify the recursive SNARK
+    let res = recursive_snark.verify(&pp, num_steps, &[<E1 as Engine>::Scalar::ZERO]);
+    assert!(res.is_ok());
+
+    let zn = res.unwrap();
+
+    // sanity: check the claimed output with a direct computation of the same
+    let mut zn_direct = vec![<E1 as Engine>::Scalar::ZERO];
+    for _i in 0..num_steps {
+      zn_direct = circuit.clone().output(&zn_direct);
+    }
+    assert_eq!(zn, zn_direct);
+
+    // a recursive SNARK does not verify for a different number of steps or initial input
+    assert!(recursive_snark
+      .verify(&pp, num_steps + 1, &[<E1 as Engine>::Scalar::ZERO])
+      .is_err());
+    assert!(recursive_snark
+      .verify(&pp, num_steps, &[<E1 as Engine>::Scalar::ONE])
+      .is_err());
+
+    // produce the prover and verifier keys for compressed snark
+    let (pk, vk) = CompressedSNARK::<_, _, _, S<E1, EE1>, S<E2, EE2>>::setup(&pp).unwrap();
+
+    // produce a compressed SNARK
+    let res = CompressedSNARK::<_, _, _, S<E1, EE1>, S<E2, EE2>>::prove(&pp, &pk, &recursive_snark);
+    assert!(res.is_ok());
+    let compressed_snark = res.unwrap();
+
+    // verify the compressed SNARK
+    let res = compressed_snark.verify(&vk, num_steps, &[<E1 as Engine>::Scalar::ZERO]);
+    assert!(res.is_ok());
+    assert_eq!(res.unwrap(), zn_direct);
+
+    // a compressed SNARK with a tampered output does not verify
+    let mut compressed_snark = compressed_snark;
+    compressed_snark.zn[0] += <E1 as Engine>::Scalar::ONE;
+    assert!(compressed_snark
+      .verify(&vk, num_steps, &[<E1 as Engine>::Scalar::ZERO])
+      .is_err());
+  }
+
+  #[test]
+  fn test_ivc_nontrivial_with_compression() {
+    test_ivc_nontrivial_with_compression_with::<PallasEngine, VestaEngine, EE<_>, EE<_>>();
+    test_ivc_nontrivial_with_compression_with::<Bn256EngineKZG, GrumpkinEngine, EEPrime<_>, EE<_>>(
+    );
+    test_ivc_nontrivial_with_compression_with::<Secp256k1Engine, Secq256k1Engine, EE<_>, EE<_>>();
+  }
+}
@@ -0,0 +1,149 @@
+//! This module implements the non-interactive folding schemes used by the CycleFold variant of Nova.
+//!
+//! The challenges are computed exactly as the augmented circuit recomputes them: primary
+//! commitments are absorbed as the limbs of their coordinates, and the public IO of instances
+//! of the CycleFold circuit, which is non-native to the primary circuit, is absorbed as limbs.
+#![allow(non_snake_case)]
+use crate::{
+  constants::NUM_CHALLENGE_BITS,
+  errors::NovaError,
+  gadgets::utils::{base_as_scalar, to_bignat_repr},
+  r1cs::{R1CSInstance, R1CSShape, R1CSWitness, RelaxedR1CSInstance, RelaxedR1CSWitness},
+  traits::{commitment::CommitmentTrait, AbsorbInROTrait, Engine, ROConstants, ROTrait},
+  Commitment, CommitmentKey,
+};
+use ff::Field;
+use rand_core::OsRng;
+
+/// Absorbs a commitment on the primary curve as the limbs of its coordinates
+pub(crate) fn absorb_primary_commitment<E1, E2>(comm: &Commitment<E1>, ro: &mut E2::RO)
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+{
+  let (x, y, _) = comm.to_coordinates();
+  for limb in to_bignat_repr::<E1::Base, E1::Scalar>(&x)
+    .into_iter()
+    .chain(to_bignat_repr::<E1::Base, E1::Scalar>(&y))
+  {
+    ro.absorb(limb);
+  }
+}
+
+/// Absorbs a relaxed instance of the primary circuit
+pub(crate) fn absorb_primary_relaxed_instance<E1, E2>(U: &RelaxedR1CSInstance<E1>, ro: &mut E2::RO)
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+{
+  absorb_primary_commitment::<E1, E2>(&U.comm_W, ro);
+  absorb_primary_commitment::<E1, E2>(&U.comm_E, ro);
+  ro.absorb(U.u);
+  for x in &U.X {
+    ro.absorb(*x);
+  }
+}
+
+/// Absorbs a relaxed instance of the CycleFold circuit, with `u` and `X` absorbed as limbs
+pub(crate) fn absorb_cyclefold_relaxed_instance<E2: Engine>(
+  U: &RelaxedR1CSInstance<E2>,
+  ro: &mut E2::RO,
+) {
+  U.comm_W.absorb_in_ro(ro);
+  U.comm_E.absorb_in_ro(ro);
+  for x in [&U.u].into_iter().chain(U.X.iter()) {
+    for limb in to_bignat_repr::<E2::Scalar, E2::Base>(x) {
+      ro.absorb(limb);
+    }
+  }
+}
+
+/// Folds the last instance of the primary circuit into its running instance.
+/// Returns the commitment to the cross-term, the folded instance-witness pair, and the challenge,
+/// which the CycleFold circuit needs to fold the commitments.
+pub(crate) fn prove_primary<E1, E2>(
+  ck: &CommitmentKey<E1>,
+  ro_consts: &ROConstants<E2>,
+  pp_digest: &E1::Scalar,
+  S: &R1CSShape<E1>,
+  U1: &RelaxedR1CSInstance<E1>,
+  W1: &RelaxedR1CSWitness<E1>,
+  U2: &R1CSInstance<E1>,
+  W2: &R1CSWitness<E1>,
+) -> Result<
+  (
+    Commitment<E1>,
+    (RelaxedR1CSInstance<E1>, RelaxedR1CSWitness<E1>),
+    E1::Scalar,
+  ),
+  NovaError,
+>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+{
+  let mut ro = E2::RO::new(ro_consts.clone());
+  ro.absorb(*pp_digest);
+
+  // U1 does not need to be absorbed since U2.X[0] = Hash(params, U1, i, z0, zi)
+  absorb_primary_commitment::<E1, E2>(&U2.comm_W, &mut ro);
+  for x in &U2.X {
+    ro.absorb(*x);
+  }
+
+  let r_T = E1::Scalar::random(&mut OsRng);
+  let (T, comm_T) = S.commit_T(ck, U1, W1, U2, W2, &r_T)?;
+  absorb_primary_commitment::<E1, E2>(&comm_T, &mut ro);
+
+  let r = ro.squeeze(NUM_CHALLENGE_BITS);
+
+  let U = U1.fold(U2, &comm_T, &r);
+  let W = W1.fold(W2, &T, &r_T, &r)?;
+
+  Ok((comm_T, (U, W), r))
+}
+
+/// Folds an instance of the CycleFold circuit into the running CycleFold instance.
+/// Returns the commitment to the cross-term and the folded instance-witness pair.
+pub(crate) fn prove_cyclefold<E1, E2>(
+  ck: &CommitmentKey<E2>,
+  ro_consts: &ROConstants<E2>,
+  pp_digest: &E1::Scalar,
+  S: &R1CSShape<E2>,
+  U1: &RelaxedR1CSInstance<E2>,
+  W1: &RelaxedR1CSWitness<E2>,
+  U2: &R1CSInstance<E2>,
+  W2: &R1CSWitness<E2>,
+) -> Result<
+  (
+    Commitment<E2>,
+    (RelaxedR1CSInstance<E2>, RelaxedR1CSWitness<E2>),
+  ),
+  NovaError,
+>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+{
+  let mut ro = E2::RO::new(ro_consts.clone());
+  ro.absorb(*pp_digest);
+
+  // U1 does not need to be absorbed since it is bound by the hash in the primary circuit's output
+  U2.comm_W.absorb_in_ro(&mut ro);
+  for x in &U2.X {
+    for limb in to_bignat_repr::<E2::Scalar, E2::Base>(x) {
+      ro.absorb(limb);
+    }
+  }
+
+  let r_T = E2::Scalar::random(&mut OsRng);
+  let (T, comm_T) = S.commit_T(ck, U1, W1, U2, W2, &r_T)?;
+  comm_T.absorb_in_ro(&mut ro);
+
+  let r = base_as_scalar::<E2>(ro.squeeze(NUM_CHALLENGE_BITS));
+
+  let U = U1.fold(U2, &comm_T, &r);
+  let W = W1.fold(W2, &T, &r_T, &r)?;
+
+  Ok((comm_T, (U, W)))
+}
@@ -50,6 +50,21 @@ pub enum NovaError {
   /// returned when the step execution produces an output whose length differs from a previously declared arity
   #[error("InvalidStepOutputLength")]
   InvalidStepOutputLength,
+  /// returned when the circuit supplied to a non-uniform computation does not match its program counter
+  #[error("InvalidCircuitIndex")]
+  InvalidCircuitIndex,
+  /// returned when the proofs supplied to a merge do not cover adjacent ranges of steps
+  #[error("InvalidMerge")]
+  InvalidMerge,
+  /// returned when a checkpoint is resumed with public parameters other than the ones it was created with
+  #[error("CheckpointDigestMismatch")]
+  CheckpointDigestMismatch,
+  /// returned when a checkpoint cannot be written or is malformed
+  #[error("InvalidCheckpoint: {reason}")]
+  InvalidCheckpoint {
+    /// The reason the checkpoint is invalid
+    reason: String,
+  },
   /// returned when the transcript engine encounters an overflow of the round number
   #[error("InternalTranscriptError")]
   InternalTranscriptError,
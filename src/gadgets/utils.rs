@@ -200,6 +200,45 @@ pub fn alloc_num_equals<F: PrimeField, CS: ConstraintSystem<F>>(
   Ok(r)
 }
 
+/// Check that a number is equal to a constant and return a bit
+pub fn alloc_num_equals_constant<F: PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  a: &AllocatedNum<F>,
+  b: F,
+) -> Result<AllocatedBit, SynthesisError> {
+  // Allocate and constrain `r`: result boolean bit.
+  // It equals `true` if `a` equals `b`, `false` otherwise
+  let r_value = a.get_value().map(|a| a == b);
+
+  let r = AllocatedBit::alloc(cs.namespace(|| "r"), r_value)?;
+
+  // Allocate t s.t. t=1 if a == b else 1/(a - b)
+  let t = AllocatedNum::alloc(cs.namespace(|| "t"), || {
+    let a = *a.get_value().get()?;
+    Ok(if a == b {
+      F::ONE
+    } else {
+      (a - b).invert().unwrap()
+    })
+  })?;
+
+  cs.enforce(
+    || "t*(a - b) = 1 - r",
+    |lc| lc + t.get_variable(),
+    |lc| lc + a.get_variable() - (b, CS::one()),
+    |lc| lc + CS::one() - r.get_variable(),
+  );
+
+  cs.enforce(
+    || "r*(a - b) = 0",
+    |lc| lc + r.get_variable(),
+    |lc| lc + a.get_variable() - (b, CS::one()),
+    |lc| lc,
+  );
+
+  Ok(r)
+}
+
 /// If condition return a otherwise b
 pub fn conditionally_select<F: PrimeField, CS: ConstraintSystem<F>>(
   mut cs: CS,
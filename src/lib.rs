@@ -10,7 +10,10 @@
 #![forbid(unsafe_code)]
 
 // main APIs exposed by this library
+pub mod cyclefold;
 pub mod nova;
+pub mod pcd;
+pub mod supernova;
 
 #[cfg(feature = "experimental")]
 pub mod neutron;
@@ -10,11 +10,23 @@ use crate::{
     solver::SatisfyingAssignment,
     ConstraintSystem, SynthesisError,
   },
-  r1cs::{CommitmentKeyHint, R1CSInstance, R1CSWitness},
+  r1cs::{
+    CommitmentKeyHint, R1CSInstance, R1CSShape, R1CSWitness, RelaxedR1CSInstance,
+    RelaxedR1CSWitness,
+  },
+  spartan::{
+    math::Math,
+    polys::multilinear::MultilinearPolynomial,
+    snark::{batch_eval_reduce, batch_eval_verify},
+    sumcheck::SumcheckProof,
+    PolyEvalInstance, PolyEvalWitness,
+  },
   traits::{
-    circuit::StepCircuit, AbsorbInRO2Trait, Engine, RO2Constants, RO2ConstantsCircuit, ROTrait,
+    circuit::StepCircuit, commitment::CommitmentEngineTrait, evaluation::EvaluationEngineTrait,
+    snark::RelaxedR1CSSNARKTrait, AbsorbInRO2Trait, Engine, RO2Constants, RO2ConstantsCircuit,
+    ROTrait, TranscriptEngineTrait,
   },
-  CommitmentKey,
+  Commitment, CommitmentKey, DerandKey,
 };
 use core::marker::PhantomData;
 use ff::Field;
@@ -27,7 +39,7 @@ pub mod nifs;
 pub mod relation;
 
 use circuit::{NeutronAugmentedCircuit, NeutronAugmentedCircuitInputs};
-use nifs::NIFS;
+use nifs::{NIFSRelaxed, NIFS};
 use relation::{FoldedInstance, FoldedWitness, Structure};
 
 /// A type that holds public parameters of Nova
@@ -376,6 +388,447 @@ where
   }
 }
 
+/// A type that holds the prover key for `CompressedSNARK`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ProverKey<E1, E2, C, S, EE>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S: RelaxedR1CSSNARKTrait<E1>,
+  EE: EvaluationEngineTrait<E1>,
+{
+  pk: S::ProverKey,
+  pk_ee: EE::ProverKey,
+  S_relaxed: R1CSShape<E1>,
+  _p: PhantomData<(C, E2)>,
+}
+
+/// A type that holds the verifier key for `CompressedSNARK`
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct VerifierKey<E1, E2, C, S, EE>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S: RelaxedR1CSSNARKTrait<E1>,
+  EE: EvaluationEngineTrait<E1>,
+{
+  F_arity: usize,
+  ro_consts: RO2Constants<E1>,
+  pp_digest: E1::Scalar,
+  vk: S::VerifierKey,
+  vk_ee: EE::VerifierKey,
+  dk: DerandKey<E1>,
+  ell: usize,
+  left: usize,
+  right: usize,
+  _p: PhantomData<(C, E2)>,
+}
+
+/// A SNARK that proves the knowledge of a valid `RecursiveSNARK`
+///
+/// The running instance of NeutronNova is folded with the last R1CS instance and then with a
+/// random folded instance for zero-knowledge. The resulting zero-fold relation
+/// `sum_{x} E(x) * (Az ∘ Bz - Cz)(x) = T` is reduced with a sum-check to a relaxed R1CS instance
+/// whose error vector is `G = Az ∘ Bz - Cz`, which is then proven with `S`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct CompressedSNARK<E1, E2, C, S, EE>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S: RelaxedR1CSSNARKTrait<E1>,
+  EE: EvaluationEngineTrait<E1>,
+{
+  r_U: FoldedInstance<E1>,
+  ri: E1::Scalar,
+  l_u: R1CSInstance<E1>,
+  nifs_Uf: NIFS<E1>,
+
+  l_ur: FoldedInstance<E1>,
+  nifs_Un: NIFSRelaxed<E1>,
+
+  wit_blind_r_Wn: E1::Scalar,
+  err_blind_r_Wn: E1::Scalar,
+
+  comm_G: Commitment<E1>,
+  sc_proof: SumcheckProof<E1>,
+  eval_E_left: E1::Scalar,
+  eval_E_right: E1::Scalar,
+  eval_G: E1::Scalar,
+  sc_proof_batch: SumcheckProof<E1>,
+  evals_batch: Vec<E1::Scalar>,
+  eval_arg: EE::EvaluationArgument,
+
+  snark: S,
+
+  zn: Vec<E1::Scalar>,
+
+  _p: PhantomData<(C, E2)>,
+}
+
+impl<E1, E2, C, S, EE> CompressedSNARK<E1, E2, C, S, EE>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+  S: RelaxedR1CSSNARKTrait<E1>,
+  EE: EvaluationEngineTrait<E1>,
+{
+  /// Creates prover and verifier keys for `CompressedSNARK`
+  pub fn setup(
+    pp: &PublicParams<E1, E2, C>,
+  ) -> Result<(ProverKey<E1, E2, C, S, EE>, VerifierKey<E1, E2, C, S, EE>), NovaError> {
+    let S_relaxed = pp.structure.relaxed_r1cs_shape();
+    let (pk, vk) = S::setup(&pp.ck, &S_relaxed)?;
+    let (pk_ee, vk_ee) = EE::setup(&pp.ck);
+
+    let pk = ProverKey {
+      pk,
+      pk_ee,
+      S_relaxed,
+      _p: Default::default(),
+    };
+
+    let vk = VerifierKey {
+      F_arity: pp.F_arity,
+      ro_consts: pp.ro_consts.clone(),
+      pp_digest: pp.digest(),
+      vk,
+      vk_ee,
+      dk: E1::CE::derand_key(&pp.ck),
+      ell: pp.structure.ell,
+      left: pp.structure.left,
+      right: pp.structure.right,
+      _p: Default::default(),
+    };
+
+    Ok((pk, vk))
+  }
+
+  /// Create a new `CompressedSNARK` (provides zero-knowledge)
+  pub fn prove(
+    pp: &PublicParams<E1, E2, C>,
+    pk: &ProverKey<E1, E2, C, S, EE>,
+    recursive_snark: &RecursiveSNARK<E1, E2, C>,
+  ) -> Result<Self, NovaError> {
+    // fold the running instance with the last instance to get Uf/Wf
+    let (nifs_Uf, (r_Uf, r_Wf)) = NIFS::prove(
+      &pp.ck,
+      &pp.ro_consts,
+      &pp.digest(),
+      &pp.structure,
+      &recursive_snark.r_U,
+      &recursive_snark.r_W,
+      &recursive_snark.l_u,
+      &recursive_snark.l_w,
+    )?;
+
+    // fold Uf/Wf with a random instance/witness to get Un/Wn
+    let (l_ur, l_wr) = pp.structure.sample_random_instance_witness(&pp.ck)?;
+
+    let (nifs_Un, (r_Un, r_Wn)) = NIFSRelaxed::prove(
+      &pp.ro_consts,
+      &pp.digest(),
+      &pp.structure,
+      &r_Uf,
+      &r_Wf,
+      &l_ur,
+      &l_wr,
+    )?;
+
+    // derandomize/unblind commitments
+    let (derandom_r_Wn, wit_blind_r_Wn, err_blind_r_Wn) = r_Wn.derandomize();
+    let derandom_r_Un = r_Un.derandomize(
+      &E1::CE::derand_key(&pp.ck),
+      &wit_blind_r_Wn,
+      &err_blind_r_Wn,
+    );
+
+    // compute the error vector G = Az ∘ Bz - Cz of the folded instance and commit to it
+    let G = pp
+      .structure
+      .error_vec(&derandom_r_Wn.W, &derandom_r_Un.u, &derandom_r_Un.X)?;
+    let comm_G = E1::CE::commit(&pp.ck, &G, &E1::Scalar::ZERO);
+
+    // the relaxed R1CS instance in which the folded u is moved to the public IO
+    let U_relaxed = RelaxedR1CSInstance {
+      comm_W: derandom_r_Un.comm_W,
+      comm_E: comm_G,
+      X: [vec![derandom_r_Un.u], derandom_r_Un.X.clone()].concat(),
+      u: E1::Scalar::ONE,
+    };
+    let W_relaxed = RelaxedR1CSWitness {
+      W: derandom_r_Wn.W.clone(),
+      r_W: E1::Scalar::ZERO,
+      E: G.clone(),
+      r_E: E1::Scalar::ZERO,
+    };
+
+    let mut transcript = E1::TE::new(b"NeutronCompressedSNARK");
+    transcript.absorb(b"pp", &pp.digest());
+    transcript.absorb(b"U", &U_relaxed);
+    transcript.absorb(b"E", &derandom_r_Un.comm_E);
+    transcript.absorb(b"T", &derandom_r_Un.T);
+
+    // run a sum-check to reduce sum_{x} E(x) * G(x) = T to evaluations of E and G
+    let (sc_proof, r, _claims) = SumcheckProof::prove_quad(
+      &derandom_r_Un.T,
+      pp.structure.ell,
+      &mut MultilinearPolynomial::new(pp.structure.full_E(&derandom_r_Wn.E)),
+      &mut MultilinearPolynomial::new(G.clone()),
+      |e: &E1::Scalar, g: &E1::Scalar| -> E1::Scalar { *e * *g },
+      &mut transcript,
+    )?;
+
+    // E holds the left split followed by the right split, and we pad it to 2 * left
+    let ell_left = pp.structure.left.log_2();
+    let ell_right = pp.structure.right.log_2();
+    let (r_right, r_left) = r.split_at(ell_right);
+    let (E_left, E_right) = derandom_r_Wn.E.split_at(pp.structure.left);
+
+    let eval_E_left = MultilinearPolynomial::evaluate_with(E_left, r_left);
+    let eval_E_right = MultilinearPolynomial::evaluate_with(
+      &[
+        E_right.to_vec(),
+        vec![E1::Scalar::ZERO; pp.structure.left - pp.structure.right],
+      ]
+      .concat(),
+      &[
+        vec![E1::Scalar::ZERO; ell_left - ell_right],
+        r_right.to_vec(),
+      ]
+      .concat(),
+    );
+    let eval_G = MultilinearPolynomial::evaluate_with(&G, &r);
+
+    transcript.absorb(b"e", &[eval_E_left, eval_E_right, eval_G].as_slice());
+
+    let E_padded = [
+      derandom_r_Wn.E.clone(),
+      vec![E1::Scalar::ZERO; pp.structure.left - pp.structure.right],
+    ]
+    .concat();
+
+    let u_vec = vec![
+      PolyEvalInstance {
+        c: derandom_r_Un.comm_E,
+        x: [vec![E1::Scalar::ZERO], r_left.to_vec()].concat(),
+        e: eval_E_left,
+      },
+      PolyEvalInstance {
+        c: derandom_r_Un.comm_E,
+        x: [
+          vec![E1::Scalar::ONE],
+          vec![E1::Scalar::ZERO; ell_left - ell_right],
+          r_right.to_vec(),
+        ]
+        .concat(),
+        e: eval_E_right,
+      },
+      PolyEvalInstance {
+        c: comm_G,
+        x: r.clone(),
+        e: eval_G,
+      },
+    ];
+    let w_vec = vec![
+      PolyEvalWitness {
+        p: E_padded.clone(),
+      },
+      PolyEvalWitness { p: E_padded },
+      PolyEvalWitness { p: G },
+    ];
+
+    let (u_joint, w_joint, sc_proof_batch, evals_batch) =
+      batch_eval_reduce(u_vec, w_vec, &mut transcript)?;
+
+    let eval_arg = EE::prove(
+      &pp.ck,
+      &pk.pk_ee,
+      &mut transcript,
+      &u_joint.c,
+      &w_joint.p,
+      &u_joint.x,
+      &u_joint.e,
+    )?;
+
+    // create a SNARK proving the knowledge of the relaxed R1CS witness
+    let snark = S::prove(&pp.ck, &pk.pk, &pk.S_relaxed, &U_relaxed, &W_relaxed)?;
+
+    Ok(Self {
+      r_U: recursive_snark.r_U.clone(),
+      ri: recursive_snark.ri,
+      l_u: recursive_snark.l_u.clone(),
+      nifs_Uf,
+
+      l_ur,
+      nifs_Un,
+
+      wit_blind_r_Wn,
+      err_blind_r_Wn,
+
+      comm_G,
+      sc_proof,
+      eval_E_left,
+      eval_E_right,
+      eval_G,
+      sc_proof_batch,
+      evals_batch,
+      eval_arg,
+
+      snark,
+
+      zn: recursive_snark.zi.clone(),
+
+      _p: Default::default(),
+    })
+  }
+
+  /// Verify the correctness of the `CompressedSNARK`
+  pub fn verify(
+    &self,
+    vk: &VerifierKey<E1, E2, C, S, EE>,
+    num_steps: usize,
+    z0: &[E1::Scalar],
+  ) -> Result<Vec<E1::Scalar>, NovaError> {
+    // the number of steps cannot be zero
+    if num_steps == 0 {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Number of steps cannot be zero".to_string(),
+      });
+    }
+
+    // check if the instances have one public output
+    if self.l_u.X.len() != 1 || self.r_U.X.len() != 1 || self.l_ur.X.len() != 1 {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Invalid number of outputs in instances".to_string(),
+      });
+    }
+
+    // check if the input and output lengths match the arity
+    if z0.len() != vk.F_arity || self.zn.len() != vk.F_arity {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Invalid number of inputs or outputs".to_string(),
+      });
+    }
+
+    // check if the output hash in the R1CS instance points to the right running instance
+    let hash = {
+      let mut hasher = E1::RO2::new(vk.ro_consts.clone());
+      hasher.absorb(vk.pp_digest);
+      hasher.absorb(E1::Scalar::from(num_steps as u64));
+      for e in z0 {
+        hasher.absorb(*e);
+      }
+      for e in &self.zn {
+        hasher.absorb(*e);
+      }
+      self.r_U.absorb_in_ro2(&mut hasher);
+      hasher.absorb(self.ri);
+
+      hasher.squeeze(NUM_HASH_BITS)
+    };
+
+    if hash != self.l_u.X[0] {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Invalid output hash in R1CS instance".to_string(),
+      });
+    }
+
+    // fold the running instance and the last instance to get a folded instance
+    let r_Uf = self
+      .nifs_Uf
+      .verify(&vk.ro_consts, &vk.pp_digest, &self.r_U, &self.l_u)?;
+
+    // fold the folded instance with the random instance
+    let r_Un = self
+      .nifs_Un
+      .verify(&vk.ro_consts, &vk.pp_digest, &r_Uf, &self.l_ur)?;
+
+    // derandomize/unblind commitments
+    let derandom_r_Un = r_Un.derandomize(&vk.dk, &self.wit_blind_r_Wn, &self.err_blind_r_Wn);
+
+    let U_relaxed = RelaxedR1CSInstance {
+      comm_W: derandom_r_Un.comm_W,
+      comm_E: self.comm_G,
+      X: [vec![derandom_r_Un.u], derandom_r_Un.X.clone()].concat(),
+      u: E1::Scalar::ONE,
+    };
+
+    let mut transcript = E1::TE::new(b"NeutronCompressedSNARK");
+    transcript.absorb(b"pp", &vk.pp_digest);
+    transcript.absorb(b"U", &U_relaxed);
+    transcript.absorb(b"E", &derandom_r_Un.comm_E);
+    transcript.absorb(b"T", &derandom_r_Un.T);
+
+    let (claim, r) = self
+      .sc_proof
+      .verify(derandom_r_Un.T, vk.ell, 2, &mut transcript)?;
+
+    if claim != self.eval_E_left * self.eval_E_right * self.eval_G {
+      return Err(NovaError::InvalidSumcheckProof);
+    }
+
+    transcript.absorb(
+      b"e",
+      &[self.eval_E_left, self.eval_E_right, self.eval_G].as_slice(),
+    );
+
+    let ell_left = vk.left.log_2();
+    let ell_right = vk.right.log_2();
+    let (r_right, r_left) = r.split_at(ell_right);
+
+    let u_vec = vec![
+      PolyEvalInstance {
+        c: derandom_r_Un.comm_E,
+        x: [vec![E1::Scalar::ZERO], r_left.to_vec()].concat(),
+        e: self.eval_E_left,
+      },
+      PolyEvalInstance {
+        c: derandom_r_Un.comm_E,
+        x: [
+          vec![E1::Scalar::ONE],
+          vec![E1::Scalar::ZERO; ell_left - ell_right],
+          r_right.to_vec(),
+        ]
+        .concat(),
+        e: self.eval_E_right,
+      },
+      PolyEvalInstance {
+        c: self.comm_G,
+        x: r,
+        e: self.eval_G,
+      },
+    ];
+
+    let u_joint = batch_eval_verify(
+      u_vec,
+      &mut transcript,
+      &self.sc_proof_batch,
+      &self.evals_batch,
+    )?;
+
+    EE::verify(
+      &vk.vk_ee,
+      &mut transcript,
+      &u_joint.c,
+      &u_joint.x,
+      &u_joint.e,
+      &self.eval_arg,
+    )?;
+
+    // check the satisfiability of the relaxed R1CS instance
+    self.snark.verify(&vk.vk, &U_relaxed)?;
+
+    Ok(self.zn.clone())
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -396,6 +849,8 @@ mod tests {
   use ff::PrimeField;
 
   type EE<E> = crate::provider::ipa_pc::EvaluationEngine<E>;
+  type EEPrime<E> = crate::provider::hyperkzg::EvaluationEngine<E>;
+  type S<E, EE> = crate::spartan::snark::RelaxedR1CSSNARK<E, EE>;
   type SPrime<E, EE> = crate::spartan::ppsnark::RelaxedR1CSSNARK<E, EE>;
 
   #[derive(Clone, Debug, Default)]
@@ -585,6 +1040,98 @@ mod tests {
     test_ivc_nontrivial_with::<Secp256k1Engine, Secq256k1Engine>();
   }
 
+  fn test_ivc_nontrivial_with_compression_with<E1, E2, S, EE>(ck_hint1: &CommitmentKeyHint<E1>)
+  where
+    E1: Engine<Base = <E2 as Engine>::Scalar>,
+    E2: Engine<Base = <E1 as Engine>::Scalar>,
+    S: RelaxedR1CSSNARKTrait<E1>,
+    EE: EvaluationEngineTrait<E1>,
+  {
+    let circuit = CubicCircuit::default();
+
+    // produce public parameters
+    let pp = PublicParams::<E1, E2, CubicCircuit<<E1 as Engine>::Scalar>>::setup(
+      &circuit,
+      ck_hint1,
+      &*default_ck_hint(),
+    )
+    .unwrap();
+
+    let num_steps = 3;
+
+    // produce a recursive SNARK
+    let mut recursive_snark = RecursiveSNARK::<E1, E2, CubicCircuit<<E1 as Engine>::Scalar>>::new(
+      &pp,
+      &circuit,
+      &[<E1 as Engine>::Scalar::ONE],
+    )
+    .unwrap();
+
+    for _i in 0..num_steps {
+      let res = recursive_snark.prove_step(&pp, &circuit);
+      assert!(res.is_ok());
+    }
+
+    // verify the recursive SNARK
+    let res = recursive_snark.verify(&pp, num_steps, &[<E1 as Engine>::Scalar::ONE]);
+    assert!(res.is_ok());
+    assert_eq!(
+      res.unwrap(),
+      vec![<E1 as Engine>::Scalar::from(0x2aaaaa3u64)]
+    );
+
+    // produce the prover and verifier keys for compressed snark
+    let (pk, vk) = CompressedSNARK::<_, _, _, S, EE>::setup(&pp).unwrap();
+
+    // produce a compressed SNARK
+    let res = CompressedSNARK::<_, _, _, S, EE>::prove(&pp, &pk, &recursive_snark);
+    assert!(res.is_ok());
+    let compressed_snark = res.unwrap();
+
+    // verify the compressed SNARK
+    let res = compressed_snark.verify(&vk, num_steps, &[<E1 as Engine>::Scalar::ONE]);
+    assert!(res.is_ok());
+    assert_eq!(
+      res.unwrap(),
+      vec![<E1 as Engine>::Scalar::from(0x2aaaaa3u64)]
+    );
+
+    // the compressed SNARK must not verify against a different number of steps or inputs
+    let res = compressed_snark.verify(&vk, num_steps + 1, &[<E1 as Engine>::Scalar::ONE]);
+    assert!(res.is_err());
+    let res = compressed_snark.verify(&vk, num_steps, &[<E1 as Engine>::Scalar::ZERO]);
+    assert!(res.is_err());
+  }
+
+  #[test]
+  fn test_ivc_nontrivial_with_compression() {
+    test_ivc_nontrivial_with_compression_with::<PallasEngine, VestaEngine, S<_, EE<_>>, EE<_>>(
+      &*default_ck_hint(),
+    );
+    test_ivc_nontrivial_with_compression_with::<
+      Bn256EngineKZG,
+      GrumpkinEngine,
+      S<_, EEPrime<_>>,
+      EEPrime<_>,
+    >(&*default_ck_hint());
+    test_ivc_nontrivial_with_compression_with::<Secp256k1Engine, Secq256k1Engine, S<_, EE<_>>, EE<_>>(
+      &*default_ck_hint(),
+    );
+  }
+
+  #[test]
+  fn test_ivc_nontrivial_with_spark_compression() {
+    test_ivc_nontrivial_with_compression_with::<PallasEngine, VestaEngine, SPrime<_, EE<_>>, EE<_>>(
+      &*SPrime::<PallasEngine, EE<_>>::ck_floor(),
+    );
+    test_ivc_nontrivial_with_compression_with::<
+      Bn256EngineKZG,
+      GrumpkinEngine,
+      SPrime<_, EEPrime<_>>,
+      EEPrime<_>,
+    >(&*SPrime::<Bn256EngineKZG, EEPrime<_>>::ck_floor());
+  }
+
   fn test_ivc_base_with<E1, E2>()
   where
     E1: Engine<Base = <E2 as Engine>::Scalar>,
@@ -293,7 +293,6 @@ impl<E: Engine> NIFS<E> {
   /// and outputs a folded instance `U` with the same shape,
   /// with the guarantee that the folded instance `U`
   /// if and only if `U1` and `U2` are satisfiable.
-  #[cfg(test)]
   pub fn verify(
     &self,
     ro_consts: &RO2Constants<E>,
@@ -343,6 +342,149 @@ impl<E: Engine> NIFS<E> {
   }
 }
 
+/// An NIFS message from NeutronNova's folding scheme when folding two folded instances
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct NIFSRelaxed<E: Engine> {
+  pub(crate) poly: UniPoly<E::Scalar>,
+}
+
+impl<E: Engine> NIFSRelaxed<E> {
+  /// Same as `NIFS::prove`, but takes two folded instance-witness pairs
+  pub fn prove(
+    ro_consts: &RO2Constants<E>,
+    pp_digest: &E::Scalar,
+    S: &Structure<E>,
+    U1: &FoldedInstance<E>,
+    W1: &FoldedWitness<E>,
+    U2: &FoldedInstance<E>,
+    W2: &FoldedWitness<E>,
+  ) -> Result<(NIFSRelaxed<E>, (FoldedInstance<E>, FoldedWitness<E>)), NovaError> {
+    // initialize a new RO
+    let mut ro = E::RO2::new(ro_consts.clone());
+
+    // append the digest of pp to the transcript
+    ro.absorb(*pp_digest);
+
+    // append U1 and U2 to transcript
+    // (this function is only used when folding in random instance)
+    U1.absorb_in_ro2(&mut ro);
+    U2.absorb_in_ro2(&mut ro);
+
+    // compute a challenge from the RO
+    let rho = ro.squeeze(NUM_CHALLENGE_BITS);
+
+    // We now run a single round of the sum-check protocol to establish
+    // T = (1-rho) * T1 + rho * T2
+    let T = (E::Scalar::ONE - rho) * U1.T + rho * U2.T;
+
+    let (res1, res2) = rayon::join(
+      || {
+        let z1 = [W1.W.clone(), vec![U1.u], U1.X.clone()].concat();
+        S.S.multiply_vec(&z1)
+      },
+      || {
+        let z2 = [W2.W.clone(), vec![U2.u], U2.X.clone()].concat();
+        S.S.multiply_vec(&z2)
+      },
+    );
+
+    let (Az1, Bz1, Cz1) = res1?;
+    let (Az2, Bz2, Cz2) = res2?;
+
+    // compute the sum-check polynomial's evaluations at 0, 2, 3, 4, and 5
+    let (eval_point_0, eval_point_2, eval_point_3, eval_point_4, eval_point_5) =
+      NIFS::<E>::prove_helper(
+        &rho,
+        (S.left, S.right),
+        &W1.E,
+        &Az1,
+        &Bz1,
+        &Cz1,
+        &W2.E,
+        &Az2,
+        &Bz2,
+        &Cz2,
+      );
+
+    let evals = vec![
+      eval_point_0,
+      T - eval_point_0,
+      eval_point_2,
+      eval_point_3,
+      eval_point_4,
+      eval_point_5,
+    ];
+    let poly = UniPoly::<E::Scalar>::from_evals(&evals);
+
+    // absorb poly in the RO
+    <UniPoly<E::Scalar> as AbsorbInRO2Trait<E>>::absorb_in_ro2(&poly, &mut ro);
+
+    // squeeze a challenge
+    let r_b = ro.squeeze(NUM_CHALLENGE_BITS);
+
+    // compute the sum-check polynomial's evaluations at r_b
+    let eq_rho_r_b = (E::Scalar::ONE - rho) * (E::Scalar::ONE - r_b) + rho * r_b;
+    let T_out = poly.evaluate(&r_b)
+      * Option::<E::Scalar>::from(eq_rho_r_b.invert()).ok_or(NovaError::InternalError)?;
+
+    let U = U1.fold_relaxed(U2, &r_b, &T_out)?;
+    let W = W1.fold_relaxed(W2, &r_b)?;
+
+    // return the folded instance and witness
+    Ok((Self { poly }, (U, W)))
+  }
+
+  /// Same as `NIFS::verify`, but takes two folded instances
+  pub fn verify(
+    &self,
+    ro_consts: &RO2Constants<E>,
+    pp_digest: &E::Scalar,
+    U1: &FoldedInstance<E>,
+    U2: &FoldedInstance<E>,
+  ) -> Result<FoldedInstance<E>, NovaError> {
+    // initialize a new RO
+    let mut ro = E::RO2::new(ro_consts.clone());
+
+    // append the digest of pp to the transcript
+    ro.absorb(*pp_digest);
+
+    // append U1 and U2 to transcript
+    U1.absorb_in_ro2(&mut ro);
+    U2.absorb_in_ro2(&mut ro);
+
+    // compute a challenge from the RO
+    let rho = ro.squeeze(NUM_CHALLENGE_BITS);
+
+    // T = (1-rho) * T1 + rho * T2
+    let T = (E::Scalar::ONE - rho) * U1.T + rho * U2.T;
+
+    // check if poly(0) + poly(1) = T
+    if self.poly.eval_at_zero() + self.poly.eval_at_one() != T {
+      return Err(NovaError::InvalidSumcheckProof);
+    }
+
+    // absorb poly in the RO
+    <UniPoly<E::Scalar> as AbsorbInRO2Trait<E>>::absorb_in_ro2(&self.poly, &mut ro);
+
+    // squeeze a challenge
+    let r_b = ro.squeeze(NUM_CHALLENGE_BITS);
+
+    // compute the sum-check polynomial's evaluations at r_b
+    let eq_rho_r_b = (E::Scalar::ONE - rho) * (E::Scalar::ONE - r_b) + rho * r_b;
+    let T_out = self.poly.evaluate(&r_b)
+      * Option::<E::Scalar>::from(eq_rho_r_b.invert()).ok_or(NovaError::ProofVerifyError {
+        reason: "eq(rho, r_b) is not invertible".to_string(),
+      })?;
+
+    let U = U1.fold_relaxed(U2, &r_b, &T_out)?;
+
+    // return the folded instance
+    Ok(U)
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -1,12 +1,14 @@
 //! This module defines relations used in the Neutron folding scheme
 use crate::{
   errors::NovaError,
-  r1cs::{R1CSInstance, R1CSShape, R1CSWitness},
+  r1cs::{R1CSInstance, R1CSShape, R1CSWitness, SparseMatrix},
   spartan::math::Math,
   traits::{commitment::CommitmentEngineTrait, AbsorbInRO2Trait, Engine, ROTrait},
-  Commitment, CommitmentKey,
+  Commitment, CommitmentKey, DerandKey,
 };
 use ff::Field;
+use once_cell::sync::OnceCell;
+use rand_core::OsRng;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -29,11 +31,11 @@ pub struct Structure<E: Engine> {
 pub struct FoldedWitness<E: Engine> {
   /// Running witness of the main relation
   pub(crate) W: Vec<E::Scalar>,
-  r_W: E::Scalar,
+  pub(crate) r_W: E::Scalar,
 
   /// eq polynomial in tensor form
   pub(crate) E: Vec<E::Scalar>,
-  r_E: E::Scalar,
+  pub(crate) r_E: E::Scalar,
 }
 
 /// A type that holds instance information for a zero-fold relation
@@ -67,34 +69,147 @@ impl<E: Engine> Structure<E> {
     }
   }
 
-  /// Check if the witness is satisfying
-  pub fn is_sat(
-    &self,
-    ck: &CommitmentKey<E>,
-    U: &FoldedInstance<E>,
-    W: &FoldedWitness<E>,
-  ) -> Result<(), NovaError> {
-    // check if the witness is satisfying
-    let z = [W.W.clone(), vec![U.u], U.X.clone()].concat();
-    let (Az, Bz, Cz) = self.S.multiply_vec(&z)?;
-
-    // full_E is the outer product of E1 and E2
+  /// Returns the outer product of the two splits of `E`
+  pub(crate) fn full_E(&self, E: &[E::Scalar]) -> Vec<E::Scalar> {
     // E1 and E2 are splits of E
-    let (E1, E2) = W.E.split_at(self.left);
+    let (E1, E2) = E.split_at(self.left);
     let mut full_E = vec![E::Scalar::ONE; self.left * self.right];
     for i in 0..self.right {
       for j in 0..self.left {
         full_E[i * self.left + j] = E2[i] * E1[j];
       }
     }
+    full_E
+  }
 
-    let sum = full_E
-      .par_iter()
-      .zip(Az.par_iter())
-      .zip(Bz.par_iter())
-      .zip(Cz.par_iter())
-      .map(|(((e, a), b), c)| *e * ((*a) * (*b) - *c))
-      .reduce(|| E::Scalar::ZERO, |acc, x| acc + x);
+  /// Computes `Az ∘ Bz - Cz` for `z = (W, u, X)`
+  pub(crate) fn error_vec(
+    &self,
+    W: &[E::Scalar],
+    u: &E::Scalar,
+    X: &[E::Scalar],
+  ) -> Result<Vec<E::Scalar>, NovaError> {
+    let z = [W.to_vec(), vec![*u], X.to_vec()].concat();
+    let (Az, Bz, Cz) = self.S.multiply_vec(&z)?;
+
+    Ok(
+      Az.par_iter()
+        .zip(Bz.par_iter())
+        .zip(Cz.par_iter())
+        .map(|((a, b), c)| *a * *b - *c)
+        .collect(),
+    )
+  }
+
+  /// Computes the sum of `Az ∘ Bz - Cz` weighted by the outer product of the splits of `E`
+  fn compute_T(
+    &self,
+    W: &[E::Scalar],
+    u: &E::Scalar,
+    X: &[E::Scalar],
+    E: &[E::Scalar],
+  ) -> Result<E::Scalar, NovaError> {
+    let full_E = self.full_E(E);
+    let err = self.error_vec(W, u, X)?;
+
+    Ok(
+      full_E
+        .par_iter()
+        .zip(err.par_iter())
+        .map(|(e, v)| *e * *v)
+        .reduce(|| E::Scalar::ZERO, |acc, x| acc + x),
+    )
+  }
+
+  /// Returns the shape of a relaxed R1CS whose satisfying instances with `u = 1`
+  /// correspond to `Az ∘ Bz - Cz = E` for `z = (W, u', X)` in `S`.
+  /// This is obtained by moving the constant column of `S` into the first public input,
+  /// so the folded `u'` of a zero-fold instance becomes a public input of the relaxed R1CS.
+  pub(crate) fn relaxed_r1cs_shape(&self) -> R1CSShape<E> {
+    let num_vars = self.S.num_vars;
+
+    let shift = |M: &SparseMatrix<E::Scalar>| -> SparseMatrix<E::Scalar> {
+      let mut M = M.clone();
+      M.indices.par_iter_mut().for_each(|c| {
+        if *c >= num_vars {
+          *c += 1
+        }
+      });
+      M.cols += 1;
+      M
+    };
+
+    R1CSShape {
+      num_cons: self.S.num_cons,
+      num_vars,
+      num_io: self.S.num_io + 1,
+      A: shift(&self.S.A),
+      B: shift(&self.S.B),
+      C: shift(&self.S.C),
+      digest: OnceCell::new(),
+    }
+  }
+
+  /// Samples a new random `FoldedInstance`/`FoldedWitness` pair
+  pub fn sample_random_instance_witness(
+    &self,
+    ck: &CommitmentKey<E>,
+  ) -> Result<(FoldedInstance<E>, FoldedWitness<E>), NovaError> {
+    // sample Z = (W, u, X)
+    let Z = (0..self.S.num_vars + self.S.num_io + 1)
+      .into_par_iter()
+      .map(|_| E::Scalar::random(&mut OsRng))
+      .collect::<Vec<E::Scalar>>();
+
+    // sample the splits of E
+    let E = (0..self.left + self.right)
+      .into_par_iter()
+      .map(|_| E::Scalar::random(&mut OsRng))
+      .collect::<Vec<E::Scalar>>();
+
+    let r_W = E::Scalar::random(&mut OsRng);
+    let r_E = E::Scalar::random(&mut OsRng);
+
+    let (W, u, X) = (
+      &Z[..self.S.num_vars],
+      Z[self.S.num_vars],
+      &Z[self.S.num_vars + 1..],
+    );
+
+    let T = self.compute_T(W, &u, X, &E)?;
+
+    // compute commitments to W,E in parallel
+    let (comm_W, comm_E) = rayon::join(
+      || E::CE::commit(ck, W, &r_W),
+      || E::CE::commit(ck, &E, &r_E),
+    );
+
+    Ok((
+      FoldedInstance {
+        comm_W,
+        comm_E,
+        T,
+        u,
+        X: X.to_vec(),
+      },
+      FoldedWitness {
+        W: W.to_vec(),
+        r_W,
+        E,
+        r_E,
+      },
+    ))
+  }
+
+  /// Check if the witness is satisfying
+  pub fn is_sat(
+    &self,
+    ck: &CommitmentKey<E>,
+    U: &FoldedInstance<E>,
+    W: &FoldedWitness<E>,
+  ) -> Result<(), NovaError> {
+    // check if the witness is satisfying
+    let sum = self.compute_T(&W.W, &U.u, &U.X, &W.E)?;
 
     if sum != U.T {
       println!("sum: {:?}", sum);
@@ -156,6 +271,45 @@ impl<E: Engine> FoldedWitness<E> {
 
     Ok(Self { W, r_W, E, r_E })
   }
+
+  /// Fold the witness with another folded witness
+  pub fn fold_relaxed(&self, W2: &FoldedWitness<E>, r_b: &E::Scalar) -> Result<Self, NovaError> {
+    if self.W.len() != W2.W.len() || self.E.len() != W2.E.len() {
+      return Err(NovaError::InvalidWitnessLength);
+    }
+
+    let W = self
+      .W
+      .par_iter()
+      .zip(W2.W.par_iter())
+      .map(|(w1, w2)| *w1 + *r_b * (*w2 - *w1))
+      .collect::<Vec<_>>();
+    let r_W = (E::Scalar::ONE - r_b) * self.r_W + *r_b * W2.r_W;
+
+    let E = self
+      .E
+      .par_iter()
+      .zip(W2.E.par_iter())
+      .map(|(e1, e2)| *e1 + *r_b * (*e2 - *e1))
+      .collect::<Vec<_>>();
+    let r_E = (E::Scalar::ONE - r_b) * self.r_E + *r_b * W2.r_E;
+
+    Ok(Self { W, r_W, E, r_E })
+  }
+
+  /// Removes the blinds from the witness and returns them
+  pub fn derandomize(&self) -> (Self, E::Scalar, E::Scalar) {
+    (
+      FoldedWitness {
+        W: self.W.clone(),
+        r_W: E::Scalar::ZERO,
+        E: self.E.clone(),
+        r_E: E::Scalar::ZERO,
+      },
+      self.r_W,
+      self.r_E,
+    )
+  }
 }
 
 impl<E: Engine> FoldedInstance<E> {
@@ -197,6 +351,48 @@ impl<E: Engine> FoldedInstance<E> {
       X,
     })
   }
+
+  /// Fold the instance with another folded instance
+  pub fn fold_relaxed(
+    &self,
+    U2: &FoldedInstance<E>,
+    r_b: &E::Scalar,
+    T_out: &E::Scalar,
+  ) -> Result<Self, NovaError> {
+    if self.X.len() != U2.X.len() {
+      return Err(NovaError::InvalidInputLength);
+    }
+
+    // we need to compute the weighted sum using weights of (1-r_b) and r_b
+    let comm_W = self.comm_W * (E::Scalar::ONE - r_b) + U2.comm_W * *r_b;
+    let comm_E = self.comm_E * (E::Scalar::ONE - r_b) + U2.comm_E * *r_b;
+    let X = self
+      .X
+      .par_iter()
+      .zip(U2.X.par_iter())
+      .map(|(x1, x2)| (E::Scalar::ONE - r_b) * x1 + *r_b * x2)
+      .collect::<Vec<_>>();
+    let u = (E::Scalar::ONE - r_b) * self.u + *r_b * U2.u;
+
+    Ok(Self {
+      comm_W,
+      comm_E,
+      T: *T_out,
+      u,
+      X,
+    })
+  }
+
+  /// Removes the given blinds from the commitments in the instance
+  pub fn derandomize(&self, dk: &DerandKey<E>, r_W: &E::Scalar, r_E: &E::Scalar) -> Self {
+    FoldedInstance {
+      comm_W: E::CE::derandomize(dk, &self.comm_W, r_W),
+      comm_E: E::CE::derandomize(dk, &self.comm_E, r_E),
+      T: self.T,
+      u: self.u,
+      X: self.X.clone(),
+    }
+  }
 }
 
 impl<E: Engine> AbsorbInRO2Trait<E> for FoldedInstance<E> {
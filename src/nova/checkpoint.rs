@@ -0,0 +1,229 @@
+//! This module implements checkpointing of a `RecursiveSNARK` so that a long-running prover can
+//! be stopped and resumed later with the same public parameters.
+//!
+//! A checkpoint consists of a small header (a magic string, a format version, and the digest of
+//! the public parameters), the non-witness state of the `RecursiveSNARK`, and finally the witness
+//! vectors, which are written and read in chunks of `CHUNK_SIZE` elements so that they are never
+//! encoded in memory as a whole.
+use super::{PublicParams, RecursiveSNARK};
+use crate::{
+  errors::NovaError,
+  r1cs::{R1CSInstance, R1CSWitness, RelaxedR1CSInstance, RelaxedR1CSWitness},
+  traits::{circuit::StepCircuit, Engine},
+};
+use bincode::config::Config;
+use core::marker::PhantomData;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// The magic string with which every checkpoint starts
+const CHECKPOINT_MAGIC: [u8; 8] = *b"NOVACKPT";
+
+/// The version of the checkpoint format written by this module
+const CHECKPOINT_VERSION: u32 = 1;
+
+/// The number of elements of a witness vector that are encoded at a time
+const CHUNK_SIZE: usize = 1 << 12;
+
+/// The part of a `RecursiveSNARK` that is not streamed in chunks
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+struct CheckpointState<E1, E2>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+{
+  z0: Vec<E1::Scalar>,
+  zi: Vec<E1::Scalar>,
+  i: usize,
+
+  r_U_primary: RelaxedR1CSInstance<E1>,
+  ri_primary: E1::Scalar,
+  r_W_primary_blinds: (E1::Scalar, E1::Scalar),
+
+  r_U_secondary: RelaxedR1CSInstance<E2>,
+  ri_secondary: E2::Scalar,
+  r_W_secondary_blinds: (E2::Scalar, E2::Scalar),
+
+  l_u_secondary: R1CSInstance<E2>,
+  l_w_secondary_blind: E2::Scalar,
+}
+
+fn config() -> impl Config {
+  bincode::config::legacy()
+    .with_little_endian()
+    .with_fixed_int_encoding()
+}
+
+fn invalid(reason: impl ToString) -> NovaError {
+  NovaError::InvalidCheckpoint {
+    reason: reason.to_string(),
+  }
+}
+
+fn write_value<T: Serialize, W: Write>(w: &mut W, v: &T) -> Result<(), NovaError> {
+  bincode::serde::encode_into_std_write(v, w, config()).map_err(invalid)?;
+  Ok(())
+}
+
+fn read_value<T: DeserializeOwned, R: Read>(r: &mut R) -> Result<T, NovaError> {
+  bincode::serde::decode_from_std_read(r, config()).map_err(invalid)
+}
+
+/// Writes the length of `v` followed by its elements, encoding `CHUNK_SIZE` elements at a time
+fn write_vec<T: Serialize, W: Write>(w: &mut W, v: &[T]) -> Result<(), NovaError> {
+  write_value(w, &(v.len() as u64))?;
+  let mut buf = Vec::new();
+  for chunk in v.chunks(CHUNK_SIZE) {
+    buf.clear();
+    for x in chunk {
+      write_value(&mut buf, x)?;
+    }
+    w.write_all(&buf).map_err(invalid)?;
+  }
+  Ok(())
+}
+
+/// Reads a vector written by `write_vec`, checking that it has the expected length before
+/// allocating any memory for it
+fn read_vec<T: DeserializeOwned, R: Read>(r: &mut R, len: usize) -> Result<Vec<T>, NovaError> {
+  let n: u64 = read_value(r)?;
+  if n != len as u64 {
+    return Err(invalid(format!(
+      "witness vector has length {n}, expected {len}"
+    )));
+  }
+  let mut v = Vec::with_capacity(len);
+  for _ in 0..len {
+    v.push(read_value(r)?);
+  }
+  Ok(v)
+}
+
+impl<E1, E2, C> RecursiveSNARK<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Writes a checkpoint of the recursive SNARK to `w`, from which the computation
+  /// can later be resumed with `RecursiveSNARK::resume`.
+  ///
+  /// The checkpoint is bound to the digest of `pp`. Witness vectors are encoded in chunks,
+  /// so the checkpoint is never held in memory as a whole; `w` should be buffered by the caller.
+  pub fn checkpoint<W: Write>(
+    &self,
+    pp: &PublicParams<E1, E2, C>,
+    w: &mut W,
+  ) -> Result<(), NovaError> {
+    w.write_all(&CHECKPOINT_MAGIC).map_err(invalid)?;
+    write_value(w, &CHECKPOINT_VERSION)?;
+    write_value(w, &pp.digest())?;
+
+    let state = CheckpointState::<E1, E2> {
+      z0: self.z0.clone(),
+      zi: self.zi.clone(),
+      i: self.i,
+      r_U_primary: self.r_U_primary.clone(),
+      ri_primary: self.ri_primary,
+      r_W_primary_blinds: (self.r_W_primary.r_W, self.r_W_primary.r_E),
+      r_U_secondary: self.r_U_secondary.clone(),
+      ri_secondary: self.ri_secondary,
+      r_W_secondary_blinds: (self.r_W_secondary.r_W, self.r_W_secondary.r_E),
+      l_u_secondary: self.l_u_secondary.clone(),
+      l_w_secondary_blind: self.l_w_secondary.r_W,
+    };
+    write_value(w, &state)?;
+
+    write_vec(w, &self.r_W_primary.W)?;
+    write_vec(w, &self.r_W_primary.E)?;
+    write_vec(w, &self.r_W_secondary.W)?;
+    write_vec(w, &self.r_W_secondary.E)?;
+    write_vec(w, &self.l_w_secondary.W)?;
+
+    Ok(())
+  }
+
+  /// Reads a recursive SNARK from a checkpoint written by `RecursiveSNARK::checkpoint`.
+  ///
+  /// Returns `NovaError::CheckpointDigestMismatch` if the checkpoint was created with public
+  /// parameters other than `pp`, and `NovaError::InvalidCheckpoint` if it is malformed.
+  /// The checkpoint is read in small pieces, so `r` should be buffered by the caller.
+  pub fn resume<R: Read>(pp: &PublicParams<E1, E2, C>, r: &mut R) -> Result<Self, NovaError> {
+    let mut magic = [0u8; CHECKPOINT_MAGIC.len()];
+    r.read_exact(&mut magic).map_err(invalid)?;
+    if magic != CHECKPOINT_MAGIC {
+      return Err(invalid("not a checkpoint of a recursive SNARK"));
+    }
+    let version: u32 = read_value(r)?;
+    if version != CHECKPOINT_VERSION {
+      return Err(invalid(format!(
+        "unsupported format version {version}, expected {CHECKPOINT_VERSION}"
+      )));
+    }
+    let digest: E1::Scalar = read_value(r)?;
+    if digest != pp.digest() {
+      return Err(NovaError::CheckpointDigestMismatch);
+    }
+
+    let state: CheckpointState<E1, E2> = read_value(r)?;
+    if state.z0.len() != pp.F_arity || state.zi.len() != pp.F_arity {
+      return Err(invalid(
+        "inputs or outputs do not match the arity of the circuit",
+      ));
+    }
+    if state.i == 0 {
+      return Err(invalid("number of steps is zero"));
+    }
+    // the running and incoming instances each carry the two hashes of the augmented circuits
+    if [
+      state.r_U_primary.X.len(),
+      state.r_U_secondary.X.len(),
+      state.l_u_secondary.X.len(),
+    ]
+    .into_iter()
+    .any(|len| len != 2)
+    {
+      return Err(invalid("instances do not have two public IO elements"));
+    }
+
+    let (S1, S2) = (&pp.r1cs_shape_primary, &pp.r1cs_shape_secondary);
+    let r_W_primary = RelaxedR1CSWitness {
+      W: read_vec(r, S1.num_vars)?,
+      E: read_vec(r, S1.num_cons)?,
+      r_W: state.r_W_primary_blinds.0,
+      r_E: state.r_W_primary_blinds.1,
+    };
+    let r_W_secondary = RelaxedR1CSWitness {
+      W: read_vec(r, S2.num_vars)?,
+      E: read_vec(r, S2.num_cons)?,
+      r_W: state.r_W_secondary_blinds.0,
+      r_E: state.r_W_secondary_blinds.1,
+    };
+    let l_w_secondary = R1CSWitness {
+      W: read_vec(r, S2.num_vars)?,
+      r_W: state.l_w_secondary_blind,
+    };
+
+    Ok(Self {
+      z0: state.z0,
+
+      r_W_primary,
+      r_U_primary: state.r_U_primary,
+      ri_primary: state.ri_primary,
+
+      r_W_secondary,
+      r_U_secondary: state.r_U_secondary,
+      ri_secondary: state.ri_secondary,
+
+      l_w_secondary,
+      l_u_secondary: state.l_u_secondary,
+
+      i: state.i,
+
+      zi: state.zi,
+
+      _p: PhantomData,
+    })
+  }
+}
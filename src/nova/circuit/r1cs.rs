@@ -55,6 +55,7 @@ impl<E: Engine> AllocatedR1CSInstance<E> {
 }
 
 /// An Allocated Relaxed R1CS Instance
+#[derive(Clone)]
 pub struct AllocatedRelaxedR1CSInstance<E: Engine> {
   pub(crate) W: AllocatedPoint<E>,
   pub(crate) E: AllocatedPoint<E>,
@@ -29,11 +29,14 @@ use once_cell::sync::OnceCell;
 use rand_core::OsRng;
 use serde::{Deserialize, Serialize};
 
-mod circuit;
+mod checkpoint;
+pub(crate) mod circuit;
 pub(crate) mod nifs;
+mod solidity;
 
 use circuit::{NovaAugmentedCircuit, NovaAugmentedCircuitInputs};
 use nifs::{NIFSRelaxed, NIFS};
+pub use solidity::SolidityVerifier;
 
 /// A type that holds public parameters of Nova
 #[derive(Serialize, Deserialize)]
@@ -1123,6 +1126,89 @@ mod tests {
     test_ivc_nontrivial_with::<Secp256k1Engine, Secq256k1Engine>();
   }
 
+  fn test_ivc_checkpoint_with<E1, E2>()
+  where
+    E1: Engine<Base = <E2 as Engine>::Scalar>,
+    E2: Engine<Base = <E1 as Engine>::Scalar>,
+  {
+    let circuit = CubicCircuit::default();
+
+    // produce public parameters
+    let pp = PublicParams::<E1, E2, CubicCircuit<E1::Scalar>>::setup(
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+
+    let z0 = vec![<E1 as Engine>::Scalar::ZERO];
+
+    // produce a recursive SNARK and checkpoint it after a few steps
+    let mut recursive_snark =
+      RecursiveSNARK::<E1, E2, CubicCircuit<<E1 as Engine>::Scalar>>::new(&pp, &circuit, &z0)
+        .unwrap();
+    for _i in 0..2 {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+    }
+    let mut bytes = Vec::new();
+    recursive_snark.checkpoint(&pp, &mut bytes).unwrap();
+
+    // resume from the checkpoint and continue the computation
+    let mut resumed = RecursiveSNARK::resume(&pp, &mut bytes.as_slice()).unwrap();
+    assert_eq!(resumed.num_steps(), 2);
+    assert_eq!(resumed.outputs(), recursive_snark.outputs());
+    for _i in 0..2 {
+      resumed.prove_step(&pp, &circuit).unwrap();
+    }
+    let mut zn_direct = z0.clone();
+    for _i in 0..4 {
+      zn_direct = circuit.output(&zn_direct);
+    }
+    assert_eq!(resumed.verify(&pp, 4, &z0), Ok(zn_direct));
+
+    // a checkpoint cannot be resumed with different public parameters
+    let pp_other = PublicParams::<E1, E2, CubicCircuit<E1::Scalar>>::setup(
+      &circuit,
+      &|shape: &R1CSShape<E1>| 2 * shape.num_cons,
+      &*default_ck_hint(),
+    )
+    .unwrap();
+    let res = RecursiveSNARK::resume(&pp_other, &mut bytes.as_slice());
+    assert_eq!(res.err(), Some(NovaError::CheckpointDigestMismatch));
+
+    // an unknown format version is rejected
+    let mut tampered = bytes.clone();
+    tampered[8] += 1;
+    let res = RecursiveSNARK::resume(&pp, &mut tampered.as_slice());
+    assert!(matches!(res, Err(NovaError::InvalidCheckpoint { .. })));
+
+    // a truncated checkpoint is rejected
+    let res = RecursiveSNARK::resume(&pp, &mut &bytes[..bytes.len() - 1]);
+    assert!(matches!(res, Err(NovaError::InvalidCheckpoint { .. })));
+
+    // a checkpoint whose instances do not match the public parameters is rejected
+    let mut malformed = recursive_snark.clone();
+    malformed.r_U_primary.X.pop();
+    let mut bytes = Vec::new();
+    malformed.checkpoint(&pp, &mut bytes).unwrap();
+    let res = RecursiveSNARK::resume(&pp, &mut bytes.as_slice());
+    assert!(matches!(res, Err(NovaError::InvalidCheckpoint { .. })));
+
+    let mut malformed = recursive_snark.clone();
+    malformed.l_u_secondary.X.push(<E2 as Engine>::Scalar::ZERO);
+    let mut bytes = Vec::new();
+    malformed.checkpoint(&pp, &mut bytes).unwrap();
+    let res = RecursiveSNARK::resume(&pp, &mut bytes.as_slice());
+    assert!(matches!(res, Err(NovaError::InvalidCheckpoint { .. })));
+  }
+
+  #[test]
+  fn test_ivc_checkpoint() {
+    test_ivc_checkpoint_with::<PallasEngine, VestaEngine>();
+    test_ivc_checkpoint_with::<Bn256EngineKZG, GrumpkinEngine>();
+    test_ivc_checkpoint_with::<Secp256k1Engine, Secq256k1Engine>();
+  }
+
   fn test_ivc_nontrivial_with_compression_with<E1, E2, EE1, EE2>()
   where
     E1: Engine<Base = <E2 as Engine>::Scalar>,
@@ -101,6 +101,7 @@ impl<E: Engine> NIFS<E> {
 
     // fold the instance using `r` and `comm_T`
     let U = U1.fold(U2, &self.comm_T, &base_as_scalar::<E>(r));
+    eprintln!("DBGN fold r {:?} U1.u {:?} u {:?} x0 {:?} W {:?}", r, U1.u, U.u, U2.X[0], U.comm_W);
 
     // return the folded instance
     Ok(U)
@@ -196,6 +197,7 @@ impl<E: Engine> NIFSRelaxed<E> {
 
     // fold the instance using `r` and `comm_T`
     let U = U1.fold_relaxed(U2, &self.comm_T, &base_as_scalar::<E>(r));
+    eprintln!("DBGN relaxed r {:?} U1.u {:?} U2.u {:?} u {:?}", r, U1.u, U2.u, U.u);
 
     // return the folded instance
     Ok(U)
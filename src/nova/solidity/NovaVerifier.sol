@@ -0,0 +1,1586 @@
+// SPDX-License-Identifier: MIT
+// This file is generated by nova-snark from the verifier key of a `CompressedSNARK`.
+// It is meant to be compiled with the IR pipeline (`solc --via-ir --optimize`).
+pragma solidity ^0.8.20;
+
+/// @title A verifier of Nova's compressed SNARKs over the BN254/Grumpkin cycle
+/// @notice Mirrors `CompressedSNARK::verify` for a single verifier key, where the primary SNARK is
+/// `spartan::ppsnark::RelaxedR1CSSNARK` with HyperKZG over BN254 and the secondary SNARK is
+/// `spartan::snark::RelaxedR1CSSNARK` with IPA over Grumpkin. The round constants of Poseidon, the
+/// R1CS matrices of the secondary circuit and the IPA generators do not fit in a contract, so they are
+/// read from the data contracts passed to the constructor, whose code hashes are fixed by the verifier
+/// key.
+///
+/// The evaluation of the secondary R1CS matrices and the multi-scalar multiplication of IPA are
+/// linear in the size of the secondary circuit and exceed the gas limit of a transaction. `start` runs
+/// every other check and emits these two checks as a claim, and each call to `resume` checks a bounded
+/// part of the claim. Once the last part is checked, the statement is marked as verified. The view
+/// function `verify` runs all the checks in a single call, which fits in the gas limit of `eth_call`.
+contract NovaVerifier {
+  /// The scalar field of BN254, which is the base field of Grumpkin
+  uint256 internal constant R_MOD = 0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001;
+  /// The base field of BN254, which is the scalar field of Grumpkin
+  uint256 internal constant Q_MOD = 0x30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47;
+
+  uint256 internal constant HASH_MASK = (1 << 250) - 1;
+  uint256 internal constant CHALLENGE_MASK = (1 << 128) - 1;
+  uint256 internal constant LIMB_MASK = (1 << 64) - 1;
+
+  // the step circuit and the public parameters
+  uint256 internal constant ARITY = {{ARITY}};
+  uint256 internal constant PP_DIGEST = {{PP_DIGEST}};
+  uint256 internal constant PROOF_WORDS = {{PROOF_WORDS}};
+  uint256 internal constant FOLDING_WORDS = 44;
+
+  // the verifier key of the primary Spartan SNARK, whose sum-check has ROUNDS_PRIMARY rounds over a
+  // circuit with 2^VARS_PRIMARY variables
+  uint256 internal constant VK_DIGEST_PRIMARY = {{VK_DIGEST_PRIMARY}};
+  uint256 internal constant ROUNDS_PRIMARY = {{ROUNDS_PRIMARY}};
+  uint256 internal constant VARS_PRIMARY = {{VARS_PRIMARY}};
+  uint256 internal constant COMM_VAL_A_X = {{COMM_VAL_A_X}};
+  uint256 internal constant COMM_VAL_A_Y = {{COMM_VAL_A_Y}};
+  uint256 internal constant COMM_VAL_B_X = {{COMM_VAL_B_X}};
+  uint256 internal constant COMM_VAL_B_Y = {{COMM_VAL_B_Y}};
+  uint256 internal constant COMM_VAL_C_X = {{COMM_VAL_C_X}};
+  uint256 internal constant COMM_VAL_C_Y = {{COMM_VAL_C_Y}};
+  uint256 internal constant COMM_ROW_X = {{COMM_ROW_X}};
+  uint256 internal constant COMM_ROW_Y = {{COMM_ROW_Y}};
+  uint256 internal constant COMM_TS_ROW_X = {{COMM_TS_ROW_X}};
+  uint256 internal constant COMM_TS_ROW_Y = {{COMM_TS_ROW_Y}};
+  uint256 internal constant COMM_COL_X = {{COMM_COL_X}};
+  uint256 internal constant COMM_COL_Y = {{COMM_COL_Y}};
+  uint256 internal constant COMM_TS_COL_X = {{COMM_TS_COL_X}};
+  uint256 internal constant COMM_TS_COL_Y = {{COMM_TS_COL_Y}};
+
+  // the verifier key of the secondary Spartan SNARK
+  uint256 internal constant VK_DIGEST_SECONDARY = {{VK_DIGEST_SECONDARY}};
+  uint256 internal constant ROUNDS_X_SECONDARY = {{ROUNDS_X_SECONDARY}};
+  uint256 internal constant ROUNDS_Y_SECONDARY = {{ROUNDS_Y_SECONDARY}};
+  uint256 internal constant ENTRIES_SECONDARY = {{ENTRIES_SECONDARY}};
+  uint256 internal constant MATRICES_SECONDARY = {{MATRICES_SECONDARY}};
+  uint256 internal constant ELL_SECONDARY =
+    ROUNDS_X_SECONDARY > ROUNDS_Y_SECONDARY - 1 ? ROUNDS_X_SECONDARY : ROUNDS_Y_SECONDARY - 1;
+
+  // the keys to derandomize commitments
+  uint256 internal constant DK_PRIMARY_X = {{DK_PRIMARY_X}};
+  uint256 internal constant DK_PRIMARY_Y = {{DK_PRIMARY_Y}};
+  uint256 internal constant DK_SECONDARY_X = {{DK_SECONDARY_X}};
+  uint256 internal constant DK_SECONDARY_Y = {{DK_SECONDARY_Y}};
+
+  // the verifier key of HyperKZG, with G2 points in the order of the pairing precompile
+  uint256 internal constant KZG_G_X = {{KZG_G_X}};
+  uint256 internal constant KZG_G_Y = {{KZG_G_Y}};
+  uint256 internal constant KZG_H_X1 = {{KZG_H_X1}};
+  uint256 internal constant KZG_H_X0 = {{KZG_H_X0}};
+  uint256 internal constant KZG_H_Y1 = {{KZG_H_Y1}};
+  uint256 internal constant KZG_H_Y0 = {{KZG_H_Y0}};
+  uint256 internal constant KZG_TAU_H_X1 = {{KZG_TAU_H_X1}};
+  uint256 internal constant KZG_TAU_H_X0 = {{KZG_TAU_H_X0}};
+  uint256 internal constant KZG_TAU_H_Y1 = {{KZG_TAU_H_Y1}};
+  uint256 internal constant KZG_TAU_H_Y0 = {{KZG_TAU_H_Y0}};
+
+  // the verifier key of IPA
+  uint256 internal constant IPA_GENERATORS = {{IPA_GENERATORS}};
+  uint256 internal constant IPA_S_X = {{IPA_S_X}};
+  uint256 internal constant IPA_S_Y = {{IPA_S_Y}};
+
+  // the constants of Poseidon, laid out as in its optimized implementation
+  uint256 internal constant POSEIDON_WIDTH = 25;
+  uint256 internal constant POSEIDON_FULL_ROUNDS = {{POSEIDON_FULL_ROUNDS}};
+  uint256 internal constant POSEIDON_PARTIAL_ROUNDS = {{POSEIDON_PARTIAL_ROUNDS}};
+  uint256 internal constant POSEIDON_MDS =
+    POSEIDON_FULL_ROUNDS * POSEIDON_WIDTH + POSEIDON_PARTIAL_ROUNDS;
+  uint256 internal constant POSEIDON_PRE_SPARSE = POSEIDON_MDS + POSEIDON_WIDTH * POSEIDON_WIDTH;
+  uint256 internal constant POSEIDON_SPARSE = POSEIDON_PRE_SPARSE + POSEIDON_WIDTH * POSEIDON_WIDTH;
+  uint256 internal constant POSEIDON_WORDS =
+    POSEIDON_SPARSE + POSEIDON_PARTIAL_ROUNDS * (2 * POSEIDON_WIDTH - 1);
+  uint256 internal constant POSEIDON_R = {{POSEIDON_R}};
+  uint256 internal constant POSEIDON_Q = {{POSEIDON_Q}};
+
+  // the data contracts, whose code is a STOP byte followed by at most CHUNK_BYTES bytes of data
+  uint256 internal constant CHUNK_BYTES = {{CHUNK_BYTES}};
+
+  // the claim left by `start`, which holds the challenge and the final claim of the inner sum-check,
+  // the evaluation of Z, a_hat, the points (rx, ry) of the matrix evaluation, the challenges of IPA
+  // and their inverses, and the terms of the multi-scalar multiplication besides the generators,
+  // each as (x, y, scalar)
+  uint256 internal constant CLAIM_RX = 4;
+  uint256 internal constant CLAIM_RY = CLAIM_RX + ROUNDS_X_SECONDARY;
+  uint256 internal constant CLAIM_RS = CLAIM_RY + ROUNDS_Y_SECONDARY;
+  uint256 internal constant CLAIM_RS_INV = CLAIM_RS + ELL_SECONDARY;
+  uint256 internal constant CLAIM_TERMS = CLAIM_RS_INV + ELL_SECONDARY;
+  uint256 internal constant TERMS = 2 + 2 * ELL_SECONDARY;
+  uint256 internal constant CLAIM_WORDS = CLAIM_TERMS + 3 * TERMS;
+
+  // the deferred checks are split into steps that each fit in the gas limit of a transaction, with
+  // a margin of about a third: the first steps evaluate the matrices and the last ones compute the
+  // multi-scalar multiplication of IPA, whose points are the generators followed by the other terms
+  uint256 internal constant ENTRIES_PER_STEP = 40000;
+  uint256 internal constant POINTS_PER_STEP = 512;
+  uint256 internal constant MATRIX_STEPS = (ENTRIES_SECONDARY + ENTRIES_PER_STEP - 1) / ENTRIES_PER_STEP;
+  uint256 internal constant MSM_POINTS = (1 << ELL_SECONDARY) + TERMS;
+  uint256 internal constant STEPS = MATRIX_STEPS + (MSM_POINTS + POINTS_PER_STEP - 1) / POINTS_PER_STEP;
+
+  /// The window of the bucket method, and the number of points below which it is not used
+  uint256 internal constant MSM_WINDOW = 6;
+  uint256 internal constant MSM_SMALL = 8;
+
+  /// An affine point, where (0, 0) is the point at infinity
+  struct Point {
+    uint256 x;
+    uint256 y;
+  }
+
+  /// A relaxed R1CS instance with two public outputs
+  struct Instance {
+    Point W;
+    Point E;
+    uint256 u;
+    uint256 x0;
+    uint256 x1;
+  }
+
+  /// The state of a `Keccak256Transcript`, where `buf` holds the bytes absorbed since the last squeeze
+  struct Transcript {
+    bytes32 lo;
+    bytes32 hi;
+    uint16 round;
+    bytes buf;
+  }
+
+  /// The single evaluation claim left to the polynomial commitment scheme, where `comm` is the
+  /// batched commitment of the primary claim
+  struct Reduced {
+    Point comm;
+    uint256[] point;
+    uint256 gamma;
+    uint256 eval;
+    uint256 cursor;
+  }
+
+  /// A verification started by `start`, where `claim` is the hash of its claim, `step` is the next
+  /// step to check, and `acc` accumulates the evaluations of the matrices, then the multi-scalar
+  /// multiplication in Jacobian coordinates
+  struct Session {
+    bytes32 claim;
+    bytes32 statement;
+    uint256 step;
+    uint256[3] acc;
+  }
+
+  error InvalidDataContracts();
+  error InvalidProof(string reason);
+  error UnknownSession();
+
+  /// Emitted when a verification starts, with the claim to pass to each call to `resume`
+  event Started(bytes32 indexed session, bytes32 indexed statement, uint256[] claim);
+  /// Emitted when the last step of a verification is checked
+  event Verified(bytes32 indexed statement);
+
+  address[] internal chunks;
+  mapping(bytes32 => Session) internal sessions;
+
+  /// @notice The statements, as hashed by `statement`, whose proofs were fully checked
+  mapping(bytes32 => bool) public verified;
+
+  constructor(address[] memory dataContracts) {
+    bytes32[{{NUM_CHUNKS}}] memory hashes = [{{CHUNK_HASHES}}];
+    if (dataContracts.length != hashes.length) revert InvalidDataContracts();
+    for (uint256 i = 0; i < hashes.length; i++) {
+      if (dataContracts[i].codehash != hashes[i]) revert InvalidDataContracts();
+    }
+    chunks = dataContracts;
+  }
+
+  /// @notice Returns the identifier of the statement that `numSteps` steps of the step circuit map
+  /// `z0` to `zn`
+  function statement(
+    uint256 numSteps,
+    uint256[] calldata z0,
+    uint256[] calldata zn
+  ) public pure returns (bytes32) {
+    return keccak256(abi.encode(numSteps, z0, zn));
+  }
+
+  /// @notice Verifies that `proof` proves `numSteps` steps of the step circuit from `z0` to `zn`
+  /// @dev Reverts with `InvalidProof` if the proof is invalid. This runs all the steps of the
+  /// verification, whose cost exceeds the gas limit of a transaction.
+  function verify(
+    uint256 numSteps,
+    uint256[] calldata z0,
+    uint256[] calldata zn,
+    uint256[] calldata proof
+  ) external view returns (bool) {
+    uint256[] memory claim = _verifyEager(numSteps, z0, zn, proof);
+    uint256[3] memory acc;
+    for (uint256 step = 0; step < STEPS; step++) {
+      _step(claim, step, acc);
+    }
+    return true;
+  }
+
+  /// @notice Checks all but the deferred checks of `proof`, and starts a session that `resume`
+  /// completes
+  /// @dev Reverts with `InvalidProof` if the proof is invalid. Restarting a session resets it.
+  function start(
+    uint256 numSteps,
+    uint256[] calldata z0,
+    uint256[] calldata zn,
+    uint256[] calldata proof
+  ) external returns (bytes32 session) {
+    uint256[] memory claim = _verifyEager(numSteps, z0, zn, proof);
+    session = keccak256(abi.encode(numSteps, z0, zn, proof));
+    Session storage s = sessions[session];
+    s.claim = keccak256(abi.encode(claim));
+    s.statement = statement(numSteps, z0, zn);
+    s.step = 0;
+    delete s.acc;
+    emit Started(session, s.statement, claim);
+  }
+
+  /// @notice Checks the next step of a session with the claim emitted when it started, and returns
+  /// whether the proof is now fully verified
+  /// @dev Reverts with `InvalidProof` if the step fails, in which case the proof is invalid
+  function resume(bytes32 session, uint256[] calldata claim) external returns (bool) {
+    Session storage s = sessions[session];
+    if (s.claim == bytes32(0) || keccak256(abi.encode(claim)) != s.claim) revert UnknownSession();
+    uint256 step = s.step;
+    uint256[3] memory acc = s.acc;
+    _step(claim, step, acc);
+
+    if (step + 1 < STEPS) {
+      s.step = step + 1;
+      s.acc = acc;
+      return false;
+    }
+    bytes32 id = s.statement;
+    delete sessions[session];
+    verified[id] = true;
+    emit Verified(id);
+    return true;
+  }
+
+  /// Runs every check but the deferred ones, and returns the claim of the deferred checks
+  function _verifyEager(
+    uint256 numSteps,
+    uint256[] calldata z0,
+    uint256[] calldata zn,
+    uint256[] calldata proof
+  ) internal view returns (uint256[] memory claim) {
+    if (numSteps == 0) revert InvalidProof("number of steps cannot be zero");
+    if (numSteps > type(uint64).max || z0.length != ARITY || zn.length != ARITY) {
+      revert InvalidProof("invalid public inputs");
+    }
+    if (proof.length != PROOF_WORDS) revert InvalidProof("invalid proof length");
+    for (uint256 i = 0; i < ARITY; i++) {
+      if (z0[i] >= R_MOD || zn[i] >= R_MOD) revert InvalidProof("non-canonical public inputs");
+    }
+
+    (Instance memory primary, Instance memory secondary) = _fold(numSteps, z0, zn, proof);
+
+    (Transcript memory t, Reduced memory red) = _ppspartan(primary, proof, FOLDING_WORDS);
+    _hyperkzg(t, red.comm, red, proof);
+
+    claim = new uint256[](CLAIM_WORDS);
+    uint256 ell = ROUNDS_PRIMARY;
+    (t, red) = _spartan(secondary, proof, red.cursor + 5 * ell + 4, claim);
+    Point memory comm = _gAdd(secondary.W, 1, secondary.E, red.gamma);
+    _ipa(t, comm, red, proof, claim);
+  }
+
+  /// Checks a step of the deferred checks described by `claim`, where `acc` is the accumulator left
+  /// by the previous step
+  function _step(uint256[] memory claim, uint256 step, uint256[3] memory acc) internal view {
+    if (step < MATRIX_STEPS) {
+      _evalMatrices(claim, step * ENTRIES_PER_STEP, acc);
+      if (step + 1 == MATRIX_STEPS) {
+        uint256 p = Q_MOD;
+        uint256 r = claim[0];
+        uint256 expected = addmod(acc[0], mulmod(r, addmod(acc[1], mulmod(r, acc[2], p), p), p), p);
+        if (claim[1] != mulmod(expected, claim[2], p)) revert InvalidProof("invalid inner sum-check");
+        delete acc[0];
+        delete acc[1];
+        delete acc[2];
+      }
+    } else {
+      _ipaMsm(claim, (step - MATRIX_STEPS) * POINTS_PER_STEP, acc);
+      if (step + 1 == STEPS && acc[2] != 0) revert InvalidProof("IPA check failed");
+    }
+  }
+
+  // ---------------------------------------------------------------------------------------------
+  // Folding
+  // ---------------------------------------------------------------------------------------------
+
+  /// Checks the hashes in the public IO of the last secondary instance, folds the running instances
+  /// as `CompressedSNARK::verify` does, and returns the derandomized folded instances
+  function _fold(
+    uint256 numSteps,
+    uint256[] calldata z0,
+    uint256[] calldata zn,
+    uint256[] calldata proof
+  ) internal view returns (Instance memory primary, Instance memory secondary) {
+    uint256[] memory poseidonR = _data(POSEIDON_R, POSEIDON_WORDS);
+    uint256[] memory poseidonQ = _data(POSEIDON_Q, POSEIDON_WORDS);
+
+    Instance memory rU2 = _relaxedSecondary(proof, 0);
+    Instance memory lu2 = Instance(
+      _grumpkin(proof, 8),
+      Point(0, 0),
+      1,
+      _scalar(proof, 10, Q_MOD),
+      _scalar(proof, 11, Q_MOD)
+    );
+    Instance memory rU1 = _relaxedPrimary(proof, 23);
+
+    _checkHashes(numSteps, z0, zn, proof, rU2, rU1, lu2, poseidonR, poseidonQ);
+
+    // fold the last secondary instance into the running one
+    Point memory T = _grumpkin(proof, 12);
+    uint256[] memory input = new uint256[](9);
+    input[0] = PP_DIGEST;
+    _absorbPoint(input, 1, lu2.W);
+    input[4] = lu2.x0 % R_MOD;
+    input[5] = lu2.x1 % R_MOD;
+    _absorbPoint(input, 6, T);
+    uint256 r = _poseidon(input, poseidonR, R_MOD) & CHALLENGE_MASK;
+    secondary = _foldSecondary(rU2, lu2, T, 0, r);
+
+    // fold the random secondary instance
+    T = _grumpkin(proof, 21);
+    Instance memory lur2 = _relaxedSecondary(proof, 14);
+    r = _poseidon(_relaxedInput(secondary, lur2, T, R_MOD), poseidonR, R_MOD) & CHALLENGE_MASK;
+    secondary = _foldSecondary(secondary, lur2, T, mulmod(r, r, Q_MOD), r);
+
+    // fold the random primary instance
+    T = _g1(proof, 38);
+    Instance memory lur1 = _relaxedPrimary(proof, 31);
+    r = _poseidon(_relaxedInput(rU1, lur1, T, Q_MOD), poseidonQ, Q_MOD) & CHALLENGE_MASK;
+    primary = _foldPrimary(rU1, lur1, T, r);
+
+    // derandomize the commitments
+    Point memory h = Point(DK_PRIMARY_X, DK_PRIMARY_Y);
+    primary.W = _g1Add(primary.W, _g1Neg(_g1Mul(h, _scalar(proof, 40, R_MOD))));
+    primary.E = _g1Add(primary.E, _g1Neg(_g1Mul(h, _scalar(proof, 41, R_MOD))));
+    Point memory hs = Point(DK_SECONDARY_X, DK_SECONDARY_Y);
+    secondary.W = _gAdd(secondary.W, 1, hs, Q_MOD - _scalar(proof, 42, Q_MOD));
+    secondary.E = _gAdd(secondary.E, 1, hs, Q_MOD - _scalar(proof, 43, Q_MOD));
+  }
+
+  /// Checks that the public IO of the last secondary instance hashes the running instances
+  function _checkHashes(
+    uint256 numSteps,
+    uint256[] calldata z0,
+    uint256[] calldata zn,
+    uint256[] calldata proof,
+    Instance memory rU2,
+    Instance memory rU1,
+    Instance memory lu2,
+    uint256[] memory poseidonR,
+    uint256[] memory poseidonQ
+  ) internal pure {
+    uint256[] memory input = new uint256[](18 + 2 * ARITY);
+    input[0] = PP_DIGEST;
+    input[1] = numSteps;
+    for (uint256 i = 0; i < ARITY; i++) {
+      input[2 + i] = z0[i];
+      input[2 + ARITY + i] = zn[i];
+    }
+    _absorbRelaxed(input, 2 + 2 * ARITY, rU2, R_MOD);
+    input[17 + 2 * ARITY] = _scalar(proof, 30, R_MOD);
+    if ((_poseidon(input, poseidonR, R_MOD) & HASH_MASK) != lu2.x0 % R_MOD) {
+      revert InvalidProof("invalid output hash in R1CS instances");
+    }
+
+    input = new uint256[](20);
+    input[0] = PP_DIGEST;
+    input[1] = numSteps;
+    _absorbRelaxed(input, 4, rU1, Q_MOD);
+    input[19] = _scalar(proof, 7, Q_MOD);
+    if ((_poseidon(input, poseidonQ, Q_MOD) & HASH_MASK) != lu2.x1) {
+      revert InvalidProof("invalid output hash in R1CS instances");
+    }
+  }
+
+  /// Returns the input of the random oracle that folds two relaxed instances
+  function _relaxedInput(
+    Instance memory U1,
+    Instance memory U2,
+    Point memory T,
+    uint256 p
+  ) internal pure returns (uint256[] memory input) {
+    input = new uint256[](34);
+    input[0] = PP_DIGEST;
+    _absorbRelaxed(input, 1, U1, p);
+    _absorbRelaxed(input, 16, U2, p);
+    _absorbPoint(input, 31, T);
+  }
+
+  /// Writes a relaxed instance as absorbed by a random oracle over the field of modulus `p`
+  function _absorbRelaxed(uint256[] memory input, uint256 pos, Instance memory U, uint256 p) internal pure {
+    _absorbPoint(input, pos, U.W);
+    _absorbPoint(input, pos + 3, U.E);
+    input[pos + 6] = U.u % p;
+    for (uint256 i = 0; i < 4; i++) {
+      input[pos + 7 + i] = (U.x0 >> (64 * i)) & LIMB_MASK;
+      input[pos + 11 + i] = (U.x1 >> (64 * i)) & LIMB_MASK;
+    }
+  }
+
+  /// Writes a commitment as absorbed by a random oracle
+  function _absorbPoint(uint256[] memory input, uint256 pos, Point memory P) internal pure {
+    input[pos] = P.x;
+    input[pos + 1] = P.y;
+    input[pos + 2] = _isInfinity(P) ? 1 : 0;
+  }
+
+  /// Folds `U2` into `U1` over Grumpkin, with `U1.E + r * T + r2 * U2.E` as the error commitment
+  function _foldSecondary(
+    Instance memory U1,
+    Instance memory U2,
+    Point memory T,
+    uint256 r2,
+    uint256 r
+  ) internal view returns (Instance memory U) {
+    U.W = _gAdd(U1.W, 1, U2.W, r);
+    uint256[] memory pts = new uint256[](6);
+    (pts[0], pts[1], pts[2], pts[3], pts[4], pts[5]) = (U1.E.x, U1.E.y, T.x, T.y, U2.E.x, U2.E.y);
+    uint256[] memory ks = new uint256[](3);
+    (ks[0], ks[1], ks[2]) = (1, r, r2);
+    uint256[3] memory acc;
+    _msm(acc, pts, ks);
+    U.E = _jAffine(acc);
+    U.u = addmod(U1.u, mulmod(r, U2.u, Q_MOD), Q_MOD);
+    U.x0 = addmod(U1.x0, mulmod(r, U2.x0, Q_MOD), Q_MOD);
+    U.x1 = addmod(U1.x1, mulmod(r, U2.x1, Q_MOD), Q_MOD);
+  }
+
+  /// Folds the relaxed instance `U2` into `U1` over BN254
+  function _foldPrimary(
+    Instance memory U1,
+    Instance memory U2,
+    Point memory T,
+    uint256 r
+  ) internal view returns (Instance memory U) {
+    U.W = _g1Add(U1.W, _g1Mul(U2.W, r));
+    U.E = _g1Add(_g1Add(U1.E, _g1Mul(T, r)), _g1Mul(U2.E, mulmod(r, r, R_MOD)));
+    U.u = addmod(U1.u, mulmod(r, U2.u, R_MOD), R_MOD);
+    U.x0 = addmod(U1.x0, mulmod(r, U2.x0, R_MOD), R_MOD);
+    U.x1 = addmod(U1.x1, mulmod(r, U2.x1, R_MOD), R_MOD);
+  }
+
+  function _relaxedPrimary(uint256[] calldata proof, uint256 pos) internal pure returns (Instance memory) {
+    return
+      Instance(
+        _g1(proof, pos),
+        _g1(proof, pos + 2),
+        _scalar(proof, pos + 4, R_MOD),
+        _scalar(proof, pos + 5, R_MOD),
+        _scalar(proof, pos + 6, R_MOD)
+      );
+  }
+
+  function _relaxedSecondary(uint256[] calldata proof, uint256 pos) internal pure returns (Instance memory) {
+    return
+      Instance(
+        _grumpkin(proof, pos),
+        _grumpkin(proof, pos + 2),
+        _scalar(proof, pos + 4, Q_MOD),
+        _scalar(proof, pos + 5, Q_MOD),
+        _scalar(proof, pos + 6, Q_MOD)
+      );
+  }
+
+  // ---------------------------------------------------------------------------------------------
+  // Preprocessing Spartan over BN254
+  // ---------------------------------------------------------------------------------------------
+
+  /// Verifies the preprocessing Spartan SNARK of the primary instance `U` that starts at word `cur` of
+  /// the proof, and returns the transcript along with the evaluation claim left to HyperKZG
+  function _ppspartan(
+    Instance memory U,
+    uint256[] calldata proof,
+    uint256 cur
+  ) internal view returns (Transcript memory t, Reduced memory red) {
+    uint256 p = R_MOD;
+    t = _newTranscript("RelaxedR1CSSNARK");
+    _absorb(t, "vk", abi.encodePacked(VK_DIGEST_PRIMARY));
+    _absorb(t, "U", abi.encodePacked(_commitmentBytes(U.W), _commitmentBytes(U.E), U.u, U.x0, U.x1));
+
+    // the commitments to Az, Bz, Cz, L_row, L_col, and to the inverses of the memory checks
+    _absorb(t, "c", _g1CommitmentBytes(proof, cur, 3));
+    uint256[] memory tau = new uint256[](ROUNDS_PRIMARY);
+    for (uint256 i = 0; i < ROUNDS_PRIMARY; i++) {
+      tau[i] = _squeeze(t, "t", p);
+    }
+    uint256 evals = cur + 18;
+    _absorb(t, "e", abi.encodePacked(_scalar(proof, evals, p), _scalar(proof, evals + 1, p), _scalar(proof, evals + 2, p)));
+    _absorb(t, "e", _g1CommitmentBytes(proof, cur + 6, 2));
+
+    // (c, gamma, r) are kept in `ch`, followed by the powers of s
+    uint256[] memory ch = new uint256[](13);
+    ch[0] = _squeeze(t, "c", p);
+    uint256 claim = addmod(
+      proof[evals],
+      mulmod(ch[0], addmod(proof[evals + 1], mulmod(ch[0], proof[evals + 2], p), p), p),
+      p
+    );
+    ch[1] = _squeeze(t, "g", p);
+    ch[2] = _squeeze(t, "r", p);
+
+    _absorb(t, "l", _g1CommitmentBytes(proof, cur + 10, 4));
+    uint256[] memory rho = new uint256[](ROUNDS_PRIMARY);
+    for (uint256 i = 0; i < ROUNDS_PRIMARY; i++) {
+      rho[i] = _squeeze(t, "r", p);
+    }
+    uint256 s = _squeeze(t, "r", p);
+    ch[3] = 1;
+    for (uint256 i = 4; i < 13; i++) {
+      ch[i] = mulmod(ch[i - 1], s, p);
+    }
+    claim = mulmod(addmod(ch[10], ch[11], p), claim, p);
+
+    uint256 e;
+    (e, red.point) = _sumcheck(t, proof, evals + 3, claim, ROUNDS_PRIMARY, 3, p);
+    evals += 3 + 3 * ROUNDS_PRIMARY;
+
+    uint256[] memory ev = new uint256[](18);
+    for (uint256 i = 0; i < 18; i++) {
+      ev[i] = _scalar(proof, evals + i, p);
+    }
+    if (e != _ppExpected(U, ev, ch, tau, rho, red.point)) revert InvalidProof("invalid sum-check");
+
+    bytes memory repr;
+    for (uint256 i = 0; i < 18; i++) {
+      repr = abi.encodePacked(repr, ev[i]);
+    }
+    _absorb(t, "e", repr);
+    uint256 c = _squeeze(t, "c", p);
+
+    // batch the claims with the powers of c, in the order of `ev`
+    Point memory C = U.W;
+    red.eval = ev[0];
+    uint256 ci = 1;
+    for (uint256 i = 1; i < 18; i++) {
+      ci = mulmod(ci, c, p);
+      C = _g1Add(C, _g1Mul(_ppCommitment(i, U, proof, cur), ci));
+      red.eval = addmod(red.eval, mulmod(ci, ev[i], p), p);
+    }
+    red.comm = C;
+    red.cursor = evals + 18;
+  }
+
+  /// Returns the commitment to the polynomial of the evaluation `ev[i]` of the preprocessing Spartan
+  /// SNARK, where those of the proof start at word `cur` in the order Az, Bz, Cz, L_row, L_col,
+  /// followed by the inverses of the memory checks
+  function _ppCommitment(
+    uint256 i,
+    Instance memory U,
+    uint256[] calldata proof,
+    uint256 cur
+  ) internal pure returns (Point memory) {
+    if (i == 0) return U.W;
+    if (i == 4) return U.E;
+    if (i == 7) return Point(COMM_VAL_A_X, COMM_VAL_A_Y);
+    if (i == 8) return Point(COMM_VAL_B_X, COMM_VAL_B_Y);
+    if (i == 9) return Point(COMM_VAL_C_X, COMM_VAL_C_Y);
+    if (i == 11) return Point(COMM_ROW_X, COMM_ROW_Y);
+    if (i == 13) return Point(COMM_TS_ROW_X, COMM_TS_ROW_Y);
+    if (i == 15) return Point(COMM_COL_X, COMM_COL_Y);
+    if (i == 17) return Point(COMM_TS_COL_X, COMM_TS_COL_Y);
+    uint256 j = i < 4 ? i - 1 : i < 7 ? i - 2 : (i - 10) / 2 + 5;
+    return _g1(proof, cur + 2 * j);
+  }
+
+  /// Returns the final claim expected from the sum-check of the preprocessing Spartan SNARK, given
+  /// the evaluations `ev` at its challenges `r`, where `ch` holds (c, gamma, r) and the powers of s
+  function _ppExpected(
+    Instance memory U,
+    uint256[] memory ev,
+    uint256[] memory ch,
+    uint256[] memory tau,
+    uint256[] memory rho,
+    uint256[] memory r
+  ) internal pure returns (uint256 expected) {
+    uint256 p = R_MOD;
+    uint256 eqTau = _eqEval(tau, 0, r, 0, p);
+    uint256 eqRho = _eqEval(rho, 0, r, 0, p);
+
+    // the identity polynomial, and the evaluation of Z = (W, u, X) padded to the length of the
+    // memory, which is twice the number of variables
+    uint256 addr = 0;
+    for (uint256 i = 0; i < ROUNDS_PRIMARY; i++) {
+      addr = addmod(addr, mulmod(r[i], 1 << (ROUNDS_PRIMARY - 1 - i), p), p);
+    }
+    uint256 l = ROUNDS_PRIMARY - VARS_PRIMARY - 1;
+    uint256 z = mulmod(_lagrange0(r, l, p), mulmod(r[l], _evalX(U, r, l + 1, p), p), p);
+    z = addmod(ev[0], z, p);
+
+    // t + r and w + r, for the rows and then the columns
+    uint256 gamma = ch[1];
+    uint256[4] memory m = [
+      addmod(addmod(addr, mulmod(gamma, eqTau, p), p), ch[2], p),
+      addmod(addmod(ev[11], mulmod(gamma, ev[5], p), p), ch[2], p),
+      addmod(addmod(addr, mulmod(gamma, z, p), p), ch[2], p),
+      addmod(addmod(ev[15], mulmod(gamma, ev[6], p), p), ch[2], p)
+    ];
+
+    // the memory checks
+    expected = addmod(
+      mulmod(ch[3], addmod(ev[10], p - ev[12], p), p),
+      mulmod(ch[4], addmod(ev[14], p - ev[16], p), p),
+      p
+    );
+    uint256 inner = addmod(
+      mulmod(ch[5], addmod(mulmod(ev[10], m[0], p), p - ev[13], p), p),
+      mulmod(ch[6], addmod(mulmod(ev[12], m[1], p), p - 1, p), p),
+      p
+    );
+    inner = addmod(inner, mulmod(ch[7], addmod(mulmod(ev[14], m[2], p), p - ev[17], p), p), p);
+    inner = addmod(inner, mulmod(ch[8], addmod(mulmod(ev[16], m[3], p), p - 1, p), p), p);
+    expected = addmod(expected, mulmod(eqRho, inner, p), p);
+
+    // the outer sum-check, with the claims about Az, Bz, Cz batched with c
+    uint256 c = ch[0];
+    uint256 outer = addmod(mulmod(ev[1], ev[2], p), p - addmod(mulmod(U.u, ev[3], p), ev[4], p), p);
+    outer = mulmod(ch[9], outer, p);
+    uint256 batched = addmod(ev[1], mulmod(c, addmod(ev[2], mulmod(c, ev[3], p), p), p), p);
+    outer = addmod(outer, mulmod(ch[10], batched, p), p);
+    expected = addmod(expected, mulmod(eqTau, outer, p), p);
+
+    // the inner sum-check
+    batched = addmod(ev[7], mulmod(c, addmod(ev[8], mulmod(c, ev[9], p), p), p), p);
+    expected = addmod(expected, mulmod(ch[11], mulmod(mulmod(ev[5], ev[6], p), batched, p), p), p);
+
+    // the witness is zero beyond the number of variables, where eq(tau, .) is masked
+    uint256 split = ROUNDS_PRIMARY - VARS_PRIMARY;
+    uint256 eqLo = 1;
+    uint256 maskLo = 1;
+    for (uint256 i = 0; i < split; i++) {
+      uint256 a = addmod(1, p - tau[i], p);
+      uint256 b = addmod(1, p - r[i], p);
+      eqLo = mulmod(eqLo, addmod(mulmod(tau[i], r[i], p), mulmod(a, b, p), p), p);
+      maskLo = mulmod(maskLo, mulmod(a, b, p), p);
+    }
+    uint256 masked = mulmod(addmod(eqLo, p - maskLo, p), _eqEval(tau, split, r, split, p), p);
+    expected = addmod(expected, mulmod(ch[12], mulmod(masked, ev[0], p), p), p);
+  }
+
+  /// Returns the transcript encoding of `n` commitments of BN254 starting at word `pos` of the proof
+  function _g1CommitmentBytes(uint256[] calldata proof, uint256 pos, uint256 n) internal pure returns (bytes memory repr) {
+    for (uint256 i = 0; i < n; i++) {
+      repr = abi.encodePacked(repr, _commitmentBytes(_g1(proof, pos + 2 * i)));
+    }
+  }
+
+  // ---------------------------------------------------------------------------------------------
+  // Spartan over Grumpkin
+  // ---------------------------------------------------------------------------------------------
+
+  /// Runs the sum-checks of the Spartan proof of the secondary instance `U` starting at word `cur` of
+  /// the proof, and returns the transcript along with the evaluation claim left to IPA. The check of
+  /// the inner sum-check, which evaluates the matrices, is deferred to `claim`.
+  function _spartan(
+    Instance memory U,
+    uint256[] calldata proof,
+    uint256 cur,
+    uint256[] memory claim
+  ) internal pure returns (Transcript memory t, Reduced memory red) {
+    t = _newTranscript("RelaxedR1CSSNARK");
+    _absorb(t, "vk", abi.encodePacked(VK_DIGEST_SECONDARY));
+    _absorb(t, "U", abi.encodePacked(_commitmentBytes(U.W), _commitmentBytes(U.E), U.u, U.x0, U.x1));
+
+    (uint256[] memory rx, uint256[4] memory claims) = _outer(t, proof, cur, U.u);
+    cur += 3 * ROUNDS_X_SECONDARY + 4;
+
+    (uint256[] memory ry, uint256 evalW) = _inner(t, proof, cur, U, claims, claim);
+    cur += 2 * ROUNDS_Y_SECONDARY + 1;
+    for (uint256 i = 0; i < ROUNDS_X_SECONDARY; i++) {
+      claim[CLAIM_RX + i] = rx[i];
+    }
+    for (uint256 i = 0; i < ROUNDS_Y_SECONDARY; i++) {
+      claim[CLAIM_RY + i] = ry[i];
+    }
+
+    red = _batch(t, proof, cur, rx, ry, evalW, claims[3]);
+  }
+
+  /// Verifies the outer sum-check, and returns its challenges and the claims about Az, Bz, Cz and E
+  function _outer(
+    Transcript memory t,
+    uint256[] calldata proof,
+    uint256 cur,
+    uint256 u
+  ) internal pure returns (uint256[] memory rx, uint256[4] memory claims) {
+    uint256 p = Q_MOD;
+    uint256[] memory tau = new uint256[](ROUNDS_X_SECONDARY);
+    for (uint256 i = 0; i < ROUNDS_X_SECONDARY; i++) {
+      tau[i] = _squeeze(t, "t", p);
+    }
+
+    uint256 e;
+    (e, rx) = _sumcheck(t, proof, cur, 0, ROUNDS_X_SECONDARY, 3, p);
+    cur += 3 * ROUNDS_X_SECONDARY;
+    for (uint256 i = 0; i < 4; i++) {
+      claims[i] = _scalar(proof, cur + i, p);
+    }
+
+    uint256 expected = addmod(
+      mulmod(claims[0], claims[1], p),
+      p - addmod(mulmod(u, claims[2], p), claims[3], p),
+      p
+    );
+    if (e != mulmod(_eqEval(tau, 0, rx, 0, p), expected, p)) revert InvalidProof("invalid outer sum-check");
+
+    _absorb(t, "claims_outer", abi.encodePacked(claims[0], claims[1], claims[2], claims[3]));
+  }
+
+  /// Verifies the inner sum-check up to the evaluation of the matrices, and returns its challenges
+  /// and the claim about W
+  function _inner(
+    Transcript memory t,
+    uint256[] calldata proof,
+    uint256 cur,
+    Instance memory U,
+    uint256[4] memory claims,
+    uint256[] memory claim
+  ) internal pure returns (uint256[] memory ry, uint256 evalW) {
+    uint256 p = Q_MOD;
+    uint256 r = _squeeze(t, "r", p);
+    uint256 joint = addmod(claims[0], mulmod(r, addmod(claims[1], mulmod(r, claims[2], p), p), p), p);
+
+    uint256 e;
+    (e, ry) = _sumcheck(t, proof, cur, joint, ROUNDS_Y_SECONDARY, 2, p);
+    evalW = _scalar(proof, cur + 2 * ROUNDS_Y_SECONDARY, p);
+
+    // the final claim must be (A + r * B + r^2 * C)(rx, ry) * Z(ry)
+    claim[0] = r;
+    claim[1] = e;
+    claim[2] = addmod(mulmod(addmod(1, p - ry[0], p), evalW, p), mulmod(ry[0], _evalX(U, ry, 1, p), p), p);
+  }
+
+  /// Verifies the sum-check that batches the claims about W and E into a single evaluation claim
+  function _batch(
+    Transcript memory t,
+    uint256[] calldata proof,
+    uint256 cur,
+    uint256[] memory rx,
+    uint256[] memory ry,
+    uint256 evalW,
+    uint256 evalE
+  ) internal pure returns (Reduced memory red) {
+    uint256 p = Q_MOD;
+    uint256 nW = ROUNDS_Y_SECONDARY - 1;
+    uint256 nE = ROUNDS_X_SECONDARY;
+    uint256 ell = ELL_SECONDARY;
+    uint256 rho = _squeeze(t, "r", p);
+
+    // each claim is scaled by 2^(ell - n_i) to account for the padding
+    uint256 claim = addmod(
+      mulmod(evalW, 1 << (ell - nW), p),
+      mulmod(rho, mulmod(evalE, 1 << (ell - nE), p), p),
+      p
+    );
+    uint256 e;
+    (e, red.point) = _sumcheck(t, proof, cur, claim, ell, 2, p);
+    cur += 2 * ell;
+
+    uint256 b0 = _scalar(proof, cur, p);
+    uint256 b1 = _scalar(proof, cur + 1, p);
+    uint256 expected = addmod(
+      mulmod(_eqEval(red.point, ell - nW, ry, 1, p), b0, p),
+      mulmod(rho, mulmod(_eqEval(red.point, ell - nE, rx, 0, p), b1, p), p),
+      p
+    );
+    if (e != expected) revert InvalidProof("invalid batching sum-check");
+
+    _absorb(t, "l", abi.encodePacked(b0, b1));
+    red.gamma = _squeeze(t, "g", p);
+
+    // each claim is rescaled by the first Lagrange polynomial over the variables it does not use
+    red.eval = addmod(
+      mulmod(_lagrange0(red.point, ell - nW, p), b0, p),
+      mulmod(red.gamma, mulmod(_lagrange0(red.point, ell - nE, p), b1, p), p),
+      p
+    );
+    red.cursor = cur + 2;
+  }
+
+  /// Adds the entries of the matrices from `start` on, up to ENTRIES_PER_STEP of them, to their
+  /// evaluations at (rx, ry) in `acc`
+  function _evalMatrices(uint256[] memory claim, uint256 start, uint256[3] memory acc) internal view {
+    uint256 n = ENTRIES_SECONDARY - start;
+    if (n > ENTRIES_PER_STEP) n = ENTRIES_PER_STEP;
+    uint256[] memory buf = _data(MATRICES_SECONDARY + 2 * start, 2 * n);
+
+    // eq(rx, row) and eq(ry, col) are products of tables over the high and the low bits
+    uint256 xLo = ROUNDS_X_SECONDARY / 2;
+    uint256 yLo = ROUNDS_Y_SECONDARY / 2;
+    uint256[] memory txHi = _eqEvals(claim, CLAIM_RX, ROUNDS_X_SECONDARY - xLo, Q_MOD);
+    uint256[] memory txLo = _eqEvals(claim, CLAIM_RX + ROUNDS_X_SECONDARY - xLo, xLo, Q_MOD);
+    uint256[] memory tyHi = _eqEvals(claim, CLAIM_RY, ROUNDS_Y_SECONDARY - yLo, Q_MOD);
+    uint256[] memory tyLo = _eqEvals(claim, CLAIM_RY + ROUNDS_Y_SECONDARY - yLo, yLo, Q_MOD);
+
+    assembly ("memory-safe") {
+      let p := Q_MOD
+      let xMask := sub(shl(xLo, 1), 1)
+      let yMask := sub(shl(yLo, 1), 1)
+      let entry := add(buf, 0x20)
+      let end := add(entry, shl(6, n))
+      for {} lt(entry, end) { entry := add(entry, 0x40) } {
+        // each entry is (matrix << 128 | row << 64 | col, val)
+        let key := mload(entry)
+        let row := and(shr(64, key), 0xffffffffffffffff)
+        let col := and(key, 0xffffffffffffffff)
+        let ex := mulmod(
+          mload(add(txHi, shl(5, add(shr(xLo, row), 1)))),
+          mload(add(txLo, shl(5, add(and(row, xMask), 1)))),
+          p
+        )
+        let ey := mulmod(
+          mload(add(tyHi, shl(5, add(shr(yLo, col), 1)))),
+          mload(add(tyLo, shl(5, add(and(col, yMask), 1)))),
+          p
+        )
+        let slot := add(acc, shl(5, shr(128, key)))
+        mstore(slot, addmod(mload(slot), mulmod(mulmod(ex, ey, p), mload(add(entry, 0x20)), p), p))
+      }
+    }
+  }
+
+  // ---------------------------------------------------------------------------------------------
+  // Sum-check
+  // ---------------------------------------------------------------------------------------------
+
+  /// Verifies a sum-check proof of `rounds` rounds starting at word `cur` of the proof, whose round
+  /// polynomials are given by all their coefficients but the linear one
+  function _sumcheck(
+    Transcript memory t,
+    uint256[] calldata proof,
+    uint256 cur,
+    uint256 claim,
+    uint256 rounds,
+    uint256 degree,
+    uint256 p
+  ) internal pure returns (uint256 e, uint256[] memory r) {
+    e = claim;
+    r = new uint256[](rounds);
+    uint256[] memory coeffs = new uint256[](degree + 1);
+    for (uint256 i = 0; i < rounds; i++) {
+      bytes memory repr;
+      uint256 linear = e;
+      for (uint256 k = 0; k < degree; k++) {
+        uint256 v = _scalar(proof, cur + i * degree + k, p);
+        // the transcript absorbs coefficients in little-endian order
+        repr = abi.encodePacked(repr, _reverse(v));
+        coeffs[k == 0 ? 0 : k + 1] = v;
+        linear = addmod(linear, p - v, p);
+        if (k == 0) linear = addmod(linear, p - v, p);
+      }
+      coeffs[1] = linear;
+
+      _absorb(t, "p", repr);
+      uint256 ri = _squeeze(t, "c", p);
+      r[i] = ri;
+
+      e = 0;
+      for (uint256 k = degree + 1; k > 0; k--) {
+        e = addmod(mulmod(e, ri, p), coeffs[k - 1], p);
+      }
+    }
+  }
+
+  // ---------------------------------------------------------------------------------------------
+  // HyperKZG over BN254
+  // ---------------------------------------------------------------------------------------------
+
+  /// Verifies a HyperKZG evaluation argument for the commitment `C`
+  function _hyperkzg(Transcript memory t, Point memory C, Reduced memory red, uint256[] calldata proof) internal view {
+    uint256 ell = red.point.length;
+    uint256 com = red.cursor;
+    uint256 w = com + 2 * (ell - 1);
+    uint256 v = w + 6;
+
+    _absorb(t, "c", _g1Bytes(proof, com, ell - 1));
+    uint256 r = _squeeze(t, "c", R_MOD);
+    _checkHyperKzgEvals(proof, v, red, r);
+
+    bytes memory repr;
+    for (uint256 i = 0; i < 3 * ell; i++) {
+      repr = abi.encodePacked(repr, _scalar(proof, v + i, R_MOD));
+    }
+    _absorb(t, "v", repr);
+    uint256 q = _squeeze(t, "r", R_MOD);
+
+    _absorb(t, "W", _g1Bytes(proof, w, 3));
+    uint256 d0 = _squeeze(t, "d", R_MOD);
+    uint256 d1 = mulmod(d0, d0, R_MOD);
+
+    // L = (1 + d0 + d1) * sum_i q^i * com_i + u0 * W0 + u1 * d0 * W1 + u2 * d1 * W2 - B * G,
+    // where com_0 = C, u = (r, -r, r^2), and B = B(u0) + d0 * B(u1) + d1 * B(u2)
+    uint256 qi = addmod(addmod(1, d0, R_MOD), d1, R_MOD);
+    Point memory L = _g1Mul(C, qi);
+    for (uint256 i = 1; i < ell; i++) {
+      qi = mulmod(qi, q, R_MOD);
+      L = _g1Add(L, _g1Mul(_g1(proof, com + 2 * (i - 1)), qi));
+    }
+    L = _g1Add(L, _g1Mul(_g1(proof, w), r));
+    L = _g1Add(L, _g1Mul(_g1(proof, w + 2), mulmod(R_MOD - r, d0, R_MOD)));
+    L = _g1Add(L, _g1Mul(_g1(proof, w + 4), mulmod(mulmod(r, r, R_MOD), d1, R_MOD)));
+
+    uint256[3] memory B;
+    for (uint256 j = ell; j > 0; j--) {
+      for (uint256 k = 0; k < 3; k++) {
+        B[k] = addmod(mulmod(B[k], q, R_MOD), proof[v + 3 * (j - 1) + k], R_MOD);
+      }
+    }
+    uint256 b = addmod(B[0], addmod(mulmod(d0, B[1], R_MOD), mulmod(d1, B[2], R_MOD), R_MOD), R_MOD);
+    L = _g1Add(L, _g1Mul(Point(KZG_G_X, KZG_G_Y), (R_MOD - b) % R_MOD));
+
+    // R = W0 + d0 * W1 + d1 * W2
+    Point memory R = _g1Add(_g1(proof, w), _g1Mul(_g1(proof, w + 2), d0));
+    R = _g1Add(R, _g1Mul(_g1(proof, w + 4), d1));
+
+    if (!_pairing(L, R)) revert InvalidProof("pairing check failed");
+  }
+
+  /// Checks the consistency of the evaluations (Y, ypos, yneg) of the intermediate polynomials
+  function _checkHyperKzgEvals(uint256[] calldata proof, uint256 v, Reduced memory red, uint256 r) internal pure {
+    uint256 ell = red.point.length;
+    for (uint256 i = 0; i < ell; i++) {
+      uint256 ypos = _scalar(proof, v + 3 * i, R_MOD);
+      uint256 yneg = _scalar(proof, v + 3 * i + 1, R_MOD);
+      uint256 Y = i + 1 < ell ? _scalar(proof, v + 3 * (i + 1) + 2, R_MOD) : red.eval;
+      uint256 x = red.point[ell - i - 1];
+      uint256 rhs = addmod(
+        mulmod(mulmod(r, addmod(1, R_MOD - x, R_MOD), R_MOD), addmod(ypos, yneg, R_MOD), R_MOD),
+        mulmod(x, addmod(ypos, R_MOD - yneg, R_MOD), R_MOD),
+        R_MOD
+      );
+      if (mulmod(addmod(r, r, R_MOD), Y, R_MOD) != rhs) revert InvalidProof("inconsistent (Y, ypos, yneg)");
+    }
+  }
+
+  /// Returns the transcript encoding of `n` points of BN254 starting at word `pos` of the proof
+  function _g1Bytes(uint256[] calldata proof, uint256 pos, uint256 n) internal pure returns (bytes memory repr) {
+    for (uint256 i = 0; i < n; i++) {
+      Point memory P = _g1(proof, pos + 2 * i);
+      repr = abi.encodePacked(repr, P.x, P.y);
+    }
+  }
+
+  // ---------------------------------------------------------------------------------------------
+  // IPA over Grumpkin
+  // ---------------------------------------------------------------------------------------------
+
+  /// Runs the inner product argument for the commitment `comm` up to its final check, which is
+  /// deferred to `claim` as the multi-scalar multiplication
+  /// a_hat * sum_i s_i * G_i + r * (a_hat * b_hat - eval) * S - comm - sum_i (r_i^2 * L_i + r_i^-2 * R_i),
+  /// which must be the point at infinity
+  function _ipa(
+    Transcript memory t,
+    Point memory comm,
+    Reduced memory red,
+    uint256[] calldata proof,
+    uint256[] memory claim
+  ) internal view {
+    uint256 ell = ELL_SECONDARY;
+    uint256 cur = red.cursor;
+
+    _domSep(t, "IPA");
+    _absorb(t, "U", abi.encodePacked(_commitmentBytes(comm), red.eval));
+    uint256 r = _squeeze(t, "r", Q_MOD);
+
+    // b_hat = <eq(point), s> = prod_i ((1 - x_i) * r_i^-1 + x_i * r_i)
+    uint256 bHat = 1;
+    for (uint256 i = 0; i < ell; i++) {
+      Point memory Li = _grumpkin(proof, cur + 2 * i);
+      Point memory Ri = _grumpkin(proof, cur + 2 * (ell + i));
+      _absorb(t, "L", _commitmentBytes(Li));
+      _absorb(t, "R", _commitmentBytes(Ri));
+      uint256 ri = _squeeze(t, "r", Q_MOD);
+      if (ri == 0) revert InvalidProof("zero challenge in IPA");
+      uint256 riInv = _inv(ri, Q_MOD);
+      claim[CLAIM_RS + i] = ri;
+      claim[CLAIM_RS_INV + i] = riInv;
+
+      uint256 x = red.point[i];
+      bHat = mulmod(bHat, addmod(mulmod(addmod(1, Q_MOD - x, Q_MOD), riInv, Q_MOD), mulmod(x, ri, Q_MOD), Q_MOD), Q_MOD);
+      _setTerm(claim, 2 + 2 * i, Li, Q_MOD - mulmod(ri, ri, Q_MOD));
+      _setTerm(claim, 3 + 2 * i, Ri, Q_MOD - mulmod(riInv, riInv, Q_MOD));
+    }
+    uint256 aHat = _scalar(proof, cur + 4 * ell, Q_MOD);
+    claim[3] = aHat;
+
+    uint256 k = mulmod(r, addmod(mulmod(aHat, bHat, Q_MOD), Q_MOD - red.eval, Q_MOD), Q_MOD);
+    _setTerm(claim, 0, Point(IPA_S_X, IPA_S_Y), k);
+    _setTerm(claim, 1, comm, Q_MOD - 1);
+  }
+
+  function _setTerm(uint256[] memory claim, uint256 i, Point memory P, uint256 k) internal pure {
+    claim[CLAIM_TERMS + 3 * i] = P.x;
+    claim[CLAIM_TERMS + 3 * i + 1] = P.y;
+    claim[CLAIM_TERMS + 3 * i + 2] = k;
+  }
+
+  /// Adds the points of the multi-scalar multiplication from `start` on, up to POINTS_PER_STEP of
+  /// them, to `acc`. The scalar of the generator G_i is a_hat * s_i, where s_i is the product of r_j
+  /// or r_j^-1 depending on whether bit j of i, from the most significant one, is set.
+  function _ipaMsm(uint256[] memory claim, uint256 start, uint256[3] memory acc) internal view {
+    uint256 generators = 1 << ELL_SECONDARY;
+    uint256 n = MSM_POINTS - start;
+    if (n > POINTS_PER_STEP) n = POINTS_PER_STEP;
+    uint256 g = start < generators ? generators - start : 0;
+    if (g > n) g = n;
+
+    uint256[] memory pts = new uint256[](2 * n);
+    uint256[] memory ks = new uint256[](n);
+    _dataInto(pts, IPA_GENERATORS + 2 * start, 2 * g);
+    uint256 aHat = claim[3];
+    for (uint256 j = 0; j < g; j++) {
+      uint256 i = start + j;
+      uint256 s = aHat;
+      for (uint256 b = 0; b < ELL_SECONDARY; b++) {
+        uint256 set = (i >> (ELL_SECONDARY - 1 - b)) & 1;
+        s = mulmod(s, claim[(set == 1 ? CLAIM_RS : CLAIM_RS_INV) + b], Q_MOD);
+      }
+      ks[j] = s;
+    }
+    for (uint256 j = g; j < n; j++) {
+      uint256 term = CLAIM_TERMS + 3 * (start + j - generators);
+      (pts[2 * j], pts[2 * j + 1], ks[j]) = (claim[term], claim[term + 1], claim[term + 2]);
+    }
+    _msm(acc, pts, ks);
+  }
+
+  // ---------------------------------------------------------------------------------------------
+  // Keccak256Transcript
+  // ---------------------------------------------------------------------------------------------
+
+  function _newTranscript(bytes memory label) internal pure returns (Transcript memory t) {
+    bytes memory input = abi.encodePacked("NoTR", label);
+    t.lo = keccak256(abi.encodePacked(input, uint8(0)));
+    t.hi = keccak256(abi.encodePacked(input, uint8(1)));
+  }
+
+  function _absorb(Transcript memory t, bytes memory label, bytes memory repr) internal pure {
+    t.buf = abi.encodePacked(t.buf, label, repr);
+  }
+
+  function _domSep(Transcript memory t, bytes memory tag) internal pure {
+    t.buf = abi.encodePacked(t.buf, "NoDS", tag);
+  }
+
+  /// Squeezes a challenge in the field of modulus `p`
+  function _squeeze(Transcript memory t, bytes memory label, uint256 p) internal pure returns (uint256) {
+    bytes memory input = abi.encodePacked(t.buf, "NoDS", uint8(t.round & 0xff), uint8(t.round >> 8), t.lo, t.hi, label);
+    t.lo = keccak256(abi.encodePacked(input, uint8(0)));
+    t.hi = keccak256(abi.encodePacked(input, uint8(1)));
+    t.round += 1;
+    t.buf = "";
+
+    // the 64-byte state is read as a little-endian integer and reduced modulo p
+    uint256 lo = _reverse(uint256(t.lo)) % p;
+    uint256 hi = _reverse(uint256(t.hi)) % p;
+    uint256 shift = (type(uint256).max % p) + 1;
+    return addmod(mulmod(hi, shift, p), lo, p);
+  }
+
+  /// Returns the transcript encoding of a commitment
+  function _commitmentBytes(Point memory P) internal pure returns (bytes memory) {
+    return abi.encodePacked(P.x, P.y, uint8(_isInfinity(P) ? 0 : 1));
+  }
+
+  /// Reverses the order of the bytes of a word
+  function _reverse(uint256 v) internal pure returns (uint256) {
+    v =
+      ((v >> 8) & 0x00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff) |
+      ((v & 0x00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff) << 8);
+    v =
+      ((v >> 16) & 0x0000ffff0000ffff0000ffff0000ffff0000ffff0000ffff0000ffff0000ffff) |
+      ((v & 0x0000ffff0000ffff0000ffff0000ffff0000ffff0000ffff0000ffff0000ffff) << 16);
+    v =
+      ((v >> 32) & 0x00000000ffffffff00000000ffffffff00000000ffffffff00000000ffffffff) |
+      ((v & 0x00000000ffffffff00000000ffffffff00000000ffffffff00000000ffffffff) << 32);
+    v =
+      ((v >> 64) & 0x0000000000000000ffffffffffffffff0000000000000000ffffffffffffffff) |
+      ((v & 0x0000000000000000ffffffffffffffff0000000000000000ffffffffffffffff) << 64);
+    return (v >> 128) | (v << 128);
+  }
+
+  // ---------------------------------------------------------------------------------------------
+  // Poseidon
+  // ---------------------------------------------------------------------------------------------
+
+  /// Hashes `input` with the Poseidon sponge of Nova's random oracle, whose constants are `c`
+  function _poseidon(uint256[] memory input, uint256[] memory c, uint256 p) internal pure returns (uint256) {
+    uint256[] memory s = new uint256[](POSEIDON_WIDTH);
+    uint256[] memory tmp = new uint256[](POSEIDON_WIDTH);
+    s[0] = _ioTag(input.length);
+    uint256 pos = 0;
+    for (uint256 i = 0; i < input.length; i++) {
+      if (pos == POSEIDON_WIDTH - 1) {
+        _permute(s, tmp, c, p);
+        pos = 0;
+      }
+      s[pos + 1] = addmod(s[pos + 1], input[i], p);
+      pos++;
+    }
+    _permute(s, tmp, c, p);
+    return s[1];
+  }
+
+  /// Returns the tag of the IO pattern (absorb `n`, squeeze 1) of the sponge
+  function _ioTag(uint256 n) internal pure returns (uint256) {
+    unchecked {
+      uint128 x = type(uint128).max - 158;
+      return uint256(x * uint128(n + (1 << 31)) + x * x);
+    }
+  }
+
+  function _permute(uint256[] memory s, uint256[] memory tmp, uint256[] memory c, uint256 p) internal pure {
+    uint256 half = POSEIDON_FULL_ROUNDS / 2;
+    uint256 rounds = POSEIDON_FULL_ROUNDS + POSEIDON_PARTIAL_ROUNDS;
+    for (uint256 i = 0; i < POSEIDON_WIDTH; i++) {
+      s[i] = addmod(s[i], c[i], p);
+    }
+    uint256 k = POSEIDON_WIDTH;
+    for (uint256 round = 0; round < rounds; round++) {
+      bool partialRound = round >= half && round < half + POSEIDON_PARTIAL_ROUNDS;
+      if (partialRound) {
+        s[0] = addmod(_pow5(s[0], p), c[k], p);
+        k += 1;
+      } else if (round + 1 == rounds) {
+        for (uint256 i = 0; i < POSEIDON_WIDTH; i++) {
+          s[i] = _pow5(s[i], p);
+        }
+      } else {
+        for (uint256 i = 0; i < POSEIDON_WIDTH; i++) {
+          s[i] = addmod(_pow5(s[i], p), c[k + i], p);
+        }
+        k += POSEIDON_WIDTH;
+      }
+
+      if (round + 1 == half) {
+        // s = s * M', with the pre-sparse matrix
+        _mulMatrix(s, tmp, c, POSEIDON_PRE_SPARSE, 1, POSEIDON_WIDTH, p);
+      } else if (partialRound) {
+        // s = s * M'', with a sparse matrix given by its first column and the rest of its first row
+        uint256 m = POSEIDON_SPARSE + (round - half) * (2 * POSEIDON_WIDTH - 1);
+        assembly ("memory-safe") {
+          let sp := add(s, 0x20)
+          let cp := add(add(c, 0x20), shl(5, m))
+          let s0 := mload(sp)
+          let acc := 0
+          for { let i := 0 } lt(i, POSEIDON_WIDTH) { i := add(i, 1) } {
+            acc := addmod(acc, mulmod(mload(add(cp, shl(5, i))), mload(add(sp, shl(5, i))), p), p)
+          }
+          let vp := add(cp, shl(5, sub(POSEIDON_WIDTH, 1)))
+          for { let j := 1 } lt(j, POSEIDON_WIDTH) { j := add(j, 1) } {
+            let slot := add(sp, shl(5, j))
+            mstore(slot, addmod(mload(slot), mulmod(mload(add(vp, shl(5, j))), s0, p), p))
+          }
+          mstore(sp, acc)
+        }
+      } else {
+        // s = M * s, with the MDS matrix
+        _mulMatrix(s, tmp, c, POSEIDON_MDS, POSEIDON_WIDTH, 1, p);
+      }
+    }
+  }
+
+  /// Sets `s` to the product of `s` with the square matrix at word `m` of `c`, where entry (i, j) of
+  /// the product is sum_k c[m + i * rowStride + k * colStride] * s[k]
+  function _mulMatrix(
+    uint256[] memory s,
+    uint256[] memory tmp,
+    uint256[] memory c,
+    uint256 m,
+    uint256 rowStride,
+    uint256 colStride,
+    uint256 p
+  ) internal pure {
+    assembly ("memory-safe") {
+      let sp := add(s, 0x20)
+      let tp := add(tmp, 0x20)
+      let cp := add(add(c, 0x20), shl(5, m))
+      for { let i := 0 } lt(i, POSEIDON_WIDTH) { i := add(i, 1) } {
+        let row := add(cp, shl(5, mul(i, rowStride)))
+        let acc := 0
+        for { let k := 0 } lt(k, POSEIDON_WIDTH) { k := add(k, 1) } {
+          acc := addmod(acc, mulmod(mload(add(row, shl(5, mul(k, colStride)))), mload(add(sp, shl(5, k))), p), p)
+        }
+        mstore(add(tp, shl(5, i)), acc)
+      }
+      mcopy(sp, tp, shl(5, POSEIDON_WIDTH))
+    }
+  }
+
+  function _pow5(uint256 x, uint256 p) internal pure returns (uint256) {
+    uint256 x2 = mulmod(x, x, p);
+    return mulmod(mulmod(x2, x2, p), x, p);
+  }
+
+  // ---------------------------------------------------------------------------------------------
+  // Field and polynomial helpers
+  // ---------------------------------------------------------------------------------------------
+
+  /// Reads word `pos` of the proof, which must be a canonical element of the field of modulus `p`
+  function _scalar(uint256[] calldata proof, uint256 pos, uint256 p) internal pure returns (uint256 v) {
+    v = proof[pos];
+    if (v >= p) revert InvalidProof("non-canonical field element");
+  }
+
+  function _inv(uint256 a, uint256 p) internal view returns (uint256 r) {
+    if (a == 0) revert InvalidProof("inversion of zero");
+    bool ok;
+    assembly ("memory-safe") {
+      let ptr := mload(0x40)
+      mstore(ptr, 0x20)
+      mstore(add(ptr, 0x20), 0x20)
+      mstore(add(ptr, 0x40), 0x20)
+      mstore(add(ptr, 0x60), a)
+      mstore(add(ptr, 0x80), sub(p, 2))
+      mstore(add(ptr, 0xa0), p)
+      ok := staticcall(gas(), 0x05, ptr, 0xc0, ptr, 0x20)
+      r := mload(ptr)
+    }
+    if (!ok) revert InvalidProof("modexp failed");
+  }
+
+  /// Returns the evaluations of eq(r, .) over the hypercube, as `EqPolynomial::evals_from_points`,
+  /// where r is the slice of `n` elements of `v` from `off` on
+  function _eqEvals(uint256[] memory v, uint256 off, uint256 n, uint256 p) internal pure returns (uint256[] memory evals) {
+    evals = new uint256[](1 << n);
+    evals[0] = 1;
+    uint256 size = 1;
+    for (uint256 k = n; k > 0; k--) {
+      uint256 rk = v[off + k - 1];
+      for (uint256 i = 0; i < size; i++) {
+        uint256 y = mulmod(evals[i], rk, p);
+        evals[i + size] = y;
+        evals[i] = addmod(evals[i], p - y, p);
+      }
+      size <<= 1;
+    }
+  }
+
+  /// Returns eq(a[aOff..], b[bOff..]), where both slices have the length of `b[bOff..]`
+  function _eqEval(
+    uint256[] memory a,
+    uint256 aOff,
+    uint256[] memory b,
+    uint256 bOff,
+    uint256 p
+  ) internal pure returns (uint256 e) {
+    e = 1;
+    for (uint256 i = 0; i + bOff < b.length; i++) {
+      uint256 x = a[aOff + i];
+      uint256 y = b[bOff + i];
+      uint256 t = addmod(mulmod(x, y, p), mulmod(addmod(1, p - x, p), addmod(1, p - y, p), p), p);
+      e = mulmod(e, t, p);
+    }
+  }
+
+  /// Returns the product of (1 - r_i) over the first `n` elements of `r`
+  function _lagrange0(uint256[] memory r, uint256 n, uint256 p) internal pure returns (uint256 l) {
+    l = 1;
+    for (uint256 i = 0; i < n; i++) {
+      l = mulmod(l, addmod(1, p - r[i], p), p);
+    }
+  }
+
+  /// Evaluates the sparse polynomial (u, x0, x1) of the public IO at `r[off..]`
+  function _evalX(Instance memory U, uint256[] memory r, uint256 off, uint256 p) internal pure returns (uint256) {
+    uint256 n = r.length;
+    uint256 common = 1;
+    for (uint256 i = off; i < n - 2; i++) {
+      common = mulmod(common, addmod(1, p - r[i], p), p);
+    }
+    uint256 s1 = r[n - 2];
+    uint256 s2 = r[n - 1];
+    uint256 t2 = addmod(1, p - s2, p);
+    uint256 evalX = addmod(
+      mulmod(addmod(1, p - s1, p), addmod(mulmod(U.u, t2, p), mulmod(U.x0, s2, p), p), p),
+      mulmod(s1, mulmod(U.x1, t2, p), p),
+      p
+    );
+    return mulmod(common, evalX, p);
+  }
+
+  // ---------------------------------------------------------------------------------------------
+  // BN254, with the precompiles
+  // ---------------------------------------------------------------------------------------------
+
+  /// Reads a point of BN254 at word `pos` of the proof
+  function _g1(uint256[] calldata proof, uint256 pos) internal pure returns (Point memory P) {
+    P = Point(proof[pos], proof[pos + 1]);
+    if (P.x >= Q_MOD || P.y >= Q_MOD) revert InvalidProof("non-canonical point");
+  }
+
+  function _g1Add(Point memory a, Point memory b) internal view returns (Point memory c) {
+    uint256[4] memory input = [a.x, a.y, b.x, b.y];
+    bool ok;
+    assembly ("memory-safe") {
+      ok := staticcall(gas(), 0x06, input, 0x80, c, 0x40)
+    }
+    if (!ok) revert InvalidProof("invalid point of BN254");
+  }
+
+  function _g1Mul(Point memory a, uint256 k) internal view returns (Point memory c) {
+    uint256[3] memory input = [a.x, a.y, k];
+    bool ok;
+    assembly ("memory-safe") {
+      ok := staticcall(gas(), 0x07, input, 0x60, c, 0x40)
+    }
+    if (!ok) revert InvalidProof("invalid point of BN254");
+  }
+
+  function _g1Neg(Point memory a) internal pure returns (Point memory) {
+    return _isInfinity(a) ? a : Point(a.x, Q_MOD - a.y);
+  }
+
+  /// Checks that e(L, H) = e(R, tau_H)
+  function _pairing(Point memory L, Point memory R) internal view returns (bool) {
+    Point memory negR = _g1Neg(R);
+    uint256[12] memory input = [
+      L.x,
+      L.y,
+      KZG_H_X1,
+      KZG_H_X0,
+      KZG_H_Y1,
+      KZG_H_Y0,
+      negR.x,
+      negR.y,
+      KZG_TAU_H_X1,
+      KZG_TAU_H_X0,
+      KZG_TAU_H_Y1,
+      KZG_TAU_H_Y0
+    ];
+    uint256[1] memory out;
+    bool ok;
+    assembly ("memory-safe") {
+      ok := staticcall(gas(), 0x08, input, 0x180, out, 0x20)
+    }
+    return ok && out[0] == 1;
+  }
+
+  function _isInfinity(Point memory P) internal pure returns (bool) {
+    return P.x == 0 && P.y == 0;
+  }
+
+  // ---------------------------------------------------------------------------------------------
+  // Grumpkin (y^2 = x^3 - 17 over the scalar field of BN254)
+  // ---------------------------------------------------------------------------------------------
+
+  /// Reads a point of Grumpkin at word `pos` of the proof
+  function _grumpkin(uint256[] calldata proof, uint256 pos) internal pure returns (Point memory P) {
+    P = Point(proof[pos], proof[pos + 1]);
+    if (P.x >= R_MOD || P.y >= R_MOD) revert InvalidProof("non-canonical point");
+    if (!_isInfinity(P)) {
+      uint256 rhs = addmod(mulmod(mulmod(P.x, P.x, R_MOD), P.x, R_MOD), R_MOD - 17, R_MOD);
+      if (mulmod(P.y, P.y, R_MOD) != rhs) revert InvalidProof("invalid point of Grumpkin");
+    }
+  }
+
+  /// Returns a * P + b * Q
+  function _gAdd(Point memory P, uint256 a, Point memory Q, uint256 b) internal view returns (Point memory) {
+    uint256[] memory pts = new uint256[](4);
+    (pts[0], pts[1], pts[2], pts[3]) = (P.x, P.y, Q.x, Q.y);
+    uint256[] memory ks = new uint256[](2);
+    (ks[0], ks[1]) = (a, b);
+    uint256[3] memory acc;
+    _msm(acc, pts, ks);
+    return _jAffine(acc);
+  }
+
+  /// Converts a point in Jacobian coordinates (x, y, z), where z = 0 for the point at infinity, to an
+  /// affine point
+  function _jAffine(uint256[3] memory a) internal view returns (Point memory) {
+    if (a[2] == 0) return Point(0, 0);
+    uint256 zi = _inv(a[2], R_MOD);
+    uint256 zi2 = mulmod(zi, zi, R_MOD);
+    return Point(mulmod(a[0], zi2, R_MOD), mulmod(a[1], mulmod(zi2, zi, R_MOD), R_MOD));
+  }
+
+  /// Adds sum_i ks[i] * P_i to the point `acc` in Jacobian coordinates, where P_i is the affine point
+  /// (pts[2i], pts[2i + 1]) of Grumpkin and (0, 0) is the point at infinity. Small sums use a
+  /// double-and-add over all the points at once, and larger ones use the bucket method.
+  function _msm(uint256[3] memory acc, uint256[] memory pts, uint256[] memory ks) internal pure {
+    assembly ("memory-safe") {
+      // doubles the point at `a`, with the formulas dbl-2009-l for a = 0
+      function dbl(a) {
+        let z := mload(add(a, 0x40))
+        if iszero(z) { leave }
+        let p := 0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001
+        let x := mload(a)
+        let y := mload(add(a, 0x20))
+        let xx := mulmod(x, x, p)
+        let yy := mulmod(y, y, p)
+        let yyyy := mulmod(yy, yy, p)
+        let d := addmod(x, yy, p)
+        d := addmod(mulmod(d, d, p), sub(p, addmod(xx, yyyy, p)), p)
+        d := addmod(d, d, p)
+        let e := mulmod(3, xx, p)
+        let x3 := addmod(mulmod(e, e, p), sub(p, addmod(d, d, p)), p)
+        mstore(add(a, 0x40), mulmod(addmod(y, y, p), z, p))
+        mstore(add(a, 0x20), addmod(mulmod(e, addmod(d, sub(p, x3), p), p), sub(p, mulmod(8, yyyy, p)), p))
+        mstore(a, x3)
+      }
+
+      // adds the affine point (x2, y2) other than the point at infinity to the point at `a`, with
+      // the formulas madd-2007-bl
+      function addAffine(a, x2, y2) {
+        let z1 := mload(add(a, 0x40))
+        if iszero(z1) {
+          mstore(a, x2)
+          mstore(add(a, 0x20), y2)
+          mstore(add(a, 0x40), 1)
+          leave
+        }
+        let p := 0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001
+        let x1 := mload(a)
+        let y1 := mload(add(a, 0x20))
+        let z1z1 := mulmod(z1, z1, p)
+        let h := addmod(mulmod(x2, z1z1, p), sub(p, x1), p)
+        let r := addmod(mulmod(y2, mulmod(z1, z1z1, p), p), sub(p, y1), p)
+        if iszero(h) {
+          switch r
+          case 0 { dbl(a) }
+          default { mstore(add(a, 0x40), 0) }
+          leave
+        }
+        r := addmod(r, r, p)
+        let hh := mulmod(h, h, p)
+        let i := mulmod(4, hh, p)
+        let j := mulmod(h, i, p)
+        let v := mulmod(x1, i, p)
+        let x3 := addmod(addmod(mulmod(r, r, p), sub(p, j), p), sub(p, addmod(v, v, p)), p)
+        mstore(a, x3)
+        mstore(add(a, 0x20), addmod(mulmod(r, addmod(v, sub(p, x3), p), p), sub(p, mulmod(2, mulmod(y1, j, p), p)), p))
+        let zh := addmod(z1, h, p)
+        mstore(add(a, 0x40), addmod(mulmod(zh, zh, p), sub(p, addmod(z1z1, hh, p)), p))
+      }
+
+      // adds the point at `b` to the point at `a`, with the formulas add-2007-bl
+      function jadd(a, b) {
+        let z2 := mload(add(b, 0x40))
+        if iszero(z2) { leave }
+        let z1 := mload(add(a, 0x40))
+        if iszero(z1) {
+          mcopy(a, b, 0x60)
+          leave
+        }
+        let p := 0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001
+        let z1z1 := mulmod(z1, z1, p)
+        let z2z2 := mulmod(z2, z2, p)
+        let u1 := mulmod(mload(a), z2z2, p)
+        let h := addmod(mulmod(mload(b), z1z1, p), sub(p, u1), p)
+        let s1 := mulmod(mulmod(mload(add(a, 0x20)), z2, p), z2z2, p)
+        let r := addmod(mulmod(mulmod(mload(add(b, 0x20)), z1, p), z1z1, p), sub(p, s1), p)
+        if iszero(h) {
+          switch r
+          case 0 { dbl(a) }
+          default { mstore(add(a, 0x40), 0) }
+          leave
+        }
+        r := addmod(r, r, p)
+        let zz := addmod(z1, z2, p)
+        mstore(add(a, 0x40), mulmod(addmod(mulmod(zz, zz, p), sub(p, addmod(z1z1, z2z2, p)), p), h, p))
+        let i := mulmod(4, mulmod(h, h, p), p)
+        let j := mulmod(h, i, p)
+        let v := mulmod(u1, i, p)
+        let x3 := addmod(addmod(mulmod(r, r, p), sub(p, j), p), sub(p, addmod(v, v, p)), p)
+        mstore(a, x3)
+        mstore(add(a, 0x20), addmod(mulmod(r, addmod(v, sub(p, x3), p), p), sub(p, mulmod(2, mulmod(s1, j, p), p)), p))
+      }
+
+      let n := mload(ks)
+      let pp := add(pts, 0x20)
+      let kp := add(ks, 0x20)
+      // the sum and the buckets are kept in memory reserved at the free memory pointer, and released
+      // once the sum is added to `acc`
+      let res := mload(0x40)
+      mstore(0x40, add(res, add(0x120, mul(shl(MSM_WINDOW, 1), 0x60))))
+      mstore(res, 0)
+      mstore(add(res, 0x20), 0)
+      mstore(add(res, 0x40), 0)
+
+      switch lt(n, MSM_SMALL)
+      case 1 {
+        for { let bit := 256 } bit { bit := sub(bit, 1) } {
+          dbl(res)
+          for { let i := 0 } lt(i, n) { i := add(i, 1) } {
+            if and(shr(sub(bit, 1), mload(add(kp, shl(5, i)))), 1) {
+              let x := mload(add(pp, shl(6, i)))
+              let y := mload(add(pp, add(shl(6, i), 0x20)))
+              if or(x, y) { addAffine(res, x, y) }
+            }
+          }
+        }
+      }
+      default {
+        let buckets := add(res, 0x60)
+        let size := mul(shl(MSM_WINDOW, 1), 0x60)
+        let running := add(buckets, size)
+        let sum := add(running, 0x60)
+        let mask := sub(shl(MSM_WINDOW, 1), 1)
+        for { let w := div(add(256, sub(MSM_WINDOW, 1)), MSM_WINDOW) } w { w := sub(w, 1) } {
+          for { let i := 0 } lt(i, MSM_WINDOW) { i := add(i, 1) } { dbl(res) }
+
+          // puts each point in the bucket of its digit
+          calldatacopy(buckets, calldatasize(), add(size, 0xc0))
+          let shift := mul(sub(w, 1), MSM_WINDOW)
+          for { let i := 0 } lt(i, n) { i := add(i, 1) } {
+            let d := and(shr(shift, mload(add(kp, shl(5, i)))), mask)
+            if d {
+              let x := mload(add(pp, shl(6, i)))
+              let y := mload(add(pp, add(shl(6, i), 0x20)))
+              if or(x, y) { addAffine(add(buckets, mul(d, 0x60)), x, y) }
+            }
+          }
+
+          // sum_d d * bucket_d, as the sum of the running sums from the last bucket down
+          for { let d := mask } d { d := sub(d, 1) } {
+            jadd(running, add(buckets, mul(d, 0x60)))
+            jadd(sum, running)
+          }
+          jadd(res, sum)
+        }
+      }
+      jadd(acc, res)
+      mstore(0x40, res)
+    }
+  }
+
+  // ---------------------------------------------------------------------------------------------
+  // Data contracts
+  // ---------------------------------------------------------------------------------------------
+
+  /// Returns `n` words of data starting at word `offset`
+  function _data(uint256 offset, uint256 n) internal view returns (uint256[] memory out) {
+    out = new uint256[](n);
+    _dataInto(out, offset, n);
+  }
+
+  /// Copies `n` words of data starting at word `offset` to the beginning of `out`
+  function _dataInto(uint256[] memory out, uint256 offset, uint256 n) internal view {
+    uint256 pos = offset * 32;
+    uint256 end = pos + n * 32;
+    uint256 dst;
+    assembly ("memory-safe") {
+      dst := add(out, 0x20)
+    }
+    while (pos < end) {
+      address chunk = chunks[pos / CHUNK_BYTES];
+      uint256 within = pos % CHUNK_BYTES;
+      uint256 len = CHUNK_BYTES - within;
+      if (len > end - pos) len = end - pos;
+      assembly ("memory-safe") {
+        extcodecopy(chunk, dst, add(within, 1), len)
+      }
+      dst += len;
+      pos += len;
+    }
+  }
+}
@@ -0,0 +1,1167 @@
+//! This module generates a Solidity verifier for a `CompressedSNARK` over the BN254/Grumpkin cycle,
+//! where the primary SNARK is the preprocessing Spartan SNARK with HyperKZG and the secondary SNARK
+//! is Spartan with IPA, and encodes proofs as calldata for it.
+//!
+//! The verifier mirrors `CompressedSNARK::verify`: it checks the hashes of the running instances
+//! with Poseidon, folds the instances, and verifies both Spartan SNARKs, replaying the absorb and
+//! squeeze rules of `Keccak256Transcript` byte for byte. The constants of Poseidon, the R1CS matrices
+//! of the secondary circuit and the IPA generators are too large to be embedded in a contract, so
+//! they are laid out as a sequence of 32-byte words and split across data contracts whose code
+//! hashes are embedded in the verifier.
+//!
+//! Two checks of the secondary SNARK are linear in the size of the secondary circuit: the evaluation
+//! of its R1CS matrices and the multi-scalar multiplication of IPA. They exceed the gas limit of a
+//! transaction, so `start` runs every other check and records these two as a claim, which `resume`
+//! then checks in bounded steps, one per transaction. The view function `verify` runs all the checks
+//! in a single call.
+use super::{CompressedSNARK, VerifierKey};
+use crate::{
+  errors::NovaError,
+  provider::{
+    hyperkzg, ipa_pc, poseidon::PoseidonConstantsCircuit, Bn256EngineKZG, GrumpkinEngine,
+  },
+  r1cs::{R1CSShape, RelaxedR1CSInstance},
+  spartan::{ppsnark, snark::RelaxedR1CSSNARK, sumcheck::SumcheckProof},
+  traits::{
+    circuit::StepCircuit, commitment::CommitmentTrait, snark::DigestHelperTrait, Engine,
+  },
+  Commitment,
+};
+use ff::PrimeField;
+use halo2curves::{Coordinates, CurveAffine};
+use sha3::{Digest, Keccak256};
+
+type E1 = Bn256EngineKZG;
+type E2 = GrumpkinEngine;
+type S1 = ppsnark::RelaxedR1CSSNARK<E1, hyperkzg::EvaluationEngine<E1>>;
+type S2 = RelaxedR1CSSNARK<E2, ipa_pc::EvaluationEngine<E2>>;
+
+/// The template of the verifier, where `{{NAME}}` marks a parameter
+const TEMPLATE: &str = include_str!("NovaVerifier.sol");
+
+/// The number of bytes of data held by each data contract, which keeps their code under the limit
+/// of EIP-170
+const CHUNK_BYTES: usize = 24544;
+
+/// The number of words of a proof that precede the Spartan SNARKs
+const FOLDING_WORDS: usize = 44;
+
+/// The width of the Poseidon permutation used by Nova's random oracles
+const POSEIDON_WIDTH: usize = 25;
+
+/// The signature of the entry points `verify` and `start`, which take the same arguments
+const VERIFY_SIGNATURE: &[u8] = b"verify(uint256,uint256[],uint256[],uint256[])";
+
+/// A big-endian word of the EVM
+type Word = [u8; 32];
+
+/// A Solidity verifier for the `CompressedSNARK`s of a given verifier key
+#[derive(Clone, Debug)]
+pub struct SolidityVerifier {
+  /// The source code of the `NovaVerifier` contract
+  pub source: String,
+  /// The creation code of each data contract, whose addresses are passed, in order, to the
+  /// constructor of `NovaVerifier`
+  pub data_contracts: Vec<Vec<u8>>,
+}
+
+/// The parameters of the verifier of the preprocessing Spartan SNARK
+struct PreprocessedLayout {
+  vk_digest: Word,
+  rounds: usize,
+  vars: usize,
+  comms: Vec<(&'static str, [Word; 2])>,
+}
+
+impl PreprocessedLayout {
+  fn new(vk: &ppsnark::VerifierKey<E1, hyperkzg::EvaluationEngine<E1>>) -> Self {
+    let S = &vk.S_comm;
+    Self {
+      vk_digest: field_word(&vk.digest()),
+      rounds: S.N.ilog2() as usize,
+      vars: vk.num_vars.ilog2() as usize,
+      comms: vec![
+        ("VAL_A", commitment_words::<E1>(&S.comm_val_A)),
+        ("VAL_B", commitment_words::<E1>(&S.comm_val_B)),
+        ("VAL_C", commitment_words::<E1>(&S.comm_val_C)),
+        ("ROW", commitment_words::<E1>(&S.comm_row)),
+        ("TS_ROW", commitment_words::<E1>(&S.comm_ts_row)),
+        ("COL", commitment_words::<E1>(&S.comm_col)),
+        ("TS_COL", commitment_words::<E1>(&S.comm_ts_col)),
+      ],
+    }
+  }
+
+  /// The number of words of the SNARK, excluding the evaluation argument
+  fn words(&self) -> usize {
+    18 + 3 + 3 * self.rounds + 18
+  }
+}
+
+/// The parameters of the verifier of a Spartan SNARK
+struct SpartanLayout {
+  vk_digest: Word,
+  rounds_x: usize,
+  rounds_y: usize,
+  matrices: usize,
+  entries: usize,
+}
+
+impl SpartanLayout {
+  fn new<E: Engine>(
+    S: &R1CSShape<E>,
+    vk_digest: Word,
+    matrices: usize,
+    data: &mut Vec<Word>,
+  ) -> Self {
+    let entries = matrix_words(S, data);
+    Self {
+      vk_digest,
+      rounds_x: S.num_cons.ilog2() as usize,
+      rounds_y: S.num_vars.ilog2() as usize + 1,
+      matrices,
+      entries,
+    }
+  }
+
+  /// The number of variables of the polynomial opened by the polynomial commitment scheme
+  fn ell(&self) -> usize {
+    self.rounds_x.max(self.rounds_y - 1)
+  }
+
+  /// The number of words of the SNARK, excluding the evaluation argument
+  fn words(&self) -> usize {
+    3 * self.rounds_x + 4 + 2 * self.rounds_y + 1 + 2 * self.ell() + 2
+  }
+}
+
+/// The parameters of a verifier and the data held by its data contracts
+struct Layout {
+  arity: usize,
+  pp_digest: Word,
+  primary: PreprocessedLayout,
+  secondary: SpartanLayout,
+  poseidon_r: usize,
+  poseidon_q: usize,
+  full_rounds: usize,
+  partial_rounds: usize,
+  ipa_generators: usize,
+  data: Vec<Word>,
+}
+
+impl Layout {
+  /// The number of words of a proof
+  fn proof_words(&self) -> usize {
+    let (rounds, ell) = (self.primary.rounds, self.secondary.ell());
+    FOLDING_WORDS
+      + self.primary.words()
+      + 2 * (rounds - 1)
+      + 6
+      + 3 * rounds
+      + self.secondary.words()
+      + 4 * ell
+      + 1
+  }
+}
+
+impl<C> VerifierKey<E1, E2, C, S1, S2>
+where
+  C: StepCircuit<<E1 as Engine>::Scalar>,
+{
+  /// Generates a Solidity verifier for the `CompressedSNARK`s of this verifier key
+  pub fn to_solidity(&self) -> Result<SolidityVerifier, NovaError> {
+    let layout = self.layout()?;
+
+    let bytes = layout.data.concat();
+    let runtimes = bytes
+      .chunks(CHUNK_BYTES)
+      .map(|chunk| [&[0u8][..], chunk].concat())
+      .collect::<Vec<_>>();
+    let hashes = runtimes
+      .iter()
+      .map(|runtime| format!("bytes32({})", hex(&Keccak256::digest(runtime).into())))
+      .collect::<Vec<_>>();
+    let data_contracts = runtimes
+      .iter()
+      .map(|runtime| {
+        let mut code = vec![0x63];
+        code.extend((runtime.len() as u32).to_be_bytes());
+        code.extend([0x80, 0x60, 0x0e, 0x60, 0x00, 0x39, 0x60, 0x00, 0xf3]);
+        code.extend(runtime);
+        code
+      })
+      .collect();
+
+    let vk_ee = &self.vk_primary.vk_ee;
+    let [dk_primary_x, dk_primary_y] = affine_words(&self.dk_primary.h);
+    let [dk_secondary_x, dk_secondary_y] = affine_words(&self.dk_secondary.h);
+    let [kzg_g_x, kzg_g_y] = affine_words(&vk_ee.G);
+    let [ipa_s_x, ipa_s_y] = affine_words(&self.vk_secondary.vk_ee.ck_s.ck[0]);
+
+    let mut params = [
+      ("ARITY", layout.arity.to_string()),
+      ("PP_DIGEST", hex(&layout.pp_digest)),
+      ("PROOF_WORDS", layout.proof_words().to_string()),
+      ("VK_DIGEST_PRIMARY", hex(&layout.primary.vk_digest)),
+      ("ROUNDS_PRIMARY", layout.primary.rounds.to_string()),
+      ("VARS_PRIMARY", layout.primary.vars.to_string()),
+      ("VK_DIGEST_SECONDARY", hex(&layout.secondary.vk_digest)),
+      ("ROUNDS_X_SECONDARY", layout.secondary.rounds_x.to_string()),
+      ("ROUNDS_Y_SECONDARY", layout.secondary.rounds_y.to_string()),
+      ("ENTRIES_SECONDARY", layout.secondary.entries.to_string()),
+      ("MATRICES_SECONDARY", layout.secondary.matrices.to_string()),
+      ("DK_PRIMARY_X", hex(&dk_primary_x)),
+      ("DK_PRIMARY_Y", hex(&dk_primary_y)),
+      ("DK_SECONDARY_X", hex(&dk_secondary_x)),
+      ("DK_SECONDARY_Y", hex(&dk_secondary_y)),
+      ("KZG_G_X", hex(&kzg_g_x)),
+      ("KZG_G_Y", hex(&kzg_g_y)),
+      ("KZG_H_X1", hex(&field_word(vk_ee.H.x.c1()))),
+      ("KZG_H_X0", hex(&field_word(vk_ee.H.x.c0()))),
+      ("KZG_H_Y1", hex(&field_word(vk_ee.H.y.c1()))),
+      ("KZG_H_Y0", hex(&field_word(vk_ee.H.y.c0()))),
+      ("KZG_TAU_H_X1", hex(&field_word(vk_ee.tau_H.x.c1()))),
+      ("KZG_TAU_H_X0", hex(&field_word(vk_ee.tau_H.x.c0()))),
+      ("KZG_TAU_H_Y1", hex(&field_word(vk_ee.tau_H.y.c1()))),
+      ("KZG_TAU_H_Y0", hex(&field_word(vk_ee.tau_H.y.c0()))),
+      ("IPA_GENERATORS", layout.ipa_generators.to_string()),
+      ("IPA_S_X", hex(&ipa_s_x)),
+      ("IPA_S_Y", hex(&ipa_s_y)),
+      ("POSEIDON_FULL_ROUNDS", layout.full_rounds.to_string()),
+      ("POSEIDON_PARTIAL_ROUNDS", layout.partial_rounds.to_string()),
+      ("POSEIDON_R", layout.poseidon_r.to_string()),
+      ("POSEIDON_Q", layout.poseidon_q.to_string()),
+      ("CHUNK_BYTES", CHUNK_BYTES.to_string()),
+      ("NUM_CHUNKS", hashes.len().to_string()),
+      ("CHUNK_HASHES", hashes.join(", ")),
+    ]
+    .map(|(name, value)| (name.to_string(), value))
+    .to_vec();
+    for (name, [x, y]) in &layout.primary.comms {
+      params.push((format!("COMM_{name}_X"), hex(x)));
+      params.push((format!("COMM_{name}_Y"), hex(y)));
+    }
+    let source = params
+      .iter()
+      .fold(TEMPLATE.to_string(), |source, (name, value)| {
+        source.replace(&format!("{{{{{name}}}}}"), value)
+      });
+    debug_assert!(!source.contains("{{"));
+
+    Ok(SolidityVerifier {
+      source,
+      data_contracts,
+    })
+  }
+
+  /// Lays out the parameters of the verifier and the data of its data contracts
+  fn layout(&self) -> Result<Layout, NovaError> {
+    let mut data = Vec::new();
+
+    let poseidon_r = data.len();
+    let (full_rounds, partial_rounds) = poseidon_words(&self.ro_consts_secondary, &mut data);
+    let poseidon_q = data.len();
+    if poseidon_words(&self.ro_consts_primary, &mut data) != (full_rounds, partial_rounds) {
+      return Err(NovaError::InternalError);
+    }
+
+    let primary = PreprocessedLayout::new(&self.vk_primary);
+    let secondary = SpartanLayout::new(
+      &self.vk_secondary.S,
+      field_word(&self.vk_secondary.digest()),
+      data.len(),
+      &mut data,
+    );
+
+    // IPA uses the first 2^ell generators of the commitment key
+    let ipa_generators = data.len();
+    let ck_v = &self.vk_secondary.vk_ee.ck_v.ck;
+    let n = 1 << secondary.ell();
+    if ck_v.len() < n {
+      return Err(NovaError::InvalidCommitmentKeyLength);
+    }
+    data.extend(ck_v[..n].iter().flat_map(affine_words));
+
+    Ok(Layout {
+      arity: self.F_arity,
+      pp_digest: field_word(&self.pp_digest),
+      primary,
+      secondary,
+      poseidon_r,
+      poseidon_q,
+      full_rounds,
+      partial_rounds,
+      ipa_generators,
+      data,
+    })
+  }
+}
+
+impl<C> CompressedSNARK<E1, E2, C, S1, S2>
+where
+  C: StepCircuit<<E1 as Engine>::Scalar>,
+{
+  /// Encodes the calldata of a call to `NovaVerifier.verify` that checks this proof of `num_steps`
+  /// steps starting from `z0`. A call to `NovaVerifier.start` takes the same arguments, so its
+  /// calldata only differs in the selector.
+  pub fn to_calldata(
+    &self,
+    num_steps: usize,
+    z0: &[<E1 as Engine>::Scalar],
+  ) -> Result<Vec<u8>, NovaError> {
+    let proof = self.proof_words()?;
+
+    let mut num_steps_word = [0u8; 32];
+    num_steps_word[24..].copy_from_slice(&(num_steps as u64).to_be_bytes());
+
+    // the head holds the number of steps and the offsets of the three dynamic arrays
+    let z0_offset = 4 * 32;
+    let zn_offset = z0_offset + 32 * (1 + z0.len());
+    let proof_offset = zn_offset + 32 * (1 + self.zn.len());
+
+    let mut calldata = Keccak256::digest(VERIFY_SIGNATURE)[..4].to_vec();
+    calldata.extend(num_steps_word);
+    for offset in [z0_offset, zn_offset, proof_offset] {
+      calldata.extend(usize_word(offset));
+    }
+    calldata.extend(usize_word(z0.len()));
+    calldata.extend(z0.iter().flat_map(field_word));
+    calldata.extend(usize_word(self.zn.len()));
+    calldata.extend(self.zn.iter().flat_map(field_word));
+    calldata.extend(usize_word(proof.len()));
+    calldata.extend(proof.concat());
+    Ok(calldata)
+  }
+
+  /// Lays out the proof as a sequence of words, in the order in which the verifier reads them
+  fn proof_words(&self) -> Result<Vec<Word>, NovaError> {
+    if self.l_u_secondary.X.len() != 2
+      || self.r_U_primary.X.len() != 2
+      || self.r_U_secondary.X.len() != 2
+      || self.l_ur_primary.X.len() != 2
+      || self.l_ur_secondary.X.len() != 2
+    {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Invalid number of outputs in R1CS instances".to_string(),
+      });
+    }
+
+    let mut words = Vec::new();
+    relaxed_words(&self.r_U_secondary, &mut words);
+    words.push(field_word(&self.ri_secondary));
+    words.extend(commitment_words::<E2>(&self.l_u_secondary.comm_W));
+    words.extend(self.l_u_secondary.X.iter().map(field_word));
+    words.extend(commitment_words::<E2>(&self.nifs_Uf_secondary.comm_T));
+    relaxed_words(&self.l_ur_secondary, &mut words);
+    words.extend(commitment_words::<E2>(&self.nifs_Un_secondary.comm_T));
+
+    relaxed_words(&self.r_U_primary, &mut words);
+    words.push(field_word(&self.ri_primary));
+    relaxed_words(&self.l_ur_primary, &mut words);
+    words.extend(commitment_words::<E1>(&self.nifs_Un_primary.comm_T));
+
+    words.push(field_word(&self.wit_blind_r_Wn_primary));
+    words.push(field_word(&self.err_blind_r_Wn_primary));
+    words.push(field_word(&self.wit_blind_r_Wn_secondary));
+    words.push(field_word(&self.err_blind_r_Wn_secondary));
+    debug_assert_eq!(words.len(), FOLDING_WORDS);
+
+    preprocessed_words(&self.snark_primary, &mut words);
+    let arg = &self.snark_primary.eval_arg;
+    words.extend(arg.com().iter().flat_map(affine_words));
+    words.extend(arg.w().iter().flat_map(affine_words));
+    words.extend(arg.v().iter().flatten().map(field_word));
+
+    spartan_words(&self.snark_secondary, &mut words);
+    let arg = &self.snark_secondary.eval_arg;
+    words.extend(arg.L_vec.iter().flat_map(commitment_words::<E2>));
+    words.extend(arg.R_vec.iter().flat_map(commitment_words::<E2>));
+    words.push(field_word(&arg.a_hat));
+
+    Ok(words)
+  }
+}
+
+fn preprocessed_words(snark: &S1, words: &mut Vec<Word>) {
+  for comm in [
+    &snark.comm_Az,
+    &snark.comm_Bz,
+    &snark.comm_Cz,
+    &snark.comm_L_row,
+    &snark.comm_L_col,
+    &snark.comm_t_plus_r_inv_row,
+    &snark.comm_w_plus_r_inv_row,
+    &snark.comm_t_plus_r_inv_col,
+    &snark.comm_w_plus_r_inv_col,
+  ] {
+    words.extend(commitment_words::<E1>(comm));
+  }
+  words.extend(
+    [
+      &snark.eval_Az_at_tau,
+      &snark.eval_Bz_at_tau,
+      &snark.eval_Cz_at_tau,
+    ]
+    .map(field_word),
+  );
+  sumcheck_words(&snark.sc, words);
+  // the evaluations are laid out in the order in which the verifier batches them
+  words.extend(
+    [
+      &snark.eval_W,
+      &snark.eval_Az,
+      &snark.eval_Bz,
+      &snark.eval_Cz,
+      &snark.eval_E,
+      &snark.eval_L_row,
+      &snark.eval_L_col,
+      &snark.eval_val_A,
+      &snark.eval_val_B,
+      &snark.eval_val_C,
+      &snark.eval_t_plus_r_inv_row,
+      &snark.eval_row,
+      &snark.eval_w_plus_r_inv_row,
+      &snark.eval_ts_row,
+      &snark.eval_t_plus_r_inv_col,
+      &snark.eval_col,
+      &snark.eval_w_plus_r_inv_col,
+      &snark.eval_ts_col,
+    ]
+    .map(field_word),
+  );
+}
+
+fn spartan_words(snark: &S2, words: &mut Vec<Word>) {
+  let (claim_Az, claim_Bz, claim_Cz) = &snark.claims_outer;
+  sumcheck_words(&snark.sc_proof_outer, words);
+  words.extend([claim_Az, claim_Bz, claim_Cz, &snark.eval_E].map(field_word));
+  sumcheck_words(&snark.sc_proof_inner, words);
+  words.push(field_word(&snark.eval_W));
+  sumcheck_words(&snark.sc_proof_batch, words);
+  words.extend(snark.evals_batch.iter().map(field_word));
+}
+
+fn sumcheck_words<E: Engine>(proof: &SumcheckProof<E>, words: &mut Vec<Word>) {
+  for poly in &proof.compressed_polys {
+    words.extend(poly.coeffs_except_linear_term.iter().map(field_word));
+  }
+}
+
+fn relaxed_words<E: Engine>(U: &RelaxedR1CSInstance<E>, words: &mut Vec<Word>) {
+  words.extend(commitment_words::<E>(&U.comm_W));
+  words.extend(commitment_words::<E>(&U.comm_E));
+  words.push(field_word(&U.u));
+  words.extend(U.X.iter().map(field_word));
+}
+
+/// Lays out the constants of the optimized Poseidon permutation, and returns its number of full
+/// and partial rounds
+fn poseidon_words<F: PrimeField>(
+  constants: &PoseidonConstantsCircuit<F>,
+  words: &mut Vec<Word>,
+) -> (usize, usize) {
+  let c = &constants.0;
+  debug_assert_eq!(
+    c.compressed_round_constants.len(),
+    c.full_rounds * POSEIDON_WIDTH + c.partial_rounds
+  );
+  words.extend(c.compressed_round_constants.iter().map(field_word));
+  words.extend(c.mds_matrices.m.iter().flatten().map(field_word));
+  words.extend(c.pre_sparse_matrix.iter().flatten().map(field_word));
+  for m in &c.sparse_matrixes {
+    words.extend(m.w_hat.iter().chain(&m.v_rest).map(field_word));
+  }
+  (c.full_rounds, c.partial_rounds)
+}
+
+/// Lays out the entries of the matrices A, B, and C, each as the word `id << 128 | row << 64 | col`
+/// followed by its value, and returns the number of entries
+fn matrix_words<E: Engine>(S: &R1CSShape<E>, words: &mut Vec<Word>) -> usize {
+  let mut entries = 0;
+  for (id, M) in [&S.A, &S.B, &S.C].into_iter().enumerate() {
+    for (row, ptrs) in M.indptr.windows(2).enumerate() {
+      for (val, col) in M.get_row_unchecked(ptrs.try_into().unwrap()) {
+        let mut key = [0u8; 32];
+        key[15] = id as u8;
+        key[16..24].copy_from_slice(&(row as u64).to_be_bytes());
+        key[24..].copy_from_slice(&(*col as u64).to_be_bytes());
+        words.push(key);
+        words.push(field_word(val));
+        entries += 1;
+      }
+    }
+  }
+  entries
+}
+
+fn field_word<F: PrimeField>(f: &F) -> Word {
+  let mut word = [0u8; 32];
+  word.copy_from_slice(f.to_repr().as_ref());
+  word.reverse();
+  word
+}
+
+fn usize_word(n: usize) -> Word {
+  let mut word = [0u8; 32];
+  word[24..].copy_from_slice(&(n as u64).to_be_bytes());
+  word
+}
+
+/// Returns the coordinates of a point, where the point at infinity is (0, 0)
+fn affine_words<A: CurveAffine>(p: &A) -> [Word; 2] {
+  Option::from(p.coordinates())
+    .map(|c: Coordinates<A>| [field_word(c.x()), field_word(c.y())])
+    .unwrap_or_default()
+}
+
+fn commitment_words<E: Engine>(comm: &Commitment<E>) -> [Word; 2] {
+  let (x, y, _) = comm.to_coordinates();
+  [field_word(&x), field_word(&y)]
+}
+
+fn hex(word: &Word) -> String {
+  let digits = word.iter().map(|b| format!("{b:02x}")).collect::<String>();
+  format!("0x{digits}")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    nova::{PublicParams, RecursiveSNARK},
+    provider::{keccak::Keccak256Transcript, poseidon::PoseidonRO},
+    spartan::polys::univariate::UniPoly,
+    traits::{
+      circuit::NonTrivialCircuit, snark::RelaxedR1CSSNARKTrait, PrimeFieldExt, ROTrait,
+      TranscriptEngineTrait,
+    },
+  };
+  use ff::{Field, PrimeFieldBits};
+  use halo2curves::bn256::{Fq, Fr};
+  use rand::{rngs::OsRng, Rng};
+  use revm::{
+    db::{CacheDB, EmptyDB},
+    primitives::{Address, ExecutionResult, Log, Output, TxKind, U256},
+    Evm,
+  };
+
+  type C = NonTrivialCircuit<Fr>;
+
+  /// Reduces a big-endian integer modulo the characteristic of `F`
+  fn word_mod<F: PrimeField>(bytes: &[u8]) -> F {
+    bytes
+      .iter()
+      .fold(F::ZERO, |acc, b| acc * F::from(256) + F::from(*b as u64))
+  }
+
+  /// Keeps the lowest `bits` bits of a field element
+  fn mask<F: PrimeField>(f: F, bits: usize) -> F {
+    let mut word = field_word(&f);
+    for (i, b) in word.iter_mut().enumerate() {
+      let low = (256 - 8 * i).saturating_sub(bits).min(8);
+      *b &= 0xffu8.checked_shr(low as u32).unwrap_or(0);
+    }
+    word_mod(&word)
+  }
+
+  /// A model of `Keccak256Transcript` at the level of bytes, as in the verifier
+  struct Transcript {
+    lo: Word,
+    hi: Word,
+    round: u16,
+    buf: Vec<u8>,
+  }
+
+  impl Transcript {
+    fn halves(input: &[u8]) -> (Word, Word) {
+      (
+        Keccak256::digest([input, &[0]].concat()).into(),
+        Keccak256::digest([input, &[1]].concat()).into(),
+      )
+    }
+
+    fn new(label: &[u8]) -> Self {
+      let (lo, hi) = Self::halves(&[b"NoTR", label].concat());
+      Self {
+        lo,
+        hi,
+        round: 0,
+        buf: Vec::new(),
+      }
+    }
+
+    fn absorb(&mut self, label: &[u8], bytes: &[u8]) {
+      self.buf.extend(label);
+      self.buf.extend(bytes);
+    }
+
+    fn dom_sep(&mut self, tag: &[u8]) {
+      self.buf.extend(b"NoDS");
+      self.buf.extend(tag);
+    }
+
+    fn squeeze<F: PrimeField>(&mut self, label: &[u8]) -> F {
+      let input = [
+        &self.buf[..],
+        b"NoDS",
+        &self.round.to_le_bytes(),
+        &self.lo,
+        &self.hi,
+        label,
+      ]
+      .concat();
+      (self.lo, self.hi) = Self::halves(&input);
+      self.round += 1;
+      self.buf.clear();
+      self.challenge()
+    }
+
+    /// Reads the state as a little-endian integer and reduces it modulo the characteristic of `F`
+    fn challenge<F: PrimeField>(&self) -> F {
+      let lo = word_mod::<F>(&self.lo.iter().rev().copied().collect::<Vec<_>>());
+      let hi = word_mod::<F>(&self.hi.iter().rev().copied().collect::<Vec<_>>());
+      lo + hi * F::from(2).pow_vartime([256])
+    }
+  }
+
+  pub(super) fn io_tag_pub(n: usize) -> u128 {
+    io_tag(n)
+  }
+
+  pub(super) fn word_mod_pub<F: PrimeField>(w: &Word) -> F {
+    word_mod(w)
+  }
+
+  fn io_tag(n: usize) -> u128 {
+    let x = u128::MAX - 158;
+    x.wrapping_mul(n as u128 + (1 << 31))
+      .wrapping_add(x.wrapping_mul(x))
+  }
+
+  fn poseidon_len(full_rounds: usize, partial_rounds: usize) -> usize {
+    let w = POSEIDON_WIDTH;
+    full_rounds * w + partial_rounds + 2 * w * w + partial_rounds * (2 * w - 1)
+  }
+
+  /// A model of the sponge of Nova's random oracles over the constants laid out for the verifier
+  fn poseidon<F: PrimeField>(input: &[F], c: &[F], full_rounds: usize, partial_rounds: usize) -> F {
+    let w = POSEIDON_WIDTH;
+    let (mds, pre_sparse) = (
+      full_rounds * w + partial_rounds,
+      full_rounds * w + partial_rounds + w * w,
+    );
+    let sparse = pre_sparse + w * w;
+    let half = full_rounds / 2;
+    let rounds = full_rounds + partial_rounds;
+    let pow5 = |x: F| x.square().square() * x;
+
+    let permute = |s: &mut Vec<F>| {
+      for i in 0..w {
+        s[i] += c[i];
+      }
+      let mut k = w;
+      for round in 0..rounds {
+        let partial = round >= half && round < half + partial_rounds;
+        if partial {
+          s[0] = pow5(s[0]) + c[k];
+          k += 1;
+        } else if round + 1 == rounds {
+          for x in s.iter_mut() {
+            *x = pow5(*x);
+          }
+        } else {
+          for i in 0..w {
+            s[i] = pow5(s[i]) + c[k + i];
+          }
+          k += w;
+        }
+
+        *s = if round + 1 == half {
+          (0..w)
+            .map(|j| (0..w).map(|i| c[pre_sparse + i * w + j] * s[i]).sum())
+            .collect()
+        } else if partial {
+          let m = sparse + (round - half) * (2 * w - 1);
+          let first = (0..w).map(|i| c[m + i] * s[i]).sum();
+          std::iter::once(first)
+            .chain((1..w).map(|j| s[j] + c[m + w + j - 1] * s[0]))
+            .collect()
+        } else {
+          (0..w)
+            .map(|i| (0..w).map(|j| c[mds + i * w + j] * s[j]).sum())
+            .collect()
+        };
+      }
+    };
+
+    let mut s = vec![F::ZERO; w];
+    s[0] = F::from_u128(io_tag(input.len()));
+    let mut pos = 0;
+    for x in input {
+      if pos == w - 1 {
+        permute(&mut s);
+        pos = 0;
+      }
+      s[pos + 1] += x;
+      pos += 1;
+    }
+    permute(&mut s);
+    s[1]
+  }
+
+  /// Decodes the calldata of `verify(uint256,uint256[],uint256[],uint256[])`
+  fn decode(calldata: &[u8]) -> (u64, Vec<Word>, Vec<Word>, Vec<Word>) {
+    assert_eq!(calldata[..4], Keccak256::digest(VERIFY_SIGNATURE)[..4]);
+    let words = calldata[4..]
+      .chunks(32)
+      .map(|w| Word::try_from(w).unwrap())
+      .collect::<Vec<_>>();
+    let int = |w: &Word| u64::from_be_bytes(w[24..].try_into().unwrap()) as usize;
+    let array = |head: usize| {
+      let start = int(&words[head]) / 32;
+      words[start + 1..start + 1 + int(&words[start])].to_vec()
+    };
+    (int(&words[0]) as u64, array(1), array(2), array(3))
+  }
+
+  #[test]
+  fn test_solidity_poseidon() {
+    fn test_with<F>()
+    where
+      F: PrimeField + PrimeFieldBits + serde::Serialize + for<'de> serde::Deserialize<'de>,
+    {
+      let constants = PoseidonConstantsCircuit::<F>::default();
+      let mut words = Vec::new();
+      let (full, partial) = poseidon_words(&constants, &mut words);
+      assert_eq!(words.len(), poseidon_len(full, partial));
+      let c = words.iter().map(|w| word_mod(w)).collect::<Vec<F>>();
+
+      for n in [1, 9, 20, 23, 24, 25, 34, 48] {
+        let input = (0..n).map(|_| F::random(OsRng)).collect::<Vec<_>>();
+        let mut ro = PoseidonRO::<F>::new(constants.clone());
+        for x in &input {
+          ro.absorb(*x);
+        }
+        assert_eq!(
+          ro.squeeze(250),
+          mask(poseidon(&input, &c, full, partial), 250)
+        );
+      }
+    }
+
+    test_with::<Fr>();
+    test_with::<Fq>();
+  }
+
+  #[test]
+  fn test_solidity_transcript() {
+    fn test_with<E: Engine>()
+    where
+      E::GE: crate::provider::traits::DlogGroup,
+    {
+      let mut transcript = Keccak256Transcript::<E>::new(b"test");
+      let mut model = Transcript::new(b"test");
+      let mut rng = OsRng;
+
+      for round in 0..3 {
+        let s = E::Scalar::random(&mut rng);
+        transcript.absorb(b"s", &s);
+        model.absorb(b"s", &field_word(&s));
+
+        let poly = UniPoly::<E::Scalar> {
+          coeffs: (0..4).map(|_| E::Scalar::random(&mut rng)).collect(),
+        };
+        transcript.absorb(b"p", &poly);
+        let repr = poly
+          .compress()
+          .coeffs_except_linear_term
+          .iter()
+          .flat_map(|c| field_word(c).into_iter().rev())
+          .collect::<Vec<_>>();
+        model.absorb(b"p", &repr);
+
+        let v = (0..3)
+          .map(|_| E::Scalar::random(&mut rng))
+          .collect::<Vec<_>>();
+        transcript.absorb(b"v", &v.as_slice());
+        model.absorb(b"v", &v.iter().flat_map(field_word).collect::<Vec<_>>());
+
+        if round == 1 {
+          transcript.dom_sep(b"IPA");
+          model.dom_sep(b"IPA");
+        }
+
+        let c: E::Scalar = transcript.squeeze(b"c").unwrap();
+        assert_eq!(c, model.squeeze::<E::Scalar>(b"c"));
+      }
+
+      // a challenge where the state of the transcript exceeds the modulus
+      model.lo = [0xff; 32];
+      model.hi = [0xff; 32];
+      let c = E::Scalar::from_uniform(&[[0xffu8; 32], [0xff; 32]].concat());
+      assert_eq!(model.challenge::<E::Scalar>(), c);
+    }
+
+    test_with::<E1>();
+    test_with::<E2>();
+  }
+
+  /// Compiles the verifier with the `solc` given by the `SOLC` environment variable, or else the one
+  /// on the `PATH`, and returns its creation code
+  fn compile(source: &str) -> Vec<u8> {
+    let solc = std::env::var("SOLC").unwrap_or_else(|_| "solc".to_string());
+    let dir = std::env::temp_dir().join(format!("nova-test-solidity-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("NovaVerifier.sol");
+    std::fs::write(&path, source).unwrap();
+    let output = std::process::Command::new(&solc)
+      .args(["--via-ir", "--optimize", "--bin", "-o"])
+      .arg(&dir)
+      .arg("--overwrite")
+      .arg(&path)
+      .output()
+      .unwrap_or_else(|_| panic!("cannot run {solc}"));
+    let bin = std::fs::read_to_string(dir.join("NovaVerifier.bin"));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(
+      output.status.success(),
+      "solc failed: {}",
+      String::from_utf8_lossy(&output.stderr)
+    );
+    hex::decode(bin.unwrap().trim()).unwrap()
+  }
+
+  /// The gas limit of a transaction, as capped by EIP-7825
+  const TX_GAS_LIMIT: u64 = 1 << 24;
+
+  /// An EVM on which a verifier and its data contracts are deployed
+  struct Chain {
+    evm: Evm<'static, (), CacheDB<EmptyDB>>,
+    verifier: Address,
+  }
+
+  impl Chain {
+    fn new(verifier: &SolidityVerifier) -> Self {
+      let evm = Evm::builder()
+        .with_db(CacheDB::new(EmptyDB::default()))
+        .modify_cfg_env(|cfg| cfg.limit_contract_code_size = Some(usize::MAX))
+        .modify_block_env(|block| block.gas_limit = U256::from(u64::MAX))
+        .build();
+      let mut chain = Self {
+        evm,
+        verifier: Address::ZERO,
+      };
+
+      let mut args = [usize_word(32), usize_word(verifier.data_contracts.len())].concat();
+      for code in &verifier.data_contracts {
+        let mut word = [0u8; 32];
+        word[12..].copy_from_slice(chain.deploy(code.clone()).as_slice());
+        args.extend(word);
+      }
+      chain.verifier = chain.deploy([compile(&verifier.source), args].concat());
+      chain
+    }
+
+    fn run(&mut self, to: TxKind, data: Vec<u8>, gas_limit: u64) -> ExecutionResult {
+      self.evm.context.evm.env.tx.transact_to = to;
+      self.evm.context.evm.env.tx.data = data.into();
+      self.evm.context.evm.env.tx.gas_limit = gas_limit;
+      self.evm.transact_commit().unwrap()
+    }
+
+    fn deploy(&mut self, code: Vec<u8>) -> Address {
+      match self.run(TxKind::Create, code, u64::MAX / 2) {
+        ExecutionResult::Success {
+          output: Output::Create(_, Some(address)),
+          ..
+        } => address,
+        result => panic!("deployment failed: {result:?}"),
+      }
+    }
+
+    /// Calls the verifier, and returns its logs and output if the call succeeds
+    fn call(&mut self, data: Vec<u8>, gas_limit: u64) -> Option<(Vec<Log>, Vec<u8>, u64)> {
+      match self.run(TxKind::Call(self.verifier), data, gas_limit) {
+        ExecutionResult::Success {
+          logs,
+          output,
+          gas_used,
+          ..
+        } => Some((logs, output.into_data().to_vec(), gas_used)),
+        ExecutionResult::Revert { .. } => None,
+        result => panic!("call failed: {result:?}"),
+      }
+    }
+  }
+
+  /// Encodes a call to `verify` or `start` from the words of the calldata of `verify`
+  fn encode(signature: &[u8], steps: u64, z0: &[Word], zn: &[Word], proof: &[Word]) -> Vec<u8> {
+    let mut calldata = Keccak256::digest(signature)[..4].to_vec();
+    calldata.extend(usize_word(steps as usize));
+    let mut offset = 3 * 32;
+    for array in [z0, zn, proof] {
+      calldata.extend(usize_word(offset));
+      offset += 32 * (1 + array.len());
+    }
+    for array in [z0, zn, proof] {
+      calldata.extend(usize_word(array.len()));
+      calldata.extend(array.concat());
+    }
+    calldata
+  }
+
+  /// Decodes the words of the dynamic array encoded at `data`
+  fn words(data: &[u8]) -> Vec<Word> {
+    let len = u64::from_be_bytes(data[56..64].try_into().unwrap()) as usize;
+    data[64..64 + 32 * len]
+      .chunks(32)
+      .map(|w| Word::try_from(w).unwrap())
+      .collect()
+  }
+
+  #[test]
+  fn test_solidity_verifier() {
+    let circuit = C::new(3);
+    let pp =
+      PublicParams::<E1, E2, C>::setup(&circuit, &*S1::ck_floor(), &*S2::ck_floor()).unwrap();
+
+    let num_steps = 3;
+    let z0 = [Fr::from(3u64)];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    for _ in 0..num_steps {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+    }
+
+    let (pk, vk) = CompressedSNARK::<_, _, _, S1, S2>::setup(&pp).unwrap();
+    let snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+    snark.verify(&vk, num_steps, &z0).unwrap();
+
+    let verifier = vk.to_solidity().unwrap();
+    let layout = vk.layout().unwrap();
+
+    // the source is complete and carries the parameters of the verifier key
+    let source = &verifier.source;
+    assert!(!source.contains("{{"));
+    assert_eq!(source.matches('{').count(), source.matches('}').count());
+    assert!(source.contains(&format!(
+      "uint256 internal constant PROOF_WORDS = {};",
+      layout.proof_words()
+    )));
+    assert!(source.contains(&format!(
+      "bytes32[{}] memory hashes",
+      verifier.data_contracts.len()
+    )));
+
+    // each data contract returns a STOP byte followed by its chunk of the data
+    let mut data: Vec<u8> = Vec::new();
+    for code in &verifier.data_contracts {
+      let len = u32::from_be_bytes(code[1..5].try_into().unwrap()) as usize;
+      assert_eq!(code.len(), 14 + len);
+      assert!(len <= 24576);
+      assert_eq!(code[14], 0);
+      data.extend(&code[15..]);
+    }
+    assert_eq!(data, layout.data.concat());
+
+    // the calldata carries the public inputs and a proof of the expected length
+    let calldata = snark.to_calldata(num_steps, &z0).unwrap();
+    let (steps, z0_words, zn_words, proof) = decode(&calldata);
+    assert_eq!(steps, num_steps as u64);
+    assert_eq!(z0_words, vec![field_word(&z0[0])]);
+    assert_eq!(
+      zn_words,
+      vec![field_word(&Fr::from(3u64).pow_vartime([512]))]
+    );
+    assert_eq!(proof.len(), layout.proof_words());
+  }
+
+  /// Runs the generated verifier on an EVM, with `solc` as in `compile`
+  #[test]
+  #[ignore = "requires solc"]
+  fn test_solidity_evm() {
+    let circuit = C::new(3);
+    let pp =
+      PublicParams::<E1, E2, C>::setup(&circuit, &*S1::ck_floor(), &*S2::ck_floor()).unwrap();
+
+    let num_steps = 3;
+    let z0 = [Fr::from(3u64)];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    for _ in 0..num_steps {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+    }
+    let (pk, vk) = CompressedSNARK::<_, _, _, S1, S2>::setup(&pp).unwrap();
+    let snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+
+    let mut chain = Chain::new(&vk.to_solidity().unwrap());
+    let calldata = snark.to_calldata(num_steps, &z0).unwrap();
+    let (steps, z0_words, zn_words, proof) = decode(&calldata);
+    let verify = |chain: &mut Chain, steps, zn: &[Word], proof: &[Word]| {
+      let calldata = encode(VERIFY_SIGNATURE, steps, &z0_words, zn, proof);
+      chain.call(calldata, u64::MAX / 2).is_some()
+    };
+
+    // `verify` accepts the proof in a single call
+    assert!(chain.call(calldata.clone(), u64::MAX / 2).is_some());
+
+    // `start` and `resume` accept the proof, with each transaction under the gas limit
+    let start = b"start(uint256,uint256[],uint256[],uint256[])";
+    let (logs, _, gas) = chain
+      .call(encode(start, steps, &z0_words, &zn_words, &proof), TX_GAS_LIMIT)
+      .expect("start failed");
+    assert!(gas < TX_GAS_LIMIT);
+    let (session, statement) = (logs[0].topics()[1], logs[0].topics()[2]);
+    let claim = words(&logs[0].data.data);
+
+    let resume = |session: &[u8], claim: &[Word]| {
+      let mut calldata = Keccak256::digest(b"resume(bytes32,uint256[])")[..4].to_vec();
+      calldata.extend(session);
+      calldata.extend(usize_word(64));
+      calldata.extend(usize_word(claim.len()));
+      calldata.extend(claim.concat());
+      calldata
+    };
+    let mut bad_claim = claim.clone();
+    bad_claim[0][31] ^= 1;
+    assert!(chain
+      .call(resume(session.as_slice(), &bad_claim), TX_GAS_LIMIT)
+      .is_none());
+
+    let mut steps_run = 0;
+    loop {
+      let (logs, output, gas) = chain
+        .call(resume(session.as_slice(), &claim), TX_GAS_LIMIT)
+        .expect("resume failed");
+      assert!(gas < TX_GAS_LIMIT, "step {steps_run} used {gas} gas");
+      steps_run += 1;
+      if output[31] == 1 {
+        assert_eq!(logs[0].topics()[1], statement);
+        break;
+      }
+    }
+    assert!(steps_run > 1);
+    let mut calldata = Keccak256::digest(b"verified(bytes32)")[..4].to_vec();
+    calldata.extend(statement.as_slice());
+    let (_, output, _) = chain.call(calldata, TX_GAS_LIMIT).unwrap();
+    assert_eq!(output[31], 1);
+
+    // the verifier rejects wrong public inputs and tampered proofs
+    assert!(!verify(&mut chain, steps + 1, &zn_words, &proof));
+    let mut zn_bad = zn_words.clone();
+    zn_bad[0][31] ^= 1;
+    assert!(!verify(&mut chain, steps, &zn_bad, &proof));
+    assert!(!verify(&mut chain, steps, &zn_words, &proof[1..]));
+
+    let mut rng = OsRng;
+    let positions = (0..FOLDING_WORDS)
+      .chain((0..8).map(|_| rng.gen_range(FOLDING_WORDS..proof.len())))
+      .chain([proof.len() - 1]);
+    for pos in positions {
+      let mut tampered = proof.clone();
+      tampered[pos][31] ^= 1;
+      assert!(
+        !verify(&mut chain, steps, &zn_words, &tampered),
+        "tampered word {pos} was accepted"
+      );
+    }
+  }
+
+  #[test]
+  #[ignore]
+  fn scratch_dump() {
+    let circuit = C::new(3);
+    let pp =
+      PublicParams::<E1, E2, C>::setup(&circuit, &*S1::ck_floor(), &*S2::ck_floor()).unwrap();
+    let num_steps = 3;
+    let z0 = [Fr::from(3u64)];
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+    for _ in 0..num_steps {
+      recursive_snark.prove_step(&pp, &circuit).unwrap();
+    }
+    let (pk, vk) = CompressedSNARK::<_, _, _, S1, S2>::setup(&pp).unwrap();
+    let snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+    snark.verify(&vk, num_steps, &z0).unwrap();
+    let v = vk.to_solidity().unwrap();
+    std::fs::create_dir_all("/tmp/sol").unwrap();
+    std::fs::write("/tmp/sol/gen.sol", &v.source).unwrap();
+    for (i, c) in v.data_contracts.iter().enumerate() {
+      std::fs::write(format!("/tmp/sol/data{i}.hex"), hex::encode(c)).unwrap();
+    }
+    std::fs::write("/tmp/sol/calldata.hex", hex::encode(snark.to_calldata(num_steps, &z0).unwrap())).unwrap();
+  }
+}
+
+#[cfg(test)]
+mod scratch_poseidon {
+  use super::*;
+  use crate::provider::poseidon::{PoseidonConstantsCircuit, PoseidonRO};
+  use crate::traits::ROTrait;
+  use ff::{Field, PrimeFieldBits};
+
+  fn run<F: PrimeField + PrimeFieldBits + serde::Serialize + for<'de> serde::Deserialize<'de>>() {
+    let mut words = Vec::new();
+    poseidon_words(&PoseidonConstantsCircuit::<F>::default(), &mut words);
+    println!("c0 {} c24 {} len {}", hex::encode(words[0]), hex::encode(words[24]), words.len());
+    for n in [1usize, 3, 20, 24, 25] {
+      let mut ro = PoseidonRO::<F>::new(PoseidonConstantsCircuit::<F>::default());
+      for i in 0..n {
+        ro.absorb(F::from((i + 1) as u64));
+      }
+      println!("n={n} {}", hex::encode(field_word(&ro.squeeze(250))));
+    }
+  }
+
+  #[test]
+  fn scratch_permute() {
+    type F = halo2curves::bn256::Fr;
+    let constants = PoseidonConstantsCircuit::<F>::default();
+    let mut words = Vec::new();
+    let (full_rounds, partial_rounds) = poseidon_words(&constants, &mut words);
+    let c = words.iter().map(|w| tests::word_mod_pub::<F>(w)).collect::<Vec<F>>();
+    let w = POSEIDON_WIDTH;
+    let (mds, pre_sparse) = (full_rounds * w + partial_rounds, full_rounds * w + partial_rounds + w * w);
+    let sparse = pre_sparse + w * w;
+    let half = full_rounds / 2;
+    let rounds = full_rounds + partial_rounds;
+    let pow5 = |x: F| x.square().square() * x;
+    let mut s = vec![F::ZERO; w];
+    s[0] = F::from_u128(tests::io_tag_pub(1));
+    s[1] = F::ONE;
+    for i in 0..w {
+      s[i] += c[i];
+    }
+    let mut k = w;
+    for round in 0..rounds {
+      let partial = round >= half && round < half + partial_rounds;
+      if partial {
+        s[0] = pow5(s[0]) + c[k];
+        k += 1;
+      } else if round + 1 == rounds {
+        for x in s.iter_mut() {
+          *x = pow5(*x);
+        }
+      } else {
+        for i in 0..w {
+          s[i] = pow5(s[i]) + c[k + i];
+        }
+        k += w;
+      }
+      s = if round + 1 == half {
+        (0..w).map(|j| (0..w).map(|i| c[pre_sparse + i * w + j] * s[i]).sum()).collect()
+      } else if partial {
+        let m = sparse + (round - half) * (2 * w - 1);
+        let first = (0..w).map(|i| c[m + i] * s[i]).sum();
+        std::iter::once(first).chain((1..w).map(|j| s[j] + c[m + w + j - 1] * s[0])).collect()
+      } else {
+        (0..w).map(|i| (0..w).map(|j| c[mds + i * w + j] * s[j]).sum()).collect()
+      };
+      println!("round {} s0 {} s1 {}", round + 1, hex::encode(field_word(&s[0])), hex::encode(field_word(&s[1])));
+    }
+  }
+
+  #[test]
+  fn scratch_poseidon() {
+    run::<halo2curves::bn256::Fr>();
+    run::<halo2curves::bn256::Fq>();
+  }
+}
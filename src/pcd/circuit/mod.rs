@@ -0,0 +1,648 @@
+//! There are two augmented circuits for proof-carrying data: the primary and the secondary.
+//! Each node of a PCD tree covers a range of steps `[start, end)` of an incremental computation
+//! and is either a leaf, which executes a single step, or a merge of two nodes over adjacent ranges.
+//!
+//! The primary circuit of a node takes as input the last secondary instance of each of its children,
+//! each carrying the hash H(params, start, end, z_start, z_end, U) of the child's secondary running instance U.
+//! It checks the hashes, folds both children's secondary running instances, and outputs the hash of the node.
+//! The secondary circuit of a node folds the running instances of its children's primary circuits
+//! along with the instance of the node's primary circuit, and outputs the hash H(params, U) of the result.
+
+use crate::{
+  constants::{NUM_CHALLENGE_BITS, NUM_HASH_BITS},
+  frontend::{
+    num::AllocatedNum, AllocatedBit, Assignment, Boolean, ConstraintSystem, SynthesisError,
+  },
+  gadgets::{
+    ecc::AllocatedPoint,
+    utils::{
+      alloc_scalar_as_base, alloc_zero, conditionally_select, conditionally_select_vec,
+      le_bits_to_num,
+    },
+  },
+  r1cs::{R1CSInstance, RelaxedR1CSInstance},
+  traits::{
+    circuit::StepCircuit, commitment::CommitmentTrait, Engine, ROCircuitTrait, ROConstantsCircuit,
+  },
+  Commitment,
+};
+use ff::Field;
+use serde::{Deserialize, Serialize};
+
+pub(crate) mod r1cs;
+use r1cs::{AllocatedR1CSInstance, AllocatedRelaxedR1CSInstance};
+
+/// The number of public IO of the primary circuit:
+/// the hashes of the secondary running instances of both children, and the hash of the node
+pub(crate) const NUM_IO_PRIMARY: usize = 3;
+
+/// The number of public IO of the secondary circuit:
+/// the hash of the node, and the hash of its primary running instance
+pub(crate) const NUM_IO_SECONDARY: usize = 2;
+
+/// The inputs of the primary circuit that describe one of the children of a merged node
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct PCDChildInputs<E: Engine> {
+  start: E::Base,
+  end: E::Base,
+  z_start: Vec<E::Base>,
+  z_end: Vec<E::Base>,
+  U: RelaxedR1CSInstance<E>,
+  u: R1CSInstance<E>,
+  T: Commitment<E>,
+}
+
+impl<E: Engine> PCDChildInputs<E> {
+  /// Create new inputs/witness for a child of a merged node
+  pub fn new(
+    start: E::Base,
+    end: E::Base,
+    z_start: Vec<E::Base>,
+    z_end: Vec<E::Base>,
+    U: RelaxedR1CSInstance<E>,
+    u: R1CSInstance<E>,
+    T: Commitment<E>,
+  ) -> Self {
+    Self {
+      start,
+      end,
+      z_start,
+      z_end,
+      U,
+      u,
+      T,
+    }
+  }
+}
+
+/// The inputs of the primary circuit
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct PCDPrimaryCircuitInputs<E: Engine> {
+  pp_digest: E::Scalar,
+  i: E::Base,
+  zi: Vec<E::Base>,
+  children: Option<(PCDChildInputs<E>, PCDChildInputs<E>, Commitment<E>)>,
+}
+
+impl<E: Engine> PCDPrimaryCircuitInputs<E> {
+  /// Create new inputs/witness for the primary circuit of a leaf, which executes step `i` on `zi`
+  pub fn leaf(pp_digest: E::Scalar, i: E::Base, zi: Vec<E::Base>) -> Self {
+    Self {
+      pp_digest,
+      i,
+      zi,
+      children: None,
+    }
+  }
+
+  /// Create new inputs/witness for the primary circuit of a node that merges `left` and `right`,
+  /// where `T` is the cross-term of folding their secondary running instances
+  pub fn merge(
+    pp_digest: E::Scalar,
+    left: PCDChildInputs<E>,
+    right: PCDChildInputs<E>,
+    T: Commitment<E>,
+  ) -> Self {
+    // the step circuit is not used by a merged node, but it still needs a valid input
+    let (i, zi) = (left.start, left.z_start.clone());
+    Self {
+      pp_digest,
+      i,
+      zi,
+      children: Some((left, right, T)),
+    }
+  }
+}
+
+/// The inputs of the secondary circuit
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct PCDSecondaryCircuitInputs<E: Engine> {
+  pp_digest: E::Scalar,
+  u: R1CSInstance<E>,
+  children: Option<(
+    RelaxedR1CSInstance<E>,
+    RelaxedR1CSInstance<E>,
+    Commitment<E>,
+    Commitment<E>,
+  )>,
+}
+
+impl<E: Engine> PCDSecondaryCircuitInputs<E> {
+  /// Create new inputs/witness for the secondary circuit of a leaf with primary instance `u`
+  pub fn leaf(pp_digest: E::Scalar, u: R1CSInstance<E>) -> Self {
+    Self {
+      pp_digest,
+      u,
+      children: None,
+    }
+  }
+
+  /// Create new inputs/witness for the secondary circuit of a merged node with primary instance `u`,
+  /// where `T_u` is the cross-term of folding `u` into `U_left`, and `T` is the cross-term of
+  /// folding `U_right` into the result
+  pub fn merge(
+    pp_digest: E::Scalar,
+    u: R1CSInstance<E>,
+    U_left: RelaxedR1CSInstance<E>,
+    U_right: RelaxedR1CSInstance<E>,
+    T_u: Commitment<E>,
+    T: Commitment<E>,
+  ) -> Self {
+    Self {
+      pp_digest,
+      u,
+      children: Some((U_left, U_right, T_u, T)),
+    }
+  }
+}
+
+/// The allocated inputs of the primary circuit that describe a child of a merged node
+struct AllocatedChild<E: Engine> {
+  start: AllocatedNum<E::Base>,
+  end: AllocatedNum<E::Base>,
+  z_start: Vec<AllocatedNum<E::Base>>,
+  z_end: Vec<AllocatedNum<E::Base>>,
+  U: AllocatedRelaxedR1CSInstance<E>,
+  u: AllocatedR1CSInstance<E>,
+  T: AllocatedPoint<E>,
+}
+
+/// The primary augmented circuit of a PCD node, which includes a step circuit F
+/// and the circuit for the verifier of the folding schemes used to merge two nodes
+pub struct PCDPrimaryCircuit<'a, E: Engine, SC: StepCircuit<E::Base>> {
+  ro_consts: ROConstantsCircuit<E>,
+  inputs: Option<PCDPrimaryCircuitInputs<E>>,
+  step_circuit: &'a SC, // The function that is applied at each leaf
+}
+
+impl<'a, E: Engine, SC: StepCircuit<E::Base>> PCDPrimaryCircuit<'a, E, SC> {
+  /// Create a new primary circuit for the provided inputs
+  pub const fn new(
+    inputs: Option<PCDPrimaryCircuitInputs<E>>,
+    step_circuit: &'a SC,
+    ro_consts: ROConstantsCircuit<E>,
+  ) -> Self {
+    Self {
+      inputs,
+      step_circuit,
+      ro_consts,
+    }
+  }
+
+  /// Allocate the inputs that describe the left (`is_left = true`) or the right child
+  fn alloc_child<CS: ConstraintSystem<<E as Engine>::Base>>(
+    &self,
+    mut cs: CS,
+    arity: usize,
+    is_left: bool,
+  ) -> Result<AllocatedChild<E>, SynthesisError> {
+    // In a leaf, the children are allocated with default values
+    let child = self
+      .inputs
+      .as_ref()
+      .and_then(|inputs| inputs.children.as_ref())
+      .map(|(left, right, _)| if is_left { left } else { right });
+
+    let start = AllocatedNum::alloc(cs.namespace(|| "start"), || {
+      Ok(child.map_or(E::Base::ZERO, |c| c.start))
+    })?;
+    let end = AllocatedNum::alloc(cs.namespace(|| "end"), || {
+      Ok(child.map_or(E::Base::ZERO, |c| c.end))
+    })?;
+
+    let z_start = (0..arity)
+      .map(|i| {
+        AllocatedNum::alloc(cs.namespace(|| format!("z_start_{i}")), || {
+          Ok(child.map_or(E::Base::ZERO, |c| c.z_start[i]))
+        })
+      })
+      .collect::<Result<Vec<AllocatedNum<E::Base>>, _>>()?;
+    let z_end = (0..arity)
+      .map(|i| {
+        AllocatedNum::alloc(cs.namespace(|| format!("z_end_{i}")), || {
+          Ok(child.map_or(E::Base::ZERO, |c| c.z_end[i]))
+        })
+      })
+      .collect::<Result<Vec<AllocatedNum<E::Base>>, _>>()?;
+
+    let U = AllocatedRelaxedR1CSInstance::alloc(
+      cs.namespace(|| "allocate U"),
+      child.map(|c| &c.U),
+      NUM_IO_SECONDARY,
+    )?;
+
+    let u = AllocatedR1CSInstance::alloc(
+      cs.namespace(|| "allocate instance u to fold"),
+      child.map(|c| &c.u),
+      NUM_IO_SECONDARY,
+    )?;
+
+    let T = AllocatedPoint::alloc(
+      cs.namespace(|| "allocate T"),
+      child.map(|c| c.T.to_coordinates()),
+    )?;
+    T.check_on_curve(cs.namespace(|| "check T on curve"))?;
+
+    Ok(AllocatedChild {
+      start,
+      end,
+      z_start,
+      z_end,
+      U,
+      u,
+      T,
+    })
+  }
+
+  /// Compute H(pp_digest, start, end, z_start, z_end, U)
+  fn synthesize_hash<CS: ConstraintSystem<E::Base>>(
+    &self,
+    mut cs: CS,
+    pp_digest: &AllocatedNum<E::Base>,
+    start: &AllocatedNum<E::Base>,
+    end: &AllocatedNum<E::Base>,
+    z_start: &[AllocatedNum<E::Base>],
+    z_end: &[AllocatedNum<E::Base>],
+    U: &AllocatedRelaxedR1CSInstance<E>,
+  ) -> Result<AllocatedNum<E::Base>, SynthesisError> {
+    let mut ro = E::ROCircuit::new(self.ro_consts.clone());
+    ro.absorb(pp_digest);
+    ro.absorb(start);
+    ro.absorb(end);
+    for e in z_start {
+      ro.absorb(e);
+    }
+    for e in z_end {
+      ro.absorb(e);
+    }
+    U.absorb_in_ro(cs.namespace(|| "absorb U"), &mut ro)?;
+
+    let hash_bits = ro.squeeze(cs.namespace(|| "hash"), NUM_HASH_BITS)?;
+    le_bits_to_num(cs.namespace(|| "bits to hash"), &hash_bits)
+  }
+
+  /// Synthesizes the merge of two children and returns the new secondary running instance.
+  /// The checks are only enforced if this is not a leaf.
+  fn synthesize_merge<CS: ConstraintSystem<<E as Engine>::Base>>(
+    &self,
+    mut cs: CS,
+    pp_digest: &AllocatedNum<E::Base>,
+    is_leaf: &AllocatedBit,
+    left: &AllocatedChild<E>,
+    right: &AllocatedChild<E>,
+    T: &AllocatedPoint<E>,
+  ) -> Result<AllocatedRelaxedR1CSInstance<E>, SynthesisError> {
+    // Check that u.X[0] = H(pp_digest, start, end, z_start, z_end, U) for each child
+    for (name, child) in [("left", left), ("right", right)] {
+      let hash = self.synthesize_hash(
+        cs.namespace(|| format!("{name} input hash")),
+        pp_digest,
+        &child.start,
+        &child.end,
+        &child.z_start,
+        &child.z_end,
+        &child.U,
+      )?;
+      enforce_equal_unless(
+        cs.namespace(|| format!("check {name} u.X[0] = H(params, start, end, z_start, z_end, U)")),
+        is_leaf,
+        &child.u.X[0],
+        &hash,
+      );
+    }
+
+    // Check that the children cover adjacent ranges
+    enforce_equal_unless(
+      cs.namespace(|| "check left.end = right.start"),
+      is_leaf,
+      &left.end,
+      &right.start,
+    );
+    for (i, (a, b)) in left.z_end.iter().zip(right.z_start.iter()).enumerate() {
+      enforce_equal_unless(
+        cs.namespace(|| format!("check left.z_end[{i}] = right.z_start[{i}]")),
+        is_leaf,
+        a,
+        b,
+      );
+    }
+
+    // Fold the last instance of each child into its running instance
+    let U_left = left.U.fold_with_r1cs(
+      cs.namespace(|| "fold left u into left U"),
+      pp_digest,
+      &left.u,
+      &left.T,
+      self.ro_consts.clone(),
+    )?;
+    let U_right = right.U.fold_with_r1cs(
+      cs.namespace(|| "fold right u into right U"),
+      pp_digest,
+      &right.u,
+      &right.T,
+      self.ro_consts.clone(),
+    )?;
+
+    // The instances bind both folded running instances, so the challenge is derived from them
+    let mut ro = E::ROCircuit::new(self.ro_consts.clone());
+    ro.absorb(pp_digest);
+    for child in [left, right] {
+      child.u.absorb_in_ro(&mut ro);
+      ro.absorb(&child.T.x);
+      ro.absorb(&child.T.y);
+      ro.absorb(&child.T.is_infinity);
+    }
+    ro.absorb(&T.x);
+    ro.absorb(&T.y);
+    ro.absorb(&T.is_infinity);
+    let r_bits = ro.squeeze(cs.namespace(|| "r bits"), NUM_CHALLENGE_BITS)?;
+
+    U_left.fold_with_relaxed_r1cs(
+      cs.namespace(|| "fold right U into left U"),
+      &U_right,
+      T,
+      &r_bits,
+    )
+  }
+
+  /// synthesize circuit giving constraint system
+  pub fn synthesize<CS: ConstraintSystem<<E as Engine>::Base>>(
+    self,
+    cs: &mut CS,
+  ) -> Result<Vec<AllocatedNum<E::Base>>, SynthesisError> {
+    let arity = self.step_circuit.arity();
+
+    // Allocate all witnesses
+    let pp_digest = alloc_scalar_as_base::<E, _>(
+      cs.namespace(|| "pp_digest"),
+      self.inputs.as_ref().map(|inputs| inputs.pp_digest),
+    )?;
+    let is_leaf = AllocatedBit::alloc(
+      cs.namespace(|| "is_leaf"),
+      self.inputs.as_ref().map(|inputs| inputs.children.is_none()),
+    )?;
+    let i = AllocatedNum::alloc(cs.namespace(|| "i"), || Ok(self.inputs.get()?.i))?;
+    let z_i = (0..arity)
+      .map(|j| {
+        AllocatedNum::alloc(cs.namespace(|| format!("zi_{j}")), || {
+          Ok(self.inputs.get()?.zi[j])
+        })
+      })
+      .collect::<Result<Vec<AllocatedNum<E::Base>>, _>>()?;
+    let left = self.alloc_child(cs.namespace(|| "allocate left child"), arity, true)?;
+    let right = self.alloc_child(cs.namespace(|| "allocate right child"), arity, false)?;
+    let T = AllocatedPoint::alloc(
+      cs.namespace(|| "allocate T"),
+      self
+        .inputs
+        .as_ref()
+        .and_then(|inputs| inputs.children.as_ref())
+        .map(|(_, _, T)| T.to_coordinates()),
+    )?;
+    T.check_on_curve(cs.namespace(|| "check T on curve"))?;
+
+    // Compute z_{i+1} = F(z_i) for a leaf
+    let z_next = self
+      .step_circuit
+      .synthesize(&mut cs.namespace(|| "F"), &z_i)?;
+
+    if z_next.len() != arity {
+      return Err(SynthesisError::IncompatibleLengthVector(
+        "z_next".to_string(),
+      ));
+    }
+
+    // Compute i + 1
+    let i_next = AllocatedNum::alloc(cs.namespace(|| "i + 1"), || {
+      Ok(*i.get_value().get()? + E::Base::ONE)
+    })?;
+    cs.enforce(
+      || "check i + 1",
+      |lc| lc,
+      |lc| lc,
+      |lc| lc + i_next.get_variable() - CS::one() - i.get_variable(),
+    );
+
+    // Synthesize the merge of the children and get the new running instance
+    let U_merge = self.synthesize_merge(
+      cs.namespace(|| "merge children"),
+      &pp_digest,
+      &is_leaf,
+      &left,
+      &right,
+      &T,
+    )?;
+
+    // A leaf has no secondary instances to fold, so it starts with the default running instance
+    let U_leaf = AllocatedRelaxedR1CSInstance::default(
+      cs.namespace(|| "Allocate U_default"),
+      NUM_IO_SECONDARY,
+    )?;
+
+    let is_leaf_bool = Boolean::from(is_leaf);
+    let start = conditionally_select(cs.namespace(|| "start"), &i, &left.start, &is_leaf_bool)?;
+    let end = conditionally_select(cs.namespace(|| "end"), &i_next, &right.end, &is_leaf_bool)?;
+    let z_start = conditionally_select_vec(
+      cs.namespace(|| "z_start"),
+      &z_i,
+      &left.z_start,
+      &is_leaf_bool,
+    )?;
+    let z_end = conditionally_select_vec(
+      cs.namespace(|| "z_end"),
+      &z_next,
+      &right.z_end,
+      &is_leaf_bool,
+    )?;
+    let U =
+      U_leaf.conditionally_select(cs.namespace(|| "compute U_new"), &U_merge, &is_leaf_bool)?;
+
+    // Compute the new hash H(pp_digest, start, end, z_start, z_end, U)
+    let hash = self.synthesize_hash(
+      cs.namespace(|| "synthesize output hash"),
+      &pp_digest,
+      &start,
+      &end,
+      &z_start,
+      &z_end,
+      &U,
+    )?;
+
+    // Output the hashes of the children's primary running instances (zero for a leaf),
+    // which are checked by the secondary circuit, and the computed hash
+    let zero = alloc_zero(cs.namespace(|| "zero"));
+    let hash_left = conditionally_select(
+      cs.namespace(|| "hash of the left child's running instance"),
+      &zero,
+      &left.u.X[1],
+      &is_leaf_bool,
+    )?;
+    let hash_right = conditionally_select(
+      cs.namespace(|| "hash of the right child's running instance"),
+      &zero,
+      &right.u.X[1],
+      &is_leaf_bool,
+    )?;
+    hash_left.inputize(cs.namespace(|| "output hash of the left child"))?;
+    hash_right.inputize(cs.namespace(|| "output hash of the right child"))?;
+    hash.inputize(cs.namespace(|| "output new hash of this circuit"))?;
+
+    Ok(z_end)
+  }
+}
+
+/// The secondary augmented circuit of a PCD node, which folds the instances of the primary circuit
+pub struct PCDSecondaryCircuit<E: Engine> {
+  ro_consts: ROConstantsCircuit<E>,
+  inputs: Option<PCDSecondaryCircuitInputs<E>>,
+}
+
+impl<E: Engine> PCDSecondaryCircuit<E> {
+  /// Create a new secondary circuit for the provided inputs
+  pub const fn new(
+    inputs: Option<PCDSecondaryCircuitInputs<E>>,
+    ro_consts: ROConstantsCircuit<E>,
+  ) -> Self {
+    Self { inputs, ro_consts }
+  }
+
+  /// Compute H(pp_digest, U)
+  fn synthesize_hash<CS: ConstraintSystem<E::Base>>(
+    &self,
+    mut cs: CS,
+    pp_digest: &AllocatedNum<E::Base>,
+    U: &AllocatedRelaxedR1CSInstance<E>,
+  ) -> Result<AllocatedNum<E::Base>, SynthesisError> {
+    let mut ro = E::ROCircuit::new(self.ro_consts.clone());
+    ro.absorb(pp_digest);
+    U.absorb_in_ro(cs.namespace(|| "absorb U"), &mut ro)?;
+
+    let hash_bits = ro.squeeze(cs.namespace(|| "hash"), NUM_HASH_BITS)?;
+    le_bits_to_num(cs.namespace(|| "bits to hash"), &hash_bits)
+  }
+
+  /// synthesize circuit giving constraint system
+  pub fn synthesize<CS: ConstraintSystem<<E as Engine>::Base>>(
+    self,
+    cs: &mut CS,
+  ) -> Result<(), SynthesisError> {
+    let children = self
+      .inputs
+      .as_ref()
+      .and_then(|inputs| inputs.children.as_ref());
+
+    // Allocate all witnesses
+    let pp_digest = alloc_scalar_as_base::<E, _>(
+      cs.namespace(|| "pp_digest"),
+      self.inputs.as_ref().map(|inputs| inputs.pp_digest),
+    )?;
+    let is_leaf = AllocatedBit::alloc(
+      cs.namespace(|| "is_leaf"),
+      self.inputs.as_ref().map(|inputs| inputs.children.is_none()),
+    )?;
+    let u = AllocatedR1CSInstance::alloc(
+      cs.namespace(|| "allocate instance u to fold"),
+      self.inputs.as_ref().map(|inputs| &inputs.u),
+      NUM_IO_PRIMARY,
+    )?;
+    let U_left = AllocatedRelaxedR1CSInstance::alloc(
+      cs.namespace(|| "Allocate U_left"),
+      children.map(|c| &c.0),
+      NUM_IO_PRIMARY,
+    )?;
+    let U_right = AllocatedRelaxedR1CSInstance::alloc(
+      cs.namespace(|| "Allocate U_right"),
+      children.map(|c| &c.1),
+      NUM_IO_PRIMARY,
+    )?;
+    let T_u = AllocatedPoint::alloc(
+      cs.namespace(|| "allocate T_u"),
+      children.map(|c| c.2.to_coordinates()),
+    )?;
+    T_u.check_on_curve(cs.namespace(|| "check T_u on curve"))?;
+    let T = AllocatedPoint::alloc(
+      cs.namespace(|| "allocate T"),
+      children.map(|c| c.3.to_coordinates()),
+    )?;
+    T.check_on_curve(cs.namespace(|| "check T on curve"))?;
+
+    // Check that u.X[0] = H(params, U_left) and u.X[1] = H(params, U_right) for a merged node,
+    // and that both are zero for a leaf. Since a hash is zero only with negligible probability,
+    // this also ensures that the primary and the secondary circuits agree on the kind of the node
+    for (name, U, x) in [("left", &U_left, &u.X[0]), ("right", &U_right, &u.X[1])] {
+      let hash =
+        self.synthesize_hash(cs.namespace(|| format!("{name} input hash")), &pp_digest, U)?;
+      cs.enforce(
+        || format!("check u.X = H(params, U_{name}) unless leaf"),
+        |lc| lc + CS::one() - is_leaf.get_variable(),
+        |lc| lc + hash.get_variable(),
+        |lc| lc + x.get_variable(),
+      );
+    }
+
+    // Fold u into the left running instance, then fold the right running instance into the result
+    let U_fold = U_left.fold_with_r1cs(
+      cs.namespace(|| "fold u into U_left"),
+      &pp_digest,
+      &u,
+      &T_u,
+      self.ro_consts.clone(),
+    )?;
+
+    // u binds both running instances, so the challenge is derived from it
+    let mut ro = E::ROCircuit::new(self.ro_consts.clone());
+    ro.absorb(&pp_digest);
+    u.absorb_in_ro(&mut ro);
+    ro.absorb(&T_u.x);
+    ro.absorb(&T_u.y);
+    ro.absorb(&T_u.is_infinity);
+    ro.absorb(&T.x);
+    ro.absorb(&T.y);
+    ro.absorb(&T.is_infinity);
+    let r_bits = ro.squeeze(cs.namespace(|| "r bits"), NUM_CHALLENGE_BITS)?;
+
+    let U_merge = U_fold.fold_with_relaxed_r1cs(
+      cs.namespace(|| "fold U_right into the result"),
+      &U_right,
+      &T,
+      &r_bits,
+    )?;
+
+    // A leaf starts with the instance of its primary circuit
+    let U_leaf = AllocatedRelaxedR1CSInstance::from_r1cs_instance(
+      cs.namespace(|| "Allocate U_leaf"),
+      u.clone(),
+    )?;
+    let U = U_leaf.conditionally_select(
+      cs.namespace(|| "compute U_new"),
+      &U_merge,
+      &Boolean::from(is_leaf),
+    )?;
+
+    let hash = self.synthesize_hash(cs.namespace(|| "synthesize output hash"), &pp_digest, &U)?;
+
+    // Outputs the unmodified hash of the primary circuit and the computed hash
+    u.X[2].inputize(cs.namespace(|| "Output unmodified hash of the primary circuit"))?;
+    hash.inputize(cs.namespace(|| "output new hash of this circuit"))?;
+
+    Ok(())
+  }
+}
+
+/// Enforces `a = b` unless `condition` is set
+fn enforce_equal_unless<F: ff::PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  condition: &AllocatedBit,
+  a: &AllocatedNum<F>,
+  b: &AllocatedNum<F>,
+) {
+  cs.enforce(
+    || "(1 - condition) * (a - b) = 0",
+    |lc| lc + CS::one() - condition.get_variable(),
+    |lc| lc + a.get_variable() - b.get_variable(),
+    |lc| lc,
+  );
+}
@@ -0,0 +1,400 @@
+//! This module implements the gadgets for folding R1CS instances in the PCD augmented circuits.
+//! Unlike the gadgets in `nova::circuit::r1cs`, these support any number of public IO,
+//! fold two running instances with each other, and represent `u` as a non-native field element
+//! because it is no longer small once two running instances are folded.
+use crate::{
+  constants::{BN_LIMB_WIDTH, BN_N_LIMBS, NUM_CHALLENGE_BITS},
+  frontend::{num::AllocatedNum, AllocatedBit, Boolean, ConstraintSystem, SynthesisError},
+  gadgets::{
+    ecc::AllocatedPoint,
+    nonnative::{
+      bignat::BigNat,
+      util::{f_to_nat, Num},
+    },
+    utils::{
+      alloc_bignat_constant, alloc_scalar_as_base, conditionally_select_bignat, le_bits_to_num,
+    },
+  },
+  r1cs::{R1CSInstance, RelaxedR1CSInstance},
+  traits::{commitment::CommitmentTrait, Engine, Group, ROCircuitTrait, ROConstantsCircuit},
+};
+use ff::Field;
+use num_bigint::BigInt;
+
+/// An Allocated R1CS Instance
+#[derive(Clone)]
+pub struct AllocatedR1CSInstance<E: Engine> {
+  pub(crate) comm_W: AllocatedPoint<E>,
+  pub(crate) X: Vec<AllocatedNum<E::Base>>,
+}
+
+impl<E: Engine> AllocatedR1CSInstance<E> {
+  /// Takes the r1cs instance and creates a new allocated r1cs instance with `num_io` public IO
+  pub fn alloc<CS: ConstraintSystem<<E as Engine>::Base>>(
+    mut cs: CS,
+    u: Option<&R1CSInstance<E>>,
+    num_io: usize,
+  ) -> Result<Self, SynthesisError> {
+    let comm_W = AllocatedPoint::alloc(
+      cs.namespace(|| "allocate comm_W"),
+      u.map(|u| u.comm_W.to_coordinates()),
+    )?;
+    comm_W.check_on_curve(cs.namespace(|| "check comm_W on curve"))?;
+
+    let X = (0..num_io)
+      .map(|i| {
+        alloc_scalar_as_base::<E, _>(
+          cs.namespace(|| format!("allocate X[{i}]")),
+          u.map(|u| u.X[i]),
+        )
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(AllocatedR1CSInstance { comm_W, X })
+  }
+
+  /// Absorb the provided instance in the RO
+  pub fn absorb_in_ro(&self, ro: &mut E::ROCircuit) {
+    ro.absorb(&self.comm_W.x);
+    ro.absorb(&self.comm_W.y);
+    ro.absorb(&self.comm_W.is_infinity);
+    for x in &self.X {
+      ro.absorb(x);
+    }
+  }
+}
+
+/// An Allocated Relaxed R1CS Instance
+#[derive(Clone)]
+pub struct AllocatedRelaxedR1CSInstance<E: Engine> {
+  pub(crate) W: AllocatedPoint<E>,
+  pub(crate) E: AllocatedPoint<E>,
+  pub(crate) u: BigNat<E::Base>,
+  pub(crate) X: Vec<BigNat<E::Base>>,
+}
+
+impl<E: Engine> AllocatedRelaxedR1CSInstance<E> {
+  /// Allocates the given `RelaxedR1CSInstance` with `num_io` public IO as a witness of the circuit
+  pub fn alloc<CS: ConstraintSystem<<E as Engine>::Base>>(
+    mut cs: CS,
+    inst: Option<&RelaxedR1CSInstance<E>>,
+    num_io: usize,
+  ) -> Result<Self, SynthesisError> {
+    // As in Nova, we do not need to check that W or E are well-formed (e.g., on the curve)
+    // since the augmented circuits check that the running instances hash to an input of the circuit
+    let W = AllocatedPoint::alloc(
+      cs.namespace(|| "allocate W"),
+      inst.map(|inst| inst.comm_W.to_coordinates()),
+    )?;
+
+    let E = AllocatedPoint::alloc(
+      cs.namespace(|| "allocate E"),
+      inst.map(|inst| inst.comm_E.to_coordinates()),
+    )?;
+
+    let u = BigNat::alloc_from_nat(
+      cs.namespace(|| "allocate u"),
+      || Ok(f_to_nat(&inst.map_or(E::Scalar::ZERO, |inst| inst.u))),
+      BN_LIMB_WIDTH,
+      BN_N_LIMBS,
+    )?;
+
+    // If the input instance is None, then allocate default values 0.
+    let X = (0..num_io)
+      .map(|i| {
+        BigNat::alloc_from_nat(
+          cs.namespace(|| format!("allocate X[{i}]")),
+          || Ok(f_to_nat(&inst.map_or(E::Scalar::ZERO, |inst| inst.X[i]))),
+          BN_LIMB_WIDTH,
+          BN_N_LIMBS,
+        )
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(AllocatedRelaxedR1CSInstance { W, E, u, X })
+  }
+
+  /// Allocates the default `RelaxedR1CSInstance` with `num_io` public IO as constants of the circuit.
+  /// W = E = 0, u = 0, X = 0
+  pub fn default<CS: ConstraintSystem<<E as Engine>::Base>>(
+    mut cs: CS,
+    num_io: usize,
+  ) -> Result<Self, SynthesisError> {
+    let W = AllocatedPoint::default(cs.namespace(|| "allocate W"))?;
+    let E = W.clone();
+
+    let u = alloc_bignat_constant(
+      cs.namespace(|| "allocate u_default"),
+      &BigInt::from(0),
+      BN_LIMB_WIDTH,
+      BN_N_LIMBS,
+    )?;
+    let X = vec![u.clone(); num_io];
+
+    Ok(AllocatedRelaxedR1CSInstance { W, E, u, X })
+  }
+
+  /// Allocates the R1CS Instance as a `RelaxedR1CSInstance` in the circuit.
+  /// E = 0, u = 1
+  pub fn from_r1cs_instance<CS: ConstraintSystem<<E as Engine>::Base>>(
+    mut cs: CS,
+    inst: AllocatedR1CSInstance<E>,
+  ) -> Result<Self, SynthesisError> {
+    let E = AllocatedPoint::default(cs.namespace(|| "allocate default E"))?;
+
+    let u = alloc_bignat_constant(
+      cs.namespace(|| "allocate u = 1"),
+      &BigInt::from(1),
+      BN_LIMB_WIDTH,
+      BN_N_LIMBS,
+    )?;
+
+    let X = inst
+      .X
+      .into_iter()
+      .enumerate()
+      .map(|(i, x)| {
+        BigNat::from_num(
+          cs.namespace(|| format!("allocate X[{i}] from r1cs")),
+          &Num::from(x),
+          BN_LIMB_WIDTH,
+          BN_N_LIMBS,
+        )
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(AllocatedRelaxedR1CSInstance {
+      W: inst.comm_W,
+      E,
+      u,
+      X,
+    })
+  }
+
+  /// Absorb the provided instance in the RO.
+  /// Both `u` and `X` are absorbed as limbs, so the hash binds their non-native values.
+  pub fn absorb_in_ro<CS: ConstraintSystem<<E as Engine>::Base>>(
+    &self,
+    mut cs: CS,
+    ro: &mut E::ROCircuit,
+  ) -> Result<(), SynthesisError> {
+    ro.absorb(&self.W.x);
+    ro.absorb(&self.W.y);
+    ro.absorb(&self.W.is_infinity);
+    ro.absorb(&self.E.x);
+    ro.absorb(&self.E.y);
+    ro.absorb(&self.E.is_infinity);
+
+    for (j, n) in [&self.u].into_iter().chain(self.X.iter()).enumerate() {
+      for (i, limb) in n.as_limbs().iter().enumerate() {
+        let limb = limb.as_allocated_num(
+          cs.namespace(|| format!("convert limb {i} of non-native element {j} to num")),
+        )?;
+        ro.absorb(&limb);
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Folds self with an r1cs instance and returns the result.
+  /// As in Nova, the challenge does not absorb `self`, which must be bound to `u` by the caller.
+  pub fn fold_with_r1cs<CS: ConstraintSystem<<E as Engine>::Base>>(
+    &self,
+    mut cs: CS,
+    params: &AllocatedNum<E::Base>, // hash of R1CSShape of F'
+    u: &AllocatedR1CSInstance<E>,
+    T: &AllocatedPoint<E>,
+    ro_consts: ROConstantsCircuit<E>,
+  ) -> Result<AllocatedRelaxedR1CSInstance<E>, SynthesisError> {
+    // Compute r:
+    let mut ro = E::ROCircuit::new(ro_consts);
+    ro.absorb(params);
+    u.absorb_in_ro(&mut ro);
+    ro.absorb(&T.x);
+    ro.absorb(&T.y);
+    ro.absorb(&T.is_infinity);
+    let r_bits = ro.squeeze(cs.namespace(|| "r bits"), NUM_CHALLENGE_BITS)?;
+    let r = le_bits_to_num(cs.namespace(|| "r"), &r_bits)?;
+
+    // W_fold = self.W + r * u.W
+    let rW = u.comm_W.scalar_mul(cs.namespace(|| "r * u.W"), &r_bits)?;
+    let W_fold = self.W.add(cs.namespace(|| "self.W + r * u.W"), &rW)?;
+
+    // E_fold = self.E + r * T
+    let rT = T.scalar_mul(cs.namespace(|| "r * T"), &r_bits)?;
+    let E_fold = self.E.add(cs.namespace(|| "self.E + r * T"), &rT)?;
+
+    let (r_bn, m_bn) = alloc_challenge_and_modulus::<E, _>(cs.namespace(|| "r and m"), r)?;
+
+    // u_fold = self.u + r
+    let u_fold = self
+      .u
+      .add(&r_bn)?
+      .red_mod(cs.namespace(|| "reduce folded u"), &m_bn)?;
+
+    // X_fold = self.X + r * u.X
+    let X_fold = self
+      .X
+      .iter()
+      .zip(u.X.iter())
+      .enumerate()
+      .map(|(i, (X_r, x))| {
+        let x_bn = BigNat::from_num(
+          cs.namespace(|| format!("allocate X[{i}]_bn")),
+          &Num::from(x.clone()),
+          BN_LIMB_WIDTH,
+          BN_N_LIMBS,
+        )?;
+        fold_bignat(
+          cs.namespace(|| format!("fold X[{i}]")),
+          X_r,
+          &x_bn,
+          &r_bn,
+          &m_bn,
+        )
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Self {
+      W: W_fold,
+      E: E_fold,
+      u: u_fold,
+      X: X_fold,
+    })
+  }
+
+  /// Folds self with another relaxed r1cs instance using the challenge `r_bits` and returns the result.
+  /// The caller must derive `r_bits` from a transcript that binds both instances and `T`.
+  pub fn fold_with_relaxed_r1cs<CS: ConstraintSystem<<E as Engine>::Base>>(
+    &self,
+    mut cs: CS,
+    U: &AllocatedRelaxedR1CSInstance<E>,
+    T: &AllocatedPoint<E>,
+    r_bits: &[AllocatedBit],
+  ) -> Result<AllocatedRelaxedR1CSInstance<E>, SynthesisError> {
+    let r = le_bits_to_num(cs.namespace(|| "r"), r_bits)?;
+
+    // W_fold = self.W + r * U.W
+    let rW = U.W.scalar_mul(cs.namespace(|| "r * U.W"), r_bits)?;
+    let W_fold = self.W.add(cs.namespace(|| "self.W + r * U.W"), &rW)?;
+
+    // E_fold = self.E + r * (T + r * U.E)
+    let rE = U.E.scalar_mul(cs.namespace(|| "r * U.E"), r_bits)?;
+    let T_rE = T.add(cs.namespace(|| "T + r * U.E"), &rE)?;
+    let r_T_rE = T_rE.scalar_mul(cs.namespace(|| "r * (T + r * U.E)"), r_bits)?;
+    let E_fold = self
+      .E
+      .add(cs.namespace(|| "self.E + r * (T + r * U.E)"), &r_T_rE)?;
+
+    let (r_bn, m_bn) = alloc_challenge_and_modulus::<E, _>(cs.namespace(|| "r and m"), r)?;
+
+    // u_fold = self.u + r * U.u
+    let u_fold = fold_bignat(cs.namespace(|| "fold u"), &self.u, &U.u, &r_bn, &m_bn)?;
+
+    // X_fold = self.X + r * U.X
+    let X_fold = self
+      .X
+      .iter()
+      .zip(U.X.iter())
+      .enumerate()
+      .map(|(i, (X1, X2))| {
+        fold_bignat(
+          cs.namespace(|| format!("fold X[{i}]")),
+          X1,
+          X2,
+          &r_bn,
+          &m_bn,
+        )
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Self {
+      W: W_fold,
+      E: E_fold,
+      u: u_fold,
+      X: X_fold,
+    })
+  }
+
+  /// If the condition is true then returns this otherwise it returns the other
+  pub fn conditionally_select<CS: ConstraintSystem<<E as Engine>::Base>>(
+    &self,
+    mut cs: CS,
+    other: &AllocatedRelaxedR1CSInstance<E>,
+    condition: &Boolean,
+  ) -> Result<AllocatedRelaxedR1CSInstance<E>, SynthesisError> {
+    let W = AllocatedPoint::conditionally_select(
+      cs.namespace(|| "W = cond ? self.W : other.W"),
+      &self.W,
+      &other.W,
+      condition,
+    )?;
+
+    let E = AllocatedPoint::conditionally_select(
+      cs.namespace(|| "E = cond ? self.E : other.E"),
+      &self.E,
+      &other.E,
+      condition,
+    )?;
+
+    let u = conditionally_select_bignat(
+      cs.namespace(|| "u = cond ? self.u : other.u"),
+      &self.u,
+      &other.u,
+      condition,
+    )?;
+
+    let X = self
+      .X
+      .iter()
+      .zip(other.X.iter())
+      .enumerate()
+      .map(|(i, (a, b))| {
+        conditionally_select_bignat(
+          cs.namespace(|| format!("X[{i}] = cond ? self.X[{i}] : other.X[{i}]")),
+          a,
+          b,
+          condition,
+        )
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(AllocatedRelaxedR1CSInstance { W, E, u, X })
+  }
+}
+
+/// Analyzes the challenge `r` into limbs and allocates the order of the non-native field as a constant
+fn alloc_challenge_and_modulus<E: Engine, CS: ConstraintSystem<E::Base>>(
+  mut cs: CS,
+  r: AllocatedNum<E::Base>,
+) -> Result<(BigNat<E::Base>, BigNat<E::Base>), SynthesisError> {
+  let r_bn = BigNat::from_num(
+    cs.namespace(|| "allocate r_bn"),
+    &Num::from(r),
+    BN_LIMB_WIDTH,
+    BN_N_LIMBS,
+  )?;
+
+  let m_bn = alloc_bignat_constant(
+    cs.namespace(|| "alloc m"),
+    &E::GE::group_params().2,
+    BN_LIMB_WIDTH,
+    BN_N_LIMBS,
+  )?;
+
+  Ok((r_bn, m_bn))
+}
+
+/// Computes `a + r * b mod m`
+fn fold_bignat<F: ff::PrimeField, CS: ConstraintSystem<F>>(
+  mut cs: CS,
+  a: &BigNat<F>,
+  b: &BigNat<F>,
+  r_bn: &BigNat<F>,
+  m_bn: &BigNat<F>,
+) -> Result<BigNat<F>, SynthesisError> {
+  let (_, r_b) = b.mult_mod(cs.namespace(|| "r * b"), r_bn, m_bn)?;
+  a.add(&r_b)?
+    .red_mod(cs.namespace(|| "reduce a + r * b"), m_bn)
+}
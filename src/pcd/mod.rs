@@ -0,0 +1,754 @@
+//! This module implements proof-carrying data (PCD) for incremental computations on top of Nova's folding scheme.
+//!
+//! Instead of folding the steps of a computation one after the other, the steps are proved as the leaves
+//! of a binary tree, and two `RecursiveSNARK`s over adjacent ranges of steps are merged into one
+//! that covers both ranges by folding their running instances with each other.
+//! Since the leaves and the subtrees are independent, a long computation can be proved in parallel.
+use crate::{
+  constants::{BN_LIMB_WIDTH, BN_N_LIMBS, NUM_CHALLENGE_BITS, NUM_HASH_BITS},
+  digest::{DigestComputer, SimpleDigestible},
+  errors::NovaError,
+  frontend::{
+    r1cs::{NovaShape, NovaWitness},
+    shape_cs::ShapeCS,
+    solver::SatisfyingAssignment,
+    ConstraintSystem, SynthesisError,
+  },
+  gadgets::{
+    nonnative::{bignat::nat_to_limbs, util::f_to_nat},
+    utils::{base_as_scalar, scalar_as_base},
+  },
+  nova::nifs::NIFS,
+  r1cs::{
+    CommitmentKeyHint, R1CSInstance, R1CSShape, R1CSWitness, RelaxedR1CSInstance,
+    RelaxedR1CSWitness,
+  },
+  traits::{
+    circuit::StepCircuit, AbsorbInROTrait, Engine, ROConstants, ROConstantsCircuit, ROTrait,
+  },
+  Commitment, CommitmentKey,
+};
+use core::marker::PhantomData;
+use ff::Field;
+use once_cell::sync::OnceCell;
+use rand_core::OsRng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+mod circuit;
+
+use circuit::{
+  PCDChildInputs, PCDPrimaryCircuit, PCDPrimaryCircuitInputs, PCDSecondaryCircuit,
+  PCDSecondaryCircuitInputs, NUM_IO_PRIMARY, NUM_IO_SECONDARY,
+};
+
+/// A type that holds public parameters of PCD
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct PublicParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  F_arity: usize,
+
+  ro_consts_primary: ROConstants<E1>,
+  ro_consts_circuit_primary: ROConstantsCircuit<E2>,
+
+  ro_consts_secondary: ROConstants<E2>,
+  ro_consts_circuit_secondary: ROConstantsCircuit<E1>,
+
+  ck_primary: CommitmentKey<E1>,
+  r1cs_shape_primary: R1CSShape<E1>,
+
+  ck_secondary: CommitmentKey<E2>,
+  r1cs_shape_secondary: R1CSShape<E2>,
+
+  #[serde(skip, default = "OnceCell::new")]
+  digest: OnceCell<E1::Scalar>,
+  _p: PhantomData<C>,
+}
+
+impl<E1, E2, C> SimpleDigestible for PublicParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+}
+
+impl<E1, E2, C> PublicParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Creates a new `PublicParams` for a circuit `C`.
+  /// See `nova::PublicParams::setup` for the meaning of `ck_hint1` and `ck_hint2`.
+  pub fn setup(
+    c: &C,
+    ck_hint1: &CommitmentKeyHint<E1>,
+    ck_hint2: &CommitmentKeyHint<E2>,
+  ) -> Result<Self, NovaError> {
+    let ro_consts_primary: ROConstants<E1> = ROConstants::<E1>::default();
+    let ro_consts_secondary: ROConstants<E2> = ROConstants::<E2>::default();
+
+    let F_arity = c.arity();
+
+    // ro_consts_circuit_primary are parameterized by E2 because the type alias uses E2::Base = E1::Scalar
+    let ro_consts_circuit_primary: ROConstantsCircuit<E2> = ROConstantsCircuit::<E2>::default();
+    let ro_consts_circuit_secondary: ROConstantsCircuit<E1> = ROConstantsCircuit::<E1>::default();
+
+    // Initialize ck for the primary
+    let circuit_primary: PCDPrimaryCircuit<'_, E2, C> =
+      PCDPrimaryCircuit::new(None, c, ro_consts_circuit_primary.clone());
+    let mut cs: ShapeCS<E1> = ShapeCS::new();
+    let _ = circuit_primary.synthesize(&mut cs);
+    let (r1cs_shape_primary, ck_primary) = cs.r1cs_shape(ck_hint1);
+
+    // Initialize ck for the secondary
+    let circuit_secondary: PCDSecondaryCircuit<E1> =
+      PCDSecondaryCircuit::new(None, ro_consts_circuit_secondary.clone());
+    let mut cs: ShapeCS<E2> = ShapeCS::new();
+    let _ = circuit_secondary.synthesize(&mut cs);
+    let (r1cs_shape_secondary, ck_secondary) = cs.r1cs_shape(ck_hint2);
+
+    if r1cs_shape_primary.num_io != NUM_IO_PRIMARY
+      || r1cs_shape_secondary.num_io != NUM_IO_SECONDARY
+    {
+      return Err(NovaError::InvalidStepCircuitIO);
+    }
+
+    let pp = PublicParams {
+      F_arity,
+
+      ro_consts_primary,
+      ro_consts_circuit_primary,
+
+      ro_consts_secondary,
+      ro_consts_circuit_secondary,
+
+      ck_primary,
+      r1cs_shape_primary,
+
+      ck_secondary,
+      r1cs_shape_secondary,
+
+      digest: OnceCell::new(),
+      _p: Default::default(),
+    };
+
+    // call pp.digest() so the digest is computed here rather than in RecursiveSNARK methods
+    let _ = pp.digest();
+
+    Ok(pp)
+  }
+
+  /// Retrieve the digest of the public parameters.
+  pub fn digest(&self) -> E1::Scalar {
+    self
+      .digest
+      .get_or_try_init(|| DigestComputer::new(self).digest())
+      .cloned()
+      .expect("Failure in retrieving digest")
+  }
+
+  /// Returns the number of constraints in the primary and secondary circuits
+  pub const fn num_constraints(&self) -> (usize, usize) {
+    (
+      self.r1cs_shape_primary.num_cons,
+      self.r1cs_shape_secondary.num_cons,
+    )
+  }
+
+  /// Returns the number of variables in the primary and secondary circuits
+  pub const fn num_variables(&self) -> (usize, usize) {
+    (
+      self.r1cs_shape_primary.num_vars,
+      self.r1cs_shape_secondary.num_vars,
+    )
+  }
+}
+
+/// A SNARK that proves the correct execution of the steps `[start, end)` of an incremental computation.
+///
+/// A `RecursiveSNARK` for a single step is created with `prove_leaf`,
+/// and two `RecursiveSNARK`s over adjacent ranges are combined with `merge`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct RecursiveSNARK<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  start: usize,
+  end: usize,
+
+  z_start: Vec<E1::Scalar>,
+  z_end: Vec<E1::Scalar>,
+
+  r_W_primary: RelaxedR1CSWitness<E1>,
+  r_U_primary: RelaxedR1CSInstance<E1>,
+
+  r_W_secondary: RelaxedR1CSWitness<E2>,
+  r_U_secondary: RelaxedR1CSInstance<E2>,
+
+  l_w_secondary: R1CSWitness<E2>,
+  l_u_secondary: R1CSInstance<E2>,
+
+  _p: PhantomData<C>,
+}
+
+impl<E1, E2, C> RecursiveSNARK<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Create a new `RecursiveSNARK` that proves the execution of the step `i` of the computation on input `zi`
+  pub fn prove_leaf(
+    pp: &PublicParams<E1, E2, C>,
+    c: &C,
+    i: usize,
+    zi: &[E1::Scalar],
+  ) -> Result<Self, NovaError> {
+    if zi.len() != pp.F_arity {
+      return Err(NovaError::InvalidInitialInputLength);
+    }
+
+    // the primary circuit executes the step
+    let mut cs_primary = SatisfyingAssignment::<E1>::new();
+    let inputs_primary: PCDPrimaryCircuitInputs<E2> = PCDPrimaryCircuitInputs::leaf(
+      scalar_as_base::<E1>(pp.digest()),
+      E1::Scalar::from(i as u64),
+      zi.to_vec(),
+    );
+    let circuit_primary: PCDPrimaryCircuit<'_, E2, C> = PCDPrimaryCircuit::new(
+      Some(inputs_primary),
+      c,
+      pp.ro_consts_circuit_primary.clone(),
+    );
+    let z_next = circuit_primary.synthesize(&mut cs_primary)?;
+    let (u_primary, w_primary) =
+      cs_primary.r1cs_instance_and_witness(&pp.r1cs_shape_primary, &pp.ck_primary)?;
+
+    // the secondary circuit starts a running instance with the primary instance
+    let mut cs_secondary = SatisfyingAssignment::<E2>::new();
+    let inputs_secondary: PCDSecondaryCircuitInputs<E1> =
+      PCDSecondaryCircuitInputs::leaf(pp.digest(), u_primary.clone());
+    let circuit_secondary: PCDSecondaryCircuit<E1> = PCDSecondaryCircuit::new(
+      Some(inputs_secondary),
+      pp.ro_consts_circuit_secondary.clone(),
+    );
+    circuit_secondary.synthesize(&mut cs_secondary)?;
+    let (l_u_secondary, l_w_secondary) =
+      cs_secondary.r1cs_instance_and_witness(&pp.r1cs_shape_secondary, &pp.ck_secondary)?;
+
+    if z_next.len() != pp.F_arity {
+      return Err(NovaError::InvalidStepOutputLength);
+    }
+
+    let z_next = z_next
+      .iter()
+      .map(|v| v.get_value().ok_or(SynthesisError::AssignmentMissing))
+      .collect::<Result<Vec<<E1 as Engine>::Scalar>, _>>()?;
+
+    Ok(Self {
+      start: i,
+      end: i + 1,
+
+      z_start: zi.to_vec(),
+      z_end: z_next,
+
+      r_W_primary: RelaxedR1CSWitness::from_r1cs_witness(&pp.r1cs_shape_primary, &w_primary),
+      r_U_primary: RelaxedR1CSInstance::from_r1cs_instance(
+        &pp.ck_primary,
+        &pp.r1cs_shape_primary,
+        &u_primary,
+      ),
+
+      r_W_secondary: RelaxedR1CSWitness::<E2>::default(&pp.r1cs_shape_secondary),
+      r_U_secondary: RelaxedR1CSInstance::<E2>::default(&pp.ck_secondary, &pp.r1cs_shape_secondary),
+
+      l_w_secondary,
+      l_u_secondary,
+
+      _p: Default::default(),
+    })
+  }
+
+  /// Merges two `RecursiveSNARK`s over adjacent ranges of steps into one that covers both ranges.
+  ///
+  /// The step circuit is not executed by a merge, but the primary circuit still synthesizes it on
+  /// the initial input of `left`, so `c` must be a circuit that accepts this input,
+  /// e.g., the circuit of the first step of `left`.
+  pub fn merge(
+    pp: &PublicParams<E1, E2, C>,
+    c: &C,
+    left: &Self,
+    right: &Self,
+  ) -> Result<Self, NovaError> {
+    if left.end != right.start || left.z_end != right.z_start {
+      return Err(NovaError::InvalidMerge);
+    }
+
+    // fold the last secondary instance of each child into its running instance,
+    // and then fold the results with each other
+    let fold_secondary = |node: &Self| {
+      NIFS::prove(
+        &pp.ck_secondary,
+        &pp.ro_consts_secondary,
+        &scalar_as_base::<E1>(pp.digest()),
+        &pp.r1cs_shape_secondary,
+        &node.r_U_secondary,
+        &node.r_W_secondary,
+        &node.l_u_secondary,
+        &node.l_w_secondary,
+      )
+    };
+    let (res_left, res_right) = rayon::join(|| fold_secondary(left), || fold_secondary(right));
+    let (nifs_left, (U_left, W_left)) = res_left?;
+    let (nifs_right, (U_right, W_right)) = res_right?;
+
+    let mut ro = E2::RO::new(pp.ro_consts_secondary.clone());
+    ro.absorb(pp.digest());
+    for (node, nifs) in [(left, &nifs_left), (right, &nifs_right)] {
+      node.l_u_secondary.absorb_in_ro(&mut ro);
+      nifs.comm_T.absorb_in_ro(&mut ro);
+    }
+    let (comm_T_secondary, r_U_secondary, r_W_secondary) = fold_relaxed(
+      &pp.ck_secondary,
+      &pp.r1cs_shape_secondary,
+      ro,
+      &U_left,
+      &W_left,
+      &U_right,
+      &W_right,
+    )?;
+
+    // the primary circuit checks and folds the secondary instances of the children
+    let child_inputs = |node: &Self, comm_T: Commitment<E2>| {
+      PCDChildInputs::new(
+        E1::Scalar::from(node.start as u64),
+        E1::Scalar::from(node.end as u64),
+        node.z_start.clone(),
+        node.z_end.clone(),
+        node.r_U_secondary.clone(),
+        node.l_u_secondary.clone(),
+        comm_T,
+      )
+    };
+    let mut cs_primary = SatisfyingAssignment::<E1>::new();
+    let inputs_primary: PCDPrimaryCircuitInputs<E2> = PCDPrimaryCircuitInputs::merge(
+      scalar_as_base::<E1>(pp.digest()),
+      child_inputs(left, nifs_left.comm_T),
+      child_inputs(right, nifs_right.comm_T),
+      comm_T_secondary,
+    );
+    let circuit_primary: PCDPrimaryCircuit<'_, E2, C> = PCDPrimaryCircuit::new(
+      Some(inputs_primary),
+      c,
+      pp.ro_consts_circuit_primary.clone(),
+    );
+    let _ = circuit_primary.synthesize(&mut cs_primary)?;
+    let (u_primary, w_primary) =
+      cs_primary.r1cs_instance_and_witness(&pp.r1cs_shape_primary, &pp.ck_primary)?;
+
+    // fold the primary instance into the left running instance, and then fold the right running instance
+    let (nifs_primary, (U_primary, W_primary)) = NIFS::prove(
+      &pp.ck_primary,
+      &pp.ro_consts_primary,
+      &pp.digest(),
+      &pp.r1cs_shape_primary,
+      &left.r_U_primary,
+      &left.r_W_primary,
+      &u_primary,
+      &w_primary,
+    )?;
+
+    let mut ro = E1::RO::new(pp.ro_consts_primary.clone());
+    ro.absorb(scalar_as_base::<E1>(pp.digest()));
+    u_primary.absorb_in_ro(&mut ro);
+    nifs_primary.comm_T.absorb_in_ro(&mut ro);
+    let (comm_T_primary, r_U_primary, r_W_primary) = fold_relaxed(
+      &pp.ck_primary,
+      &pp.r1cs_shape_primary,
+      ro,
+      &U_primary,
+      &W_primary,
+      &right.r_U_primary,
+      &right.r_W_primary,
+    )?;
+
+    let mut cs_secondary = SatisfyingAssignment::<E2>::new();
+    let inputs_secondary: PCDSecondaryCircuitInputs<E1> = PCDSecondaryCircuitInputs::merge(
+      pp.digest(),
+      u_primary,
+      left.r_U_primary.clone(),
+      right.r_U_primary.clone(),
+      nifs_primary.comm_T,
+      comm_T_primary,
+    );
+    let circuit_secondary: PCDSecondaryCircuit<E1> = PCDSecondaryCircuit::new(
+      Some(inputs_secondary),
+      pp.ro_consts_circuit_secondary.clone(),
+    );
+    circuit_secondary.synthesize(&mut cs_secondary)?;
+    let (l_u_secondary, l_w_secondary) = cs_secondary
+      .r1cs_instance_and_witness(&pp.r1cs_shape_secondary, &pp.ck_secondary)
+      .map_err(|_e| NovaError::UnSat {
+        reason: "Unable to generate a satisfying witness on the secondary curve".to_string(),
+      })?;
+
+    Ok(Self {
+      start: left.start,
+      end: right.end,
+
+      z_start: left.z_start.clone(),
+      z_end: right.z_end.clone(),
+
+      r_W_primary,
+      r_U_primary,
+
+      r_W_secondary,
+      r_U_secondary,
+
+      l_w_secondary,
+      l_u_secondary,
+
+      _p: Default::default(),
+    })
+  }
+
+  /// Merges a sequence of `RecursiveSNARK`s over adjacent ranges of steps into one that covers all of them.
+  /// The merges at each level of the resulting tree are computed in parallel.
+  /// See `merge` for the requirements on `c`, which must hold for each of the nodes.
+  pub fn merge_all(
+    pp: &PublicParams<E1, E2, C>,
+    c: &C,
+    nodes: Vec<Self>,
+  ) -> Result<Self, NovaError> {
+    let mut nodes = nodes;
+    while nodes.len() > 1 {
+      nodes = nodes
+        .par_chunks(2)
+        .map(|pair| match pair {
+          [left, right] => Self::merge(pp, c, left, right),
+          [node] => Ok(node.clone()),
+          _ => unreachable!(),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    }
+    nodes.pop().ok_or(NovaError::InvalidNumSteps)
+  }
+
+  /// Verify the correctness of the `RecursiveSNARK` for the `num_steps` steps starting at step `start` with input `z_start`
+  pub fn verify(
+    &self,
+    pp: &PublicParams<E1, E2, C>,
+    start: usize,
+    num_steps: usize,
+    z_start: &[E1::Scalar],
+  ) -> Result<Vec<E1::Scalar>, NovaError> {
+    // number of steps cannot be zero
+    let is_num_steps_zero = num_steps == 0;
+
+    // check if the provided proof covers the claimed range of steps
+    let is_range_not_match = self.start != start || self.end != start + num_steps;
+
+    // check if the initial inputs match
+    let is_inputs_not_match = self.z_start != z_start;
+
+    // check if the (relaxed) R1CS instances have the expected number of public outputs
+    let is_instance_io_not_match = self.l_u_secondary.X.len() != NUM_IO_SECONDARY
+      || self.r_U_primary.X.len() != NUM_IO_PRIMARY
+      || self.r_U_secondary.X.len() != NUM_IO_SECONDARY;
+
+    if is_num_steps_zero || is_range_not_match || is_inputs_not_match || is_instance_io_not_match {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Invalid range of steps or inputs".to_string(),
+      });
+    }
+
+    // check if the output hashes in R1CS instances point to the right running instances
+    let (hash_primary, hash_secondary) = {
+      let mut hasher = <E2 as Engine>::RO::new(pp.ro_consts_secondary.clone());
+      hasher.absorb(pp.digest());
+      hasher.absorb(E1::Scalar::from(self.start as u64));
+      hasher.absorb(E1::Scalar::from(self.end as u64));
+      for e in &self.z_start {
+        hasher.absorb(*e);
+      }
+      for e in &self.z_end {
+        hasher.absorb(*e);
+      }
+      absorb_relaxed_instance_in_ro(&self.r_U_secondary, &mut hasher);
+
+      let mut hasher2 = <E1 as Engine>::RO::new(pp.ro_consts_primary.clone());
+      hasher2.absorb(scalar_as_base::<E1>(pp.digest()));
+      absorb_relaxed_instance_in_ro(&self.r_U_primary, &mut hasher2);
+
+      (
+        hasher.squeeze(NUM_HASH_BITS),
+        hasher2.squeeze(NUM_HASH_BITS),
+      )
+    };
+
+    if hash_primary != scalar_as_base::<E2>(self.l_u_secondary.X[0])
+      || hash_secondary != self.l_u_secondary.X[1]
+    {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Invalid output hash in R1CS instances".to_string(),
+      });
+    }
+
+    // check the satisfiability of the provided instances
+    let (res_r_primary, (res_r_secondary, res_l_secondary)) = rayon::join(
+      || {
+        pp.r1cs_shape_primary
+          .is_sat_relaxed(&pp.ck_primary, &self.r_U_primary, &self.r_W_primary)
+      },
+      || {
+        rayon::join(
+          || {
+            pp.r1cs_shape_secondary.is_sat_relaxed(
+              &pp.ck_secondary,
+              &self.r_U_secondary,
+              &self.r_W_secondary,
+            )
+          },
+          || {
+            pp.r1cs_shape_secondary.is_sat(
+              &pp.ck_secondary,
+              &self.l_u_secondary,
+              &self.l_w_secondary,
+            )
+          },
+        )
+      },
+    );
+
+    // check the returned res objects
+    res_r_primary?;
+    res_r_secondary?;
+    res_l_secondary?;
+
+    Ok(self.z_end.clone())
+  }
+
+  /// Get the outputs after the last step of computation.
+  pub fn outputs(&self) -> &[E1::Scalar] {
+    &self.z_end
+  }
+
+  /// The number of steps covered by this `RecursiveSNARK`.
+  pub fn num_steps(&self) -> usize {
+    self.end - self.start
+  }
+}
+
+/// Absorbs a running instance in the RO in the same way as the PCD augmented circuits,
+/// which treat `u` as a non-native field element and absorb it as limbs
+fn absorb_relaxed_instance_in_ro<E: Engine>(U: &RelaxedR1CSInstance<E>, ro: &mut E::RO) {
+  U.comm_W.absorb_in_ro(ro);
+  U.comm_E.absorb_in_ro(ro);
+  for x in core::iter::once(&U.u).chain(U.X.iter()) {
+    let limbs: Vec<E::Scalar> = nat_to_limbs(&f_to_nat(x), BN_LIMB_WIDTH, BN_N_LIMBS).unwrap();
+    for limb in limbs {
+      ro.absorb(scalar_as_base::<E>(limb));
+    }
+  }
+}
+
+/// Folds the running instance `(U2, W2)` into `(U1, W1)`.
+/// The caller seeds `ro` with data that binds both running instances, and the challenge
+/// is squeezed from it after absorbing the commitment to the cross-term.
+fn fold_relaxed<E: Engine>(
+  ck: &CommitmentKey<E>,
+  S: &R1CSShape<E>,
+  mut ro: E::RO,
+  U1: &RelaxedR1CSInstance<E>,
+  W1: &RelaxedR1CSWitness<E>,
+  U2: &RelaxedR1CSInstance<E>,
+  W2: &RelaxedR1CSWitness<E>,
+) -> Result<(Commitment<E>, RelaxedR1CSInstance<E>, RelaxedR1CSWitness<E>), NovaError> {
+  // compute a commitment to the cross-term
+  let r_T = E::Scalar::random(&mut OsRng);
+  let (T, comm_T) = S.commit_T_relaxed(ck, U1, W1, U2, W2, &r_T)?;
+
+  // append `comm_T` to the transcript and obtain a challenge
+  comm_T.absorb_in_ro(&mut ro);
+  let r = base_as_scalar::<E>(ro.squeeze(NUM_CHALLENGE_BITS));
+
+  // fold the instances and the witnesses using `r` and `comm_T`
+  let U = U1.fold_relaxed(U2, &comm_T, &r);
+  let W = W1.fold_relaxed(W2, &T, &r_T, &r)?;
+
+  Ok((comm_T, U, W))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    frontend::{num::AllocatedNum, ConstraintSystem, SynthesisError},
+    provider::{
+      Bn256EngineKZG, GrumpkinEngine, PallasEngine, Secp256k1Engine, Secq256k1Engine, VestaEngine,
+    },
+    traits::snark::default_ck_hint,
+  };
+  use ff::PrimeField;
+
+  #[derive(Clone, Debug, Default)]
+  struct CubicCircuit<F: PrimeField> {
+    _p: PhantomData<F>,
+  }
+
+  impl<F: PrimeField> StepCircuit<F> for CubicCircuit<F> {
+    fn arity(&self) -> usize {
+      1
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(
+      &self,
+      cs: &mut CS,
+      z: &[AllocatedNum<F>],
+    ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+      // Consider a cubic equation: `x^3 + x + 5 = y`, where `x` and `y` are respectively the input and output.
+      let x = &z[0];
+      let x_sq = x.square(cs.namespace(|| "x_sq"))?;
+      let x_cu = x_sq.mul(cs.namespace(|| "x_cu"), x)?;
+      let y = AllocatedNum::alloc(cs.namespace(|| "y"), || {
+        Ok(x_cu.get_value().unwrap() + x.get_value().unwrap() + F::from(5u64))
+      })?;
+
+      cs.enforce(
+        || "y = x^3 + x + 5",
+        |lc| lc + x_cu.get_variable() + x.get_variable() + (F::from(5u64), CS::one()),
+        |lc| lc + CS::one(),
+        |lc| lc + y.get_variable(),
+      );
+
+      Ok(vec![y])
+    }
+  }
+
+  impl<F: PrimeField> CubicCircuit<F> {
+    fn output(&self, z: &[F]) -> Vec<F> {
+      vec![z[0] * z[0] * z[0] + z[0] + F::from(5u64)]
+    }
+  }
+
+  fn test_pcd_nontrivial_with<E1, E2>()
+  where
+    E1: Engine<Base = <E2 as Engine>::Scalar>,
+    E2: Engine<Base = <E1 as Engine>::Scalar>,
+  {
+    let circuit = CubicCircuit::default();
+
+    // produce public parameters
+    let pp = PublicParams::<E1, E2, CubicCircuit<E1::Scalar>>::setup(
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+
+    // compute the trace of the computation
+    let num_steps = 5;
+    let mut z = vec![vec![E1::Scalar::ONE]];
+    for i in 0..num_steps {
+      z.push(circuit.output(&z[i]));
+    }
+
+    // prove each step of the computation independently
+    let leaves = (0..num_steps)
+      .into_par_iter()
+      .map(|i| RecursiveSNARK::prove_leaf(&pp, &circuit, i, &z[i]))
+      .collect::<Result<Vec<_>, _>>()
+      .unwrap();
+    for (i, leaf) in leaves.iter().enumerate() {
+      assert_eq!(leaf.verify(&pp, i, 1, &z[i]), Ok(z[i + 1].clone()));
+    }
+
+    // merge nodes in an unbalanced order and verify the intermediate nodes
+    let node_12 = RecursiveSNARK::merge(&pp, &circuit, &leaves[1], &leaves[2]).unwrap();
+    assert_eq!(node_12.verify(&pp, 1, 2, &z[1]), Ok(z[3].clone()));
+    let node_123 = RecursiveSNARK::merge(&pp, &circuit, &node_12, &leaves[3]).unwrap();
+    assert_eq!(node_123.verify(&pp, 1, 3, &z[1]), Ok(z[4].clone()));
+    let node_0123 = RecursiveSNARK::merge(&pp, &circuit, &leaves[0], &node_123).unwrap();
+    let node = RecursiveSNARK::merge(&pp, &circuit, &node_0123, &leaves[4]).unwrap();
+    assert_eq!(node.num_steps(), num_steps);
+    assert_eq!(
+      node.verify(&pp, 0, num_steps, &z[0]),
+      Ok(z[num_steps].clone())
+    );
+
+    // merge all nodes as a balanced tree
+    let root = RecursiveSNARK::merge_all(&pp, &circuit, leaves.clone()).unwrap();
+    assert_eq!(root.outputs(), &z[num_steps][..]);
+    assert_eq!(
+      root.verify(&pp, 0, num_steps, &z[0]),
+      Ok(z[num_steps].clone())
+    );
+
+    // verification fails for a different range of steps or initial input
+    assert!(root.verify(&pp, 0, num_steps - 1, &z[0]).is_err());
+    assert!(root.verify(&pp, 1, num_steps, &z[0]).is_err());
+    assert!(root.verify(&pp, 0, num_steps, &z[1]).is_err());
+
+    // nodes over ranges that are not adjacent cannot be merged
+    let res = RecursiveSNARK::merge(&pp, &circuit, &leaves[0], &leaves[2]);
+    assert_eq!(res.err(), Some(NovaError::InvalidMerge));
+    let res = RecursiveSNARK::merge(&pp, &circuit, &leaves[1], &leaves[0]);
+    assert_eq!(res.err(), Some(NovaError::InvalidMerge));
+  }
+
+  #[test]
+  fn test_pcd_nontrivial() {
+    test_pcd_nontrivial_with::<PallasEngine, VestaEngine>();
+    test_pcd_nontrivial_with::<Bn256EngineKZG, GrumpkinEngine>();
+    test_pcd_nontrivial_with::<Secp256k1Engine, Secq256k1Engine>();
+  }
+
+  fn test_pcd_tampered_with<E1, E2>()
+  where
+    E1: Engine<Base = <E2 as Engine>::Scalar>,
+    E2: Engine<Base = <E1 as Engine>::Scalar>,
+  {
+    let circuit = CubicCircuit::default();
+    let pp = PublicParams::<E1, E2, CubicCircuit<E1::Scalar>>::setup(
+      &circuit,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+
+    let z0 = vec![E1::Scalar::ONE];
+    let z1 = circuit.output(&z0);
+    let left = RecursiveSNARK::prove_leaf(&pp, &circuit, 0, &z0).unwrap();
+    let right = RecursiveSNARK::prove_leaf(&pp, &circuit, 1, &z1).unwrap();
+
+    // a node that claims a wrong output is rejected
+    let mut tampered = right.clone();
+    tampered.z_end = vec![E1::Scalar::ZERO];
+    assert!(tampered.verify(&pp, 1, 1, &z1).is_err());
+
+    // a merge with a child that claims a wrong range does not produce a valid proof
+    let mut tampered = right.clone();
+    tampered.start = 2;
+    tampered.end = 3;
+    let mut shifted = left.clone();
+    shifted.end = 2;
+    let res = RecursiveSNARK::merge(&pp, &circuit, &shifted, &tampered)
+      .and_then(|node| node.verify(&pp, 0, 3, &z0));
+    assert!(res.is_err());
+  }
+
+  #[test]
+  fn test_pcd_tampered() {
+    test_pcd_tampered_with::<PallasEngine, VestaEngine>();
+  }
+}
@@ -99,7 +99,7 @@ pub struct DerandKey<E: Engine>
 where
   E::GE: DlogGroup,
 {
-  h: <E::GE as DlogGroup>::AffineGroupElement,
+  pub(crate) h: <E::GE as DlogGroup>::AffineGroupElement,
 }
 
 /// A KZG commitment
@@ -26,8 +26,8 @@ pub struct ProverKey<E: Engine> {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(bound = "")]
 pub struct VerifierKey<E: Engine> {
-  ck_v: CommitmentKey<E>,
-  ck_s: CommitmentKey<E>,
+  pub(crate) ck_v: CommitmentKey<E>,
+  pub(crate) ck_s: CommitmentKey<E>,
 }
 
 /// Provides an implementation of a polynomial evaluation engine using IPA
@@ -155,9 +155,9 @@ impl<E: Engine> InnerProductWitness<E> {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(bound = "")]
 pub struct InnerProductArgument<E: Engine> {
-  L_vec: Vec<Commitment<E>>,
-  R_vec: Vec<Commitment<E>>,
-  a_hat: E::Scalar,
+  pub(crate) L_vec: Vec<Commitment<E>>,
+  pub(crate) R_vec: Vec<Commitment<E>>,
+  pub(crate) a_hat: E::Scalar,
 }
 
 impl<E> InnerProductArgument<E>
@@ -30,8 +30,8 @@ pub struct CommitmentKey<E: Engine>
 where
   E::GE: DlogGroup,
 {
-  ck: Vec<<E::GE as DlogGroup>::AffineGroupElement>,
-  h: <E::GE as DlogGroup>::AffineGroupElement,
+  pub(crate) ck: Vec<<E::GE as DlogGroup>::AffineGroupElement>,
+  pub(crate) h: <E::GE as DlogGroup>::AffineGroupElement,
 }
 
 impl<E: Engine> Len for CommitmentKey<E>
@@ -49,7 +49,7 @@ pub struct DerandKey<E: Engine>
 where
   E::GE: DlogGroup,
 {
-  h: <E::GE as DlogGroup>::AffineGroupElement,
+  pub(crate) h: <E::GE as DlogGroup>::AffineGroupElement,
 }
 
 /// A type that holds a commitment
@@ -133,6 +133,22 @@ impl<E: Engine> R1CSShape<E> {
     E::CE::setup(b"ck", max(max(num_cons, num_vars), ck_hint))
   }
 
+  /// Generates public parameters shared by several Rank-1 Constraint Systems (R1CS).
+  ///
+  /// The returned `CommitmentKey` is large enough to be used with any of the provided shapes,
+  /// with `ck_floor` applied to each shape as in `commitment_key`.
+  pub fn commitment_key_for_shapes(
+    shapes: &[&R1CSShape<E>],
+    ck_floor: &CommitmentKeyHint<E>,
+  ) -> CommitmentKey<E> {
+    let size = shapes
+      .iter()
+      .map(|S| max(max(S.num_cons, S.num_vars), ck_floor(S)))
+      .max()
+      .unwrap_or(0);
+    E::CE::setup(b"ck", size)
+  }
+
   /// Returns the digest of the `R1CSShape`
   pub fn digest(&self) -> E::Scalar {
     self
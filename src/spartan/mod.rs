@@ -38,8 +38,8 @@ fn powers<E: Engine>(s: &E::Scalar, n: usize) -> Vec<E::Scalar> {
 }
 
 /// A type that holds a witness to a polynomial evaluation instance
-struct PolyEvalWitness<E: Engine> {
-  p: Vec<E::Scalar>, // polynomial
+pub(crate) struct PolyEvalWitness<E: Engine> {
+  pub(crate) p: Vec<E::Scalar>, // polynomial
 }
 
 impl<E: Engine> PolyEvalWitness<E> {
@@ -165,10 +165,10 @@ impl<E: Engine> PolyEvalWitness<E> {
 }
 
 /// A type that holds a polynomial evaluation instance
-struct PolyEvalInstance<E: Engine> {
-  c: Commitment<E>,  // commitment to the polynomial
-  x: Vec<E::Scalar>, // evaluation point
-  e: E::Scalar,      // claimed evaluation
+pub(crate) struct PolyEvalInstance<E: Engine> {
+  pub(crate) c: Commitment<E>,  // commitment to the polynomial
+  pub(crate) x: Vec<E::Scalar>, // evaluation point
+  pub(crate) e: E::Scalar,      // claimed evaluation
 }
 
 impl<E: Engine> PolyEvalInstance<E> {
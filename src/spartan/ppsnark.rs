@@ -65,18 +65,18 @@ pub struct R1CSShapeSparkRepr<E: Engine> {
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(bound = "")]
 pub struct R1CSShapeSparkCommitment<E: Engine> {
-  N: usize, // size of each vector
+  pub(crate) N: usize, // size of each vector
 
   // commitments to the dense representation
-  comm_row: Commitment<E>,
-  comm_col: Commitment<E>,
-  comm_val_A: Commitment<E>,
-  comm_val_B: Commitment<E>,
-  comm_val_C: Commitment<E>,
+  pub(crate) comm_row: Commitment<E>,
+  pub(crate) comm_col: Commitment<E>,
+  pub(crate) comm_val_A: Commitment<E>,
+  pub(crate) comm_val_B: Commitment<E>,
+  pub(crate) comm_val_C: Commitment<E>,
 
   // commitments to the timestamp polynomials
-  comm_ts_row: Commitment<E>,
-  comm_ts_col: Commitment<E>,
+  pub(crate) comm_ts_row: Commitment<E>,
+  pub(crate) comm_ts_col: Commitment<E>,
 }
 
 impl<E: Engine> TranscriptReprTrait<E::GE> for R1CSShapeSparkCommitment<E> {
@@ -794,9 +794,9 @@ pub struct ProverKey<E: Engine, EE: EvaluationEngineTrait<E>> {
 #[serde(bound = "")]
 pub struct VerifierKey<E: Engine, EE: EvaluationEngineTrait<E>> {
   num_cons: usize,
-  num_vars: usize,
-  vk_ee: EE::VerifierKey,
-  S_comm: R1CSShapeSparkCommitment<E>,
+  pub(crate) num_vars: usize,
+  pub(crate) vk_ee: EE::VerifierKey,
+  pub(crate) S_comm: R1CSShapeSparkCommitment<E>,
   #[serde(skip, default = "OnceCell::new")]
   digest: OnceCell<E::Scalar>,
 }
@@ -811,51 +811,51 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> SimpleDigestible for VerifierKey<E
 pub struct RelaxedR1CSSNARK<E: Engine, EE: EvaluationEngineTrait<E>> {
   // commitment to oracles: the first three are for Az, Bz, Cz,
   // and the last two are for memory reads
-  comm_Az: Commitment<E>,
-  comm_Bz: Commitment<E>,
-  comm_Cz: Commitment<E>,
-  comm_L_row: Commitment<E>,
-  comm_L_col: Commitment<E>,
+  pub(crate) comm_Az: Commitment<E>,
+  pub(crate) comm_Bz: Commitment<E>,
+  pub(crate) comm_Cz: Commitment<E>,
+  pub(crate) comm_L_row: Commitment<E>,
+  pub(crate) comm_L_col: Commitment<E>,
 
   // commitments to aid the memory checks
-  comm_t_plus_r_inv_row: Commitment<E>,
-  comm_w_plus_r_inv_row: Commitment<E>,
-  comm_t_plus_r_inv_col: Commitment<E>,
-  comm_w_plus_r_inv_col: Commitment<E>,
+  pub(crate) comm_t_plus_r_inv_row: Commitment<E>,
+  pub(crate) comm_w_plus_r_inv_row: Commitment<E>,
+  pub(crate) comm_t_plus_r_inv_col: Commitment<E>,
+  pub(crate) comm_w_plus_r_inv_col: Commitment<E>,
 
   // claims about Az, Bz, and Cz polynomials
-  eval_Az_at_tau: E::Scalar,
-  eval_Bz_at_tau: E::Scalar,
-  eval_Cz_at_tau: E::Scalar,
+  pub(crate) eval_Az_at_tau: E::Scalar,
+  pub(crate) eval_Bz_at_tau: E::Scalar,
+  pub(crate) eval_Cz_at_tau: E::Scalar,
 
   // sum-check
-  sc: SumcheckProof<E>,
+  pub(crate) sc: SumcheckProof<E>,
 
   // claims from the end of sum-check
-  eval_Az: E::Scalar,
-  eval_Bz: E::Scalar,
-  eval_Cz: E::Scalar,
-  eval_E: E::Scalar,
-  eval_L_row: E::Scalar,
-  eval_L_col: E::Scalar,
-  eval_val_A: E::Scalar,
-  eval_val_B: E::Scalar,
-  eval_val_C: E::Scalar,
-
-  eval_W: E::Scalar,
-
-  eval_t_plus_r_inv_row: E::Scalar,
-  eval_row: E::Scalar, // address
-  eval_w_plus_r_inv_row: E::Scalar,
-  eval_ts_row: E::Scalar,
-
-  eval_t_plus_r_inv_col: E::Scalar,
-  eval_col: E::Scalar, // address
-  eval_w_plus_r_inv_col: E::Scalar,
-  eval_ts_col: E::Scalar,
+  pub(crate) eval_Az: E::Scalar,
+  pub(crate) eval_Bz: E::Scalar,
+  pub(crate) eval_Cz: E::Scalar,
+  pub(crate) eval_E: E::Scalar,
+  pub(crate) eval_L_row: E::Scalar,
+  pub(crate) eval_L_col: E::Scalar,
+  pub(crate) eval_val_A: E::Scalar,
+  pub(crate) eval_val_B: E::Scalar,
+  pub(crate) eval_val_C: E::Scalar,
+
+  pub(crate) eval_W: E::Scalar,
+
+  pub(crate) eval_t_plus_r_inv_row: E::Scalar,
+  pub(crate) eval_row: E::Scalar, // address
+  pub(crate) eval_w_plus_r_inv_row: E::Scalar,
+  pub(crate) eval_ts_row: E::Scalar,
+
+  pub(crate) eval_t_plus_r_inv_col: E::Scalar,
+  pub(crate) eval_col: E::Scalar, // address
+  pub(crate) eval_w_plus_r_inv_col: E::Scalar,
+  pub(crate) eval_ts_col: E::Scalar,
 
   // a PCS evaluation argument
-  eval_arg: EE::EvaluationArgument,
+  pub(crate) eval_arg: EE::EvaluationArgument,
 }
 
 impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARK<E, EE> {
@@ -1364,6 +1364,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
   /// verifies a proof of satisfiability of a `RelaxedR1CS` instance
   fn verify(&self, vk: &Self::VerifierKey, U: &RelaxedR1CSInstance<E>) -> Result<(), NovaError> {
     let mut transcript = E::TE::new(b"RelaxedR1CSSNARK");
+    eprintln!("DBG vk {:?} W {:?} E {:?} u {:?} X {:?} Ubytes {}", vk.digest(), U.comm_W, U.comm_E, U.u, U.X, hex::encode(U.to_transcript_bytes()));
 
     // append the verifier key (including commitment to R1CS matrices) and the RelaxedR1CSInstance to the transcript
     transcript.absorb(b"vk", &vk.digest());
@@ -1416,6 +1417,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
     let s = transcript.squeeze(b"r")?;
     let coeffs = powers::<E>(&s, num_claims);
     let claim = (coeffs[7] + coeffs[8]) * claim; // rest are zeros
+    eprintln!("DBG tau0 {:?} c {:?} gamma {:?} r {:?} rho0 {:?} s {:?} claim {:?}", tau[0], c, gamma, r, rho[0], s, claim);
 
     // verify sc
     let (claim_sc_final, rand_sc) = self.sc.verify(claim, num_rounds_sc, 3, &mut transcript)?;
@@ -1520,6 +1522,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
         + claim_witness_final_expected
     };
 
+    eprintln!("DBG e {:?} exp {:?} rsc0 {:?}", claim_sc_final, claim_sc_final_expected, rand_sc[0]);
     if claim_sc_final_expected != claim_sc_final {
       return Err(NovaError::InvalidSumcheckProof);
     }
@@ -41,8 +41,8 @@ pub struct ProverKey<E: Engine, EE: EvaluationEngineTrait<E>> {
 #[derive(Serialize, Deserialize)]
 #[serde(bound = "")]
 pub struct VerifierKey<E: Engine, EE: EvaluationEngineTrait<E>> {
-  vk_ee: EE::VerifierKey,
-  S: R1CSShape<E>,
+  pub(crate) vk_ee: EE::VerifierKey,
+  pub(crate) S: R1CSShape<E>,
   #[serde(skip, default = "OnceCell::new")]
   digest: OnceCell<E::Scalar>,
 }
@@ -79,14 +79,14 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> DigestHelperTrait<E> for VerifierK
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(bound = "")]
 pub struct RelaxedR1CSSNARK<E: Engine, EE: EvaluationEngineTrait<E>> {
-  sc_proof_outer: SumcheckProof<E>,
-  claims_outer: (E::Scalar, E::Scalar, E::Scalar),
-  eval_E: E::Scalar,
-  sc_proof_inner: SumcheckProof<E>,
-  eval_W: E::Scalar,
-  sc_proof_batch: SumcheckProof<E>,
-  evals_batch: Vec<E::Scalar>,
-  eval_arg: EE::EvaluationArgument,
+  pub(crate) sc_proof_outer: SumcheckProof<E>,
+  pub(crate) claims_outer: (E::Scalar, E::Scalar, E::Scalar),
+  pub(crate) eval_E: E::Scalar,
+  pub(crate) sc_proof_inner: SumcheckProof<E>,
+  pub(crate) eval_W: E::Scalar,
+  pub(crate) sc_proof_batch: SumcheckProof<E>,
+  pub(crate) evals_batch: Vec<E::Scalar>,
+  pub(crate) eval_arg: EE::EvaluationArgument,
 }
 
 impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for RelaxedR1CSSNARK<E, EE> {
@@ -287,6 +287,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
     let taus_bound_rx = tau.evaluate(&r_x);
     let claim_outer_final_expected =
       taus_bound_rx * (claim_Az * claim_Bz - U.u * claim_Cz - self.eval_E);
+    eprintln!("DBGS vk {:?} U {} eq {:?} e {:?} rx0 {:?} exp {:?}", vk.digest(), hex::encode(crate::traits::TranscriptReprTrait::<E::GE>::to_transcript_bytes(U)), taus_bound_rx, claim_outer_final, r_x[0], claim_outer_final_expected);
     if claim_outer_final != claim_outer_final_expected {
       return Err(NovaError::InvalidSumcheckProof);
     }
@@ -410,7 +411,7 @@ impl<E: Engine, EE: EvaluationEngineTrait<E>> RelaxedR1CSSNARKTrait<E> for Relax
 ///
 /// We allow the polynomial Pᵢ to have different sizes, by appropriately scaling
 /// the claims and resulting evaluations from Sumcheck.
-fn batch_eval_reduce<E: Engine>(
+pub(crate) fn batch_eval_reduce<E: Engine>(
   u_vec: Vec<PolyEvalInstance<E>>,
   w_vec: Vec<PolyEvalWitness<E>>,
   transcript: &mut E::TE,
@@ -484,7 +485,7 @@ fn batch_eval_reduce<E: Engine>(
 
 /// Verifies a batch of polynomial evaluation claims using Sumcheck
 /// reducing them to a single claim at the same point.
-fn batch_eval_verify<E: Engine>(
+pub(crate) fn batch_eval_verify<E: Engine>(
   u_vec: Vec<PolyEvalInstance<E>>,
   transcript: &mut E::TE,
   sc_proof_batch: &SumcheckProof<E>,
@@ -0,0 +1,575 @@
+//! There are two kinds of augmented circuits in SuperNova: the primary and the secondary.
+//! The primary augmented circuit is instantiated once for each of the step circuits of a
+//! non-uniform computation, and all of them hold a single running instance of the secondary circuit.
+//! The secondary augmented circuit holds one running instance for each of the primary circuits,
+//! and folds the last primary instance into the running instance of the circuit that produced it.
+//!
+//! Each circuit takes as input the hash H(params, i, pc, z0, zi, U\[0..n\], ri) of its running instances,
+//! where `pc` is the program counter that selects the step circuit to execute at step `i`.
+
+use crate::{
+  constants::NUM_HASH_BITS,
+  frontend::{
+    num::AllocatedNum, AllocatedBit, Assignment, Boolean, ConstraintSystem, SynthesisError,
+  },
+  gadgets::{
+    ecc::AllocatedPoint,
+    utils::{
+      alloc_num_equals, alloc_num_equals_constant, alloc_scalar_as_base, alloc_zero,
+      conditionally_select_vec, le_bits_to_num,
+    },
+  },
+  nova::circuit::r1cs::{AllocatedR1CSInstance, AllocatedRelaxedR1CSInstance},
+  r1cs::{R1CSInstance, RelaxedR1CSInstance},
+  supernova::StepCircuit,
+  traits::{commitment::CommitmentTrait, Engine, ROCircuitTrait, ROConstantsCircuit},
+  Commitment,
+};
+use core::marker::PhantomData;
+use ff::{Field, PrimeField};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct SuperNovaAugmentedCircuitInputs<E: Engine> {
+  pp_digest: E::Scalar,
+  i: E::Base,
+  z0: Vec<E::Base>,
+  zi: Option<Vec<E::Base>>,
+  U: Option<Vec<RelaxedR1CSInstance<E>>>,
+  ri: Option<E::Base>,
+  r_next: E::Base,
+  u: Option<R1CSInstance<E>>,
+  T: Option<Commitment<E>>,
+  program_counter: E::Base,
+  last_augmented_circuit_index: E::Base,
+}
+
+impl<E: Engine> SuperNovaAugmentedCircuitInputs<E> {
+  /// Create new inputs/witness for the verification circuit
+  pub fn new(
+    pp_digest: E::Scalar,
+    i: E::Base,
+    z0: Vec<E::Base>,
+    zi: Option<Vec<E::Base>>,
+    U: Option<Vec<RelaxedR1CSInstance<E>>>,
+    ri: Option<E::Base>,
+    r_next: E::Base,
+    u: Option<R1CSInstance<E>>,
+    T: Option<Commitment<E>>,
+    program_counter: E::Base,
+    last_augmented_circuit_index: E::Base,
+  ) -> Self {
+    Self {
+      pp_digest,
+      i,
+      z0,
+      zi,
+      U,
+      ri,
+      r_next,
+      u,
+      T,
+      program_counter,
+      last_augmented_circuit_index,
+    }
+  }
+}
+
+/// The static parameters of an augmented circuit in SuperNova
+#[derive(Clone, Copy, Debug)]
+pub struct SuperNovaAugmentedCircuitParams {
+  is_primary_circuit: bool,
+  circuit_index: usize,
+  initial_circuit_index: usize,
+  num_augmented_circuits: usize,
+}
+
+impl SuperNovaAugmentedCircuitParams {
+  /// Parameters of the primary augmented circuit wrapping the step circuit `circuit_index`
+  pub const fn primary(circuit_index: usize, initial_circuit_index: usize) -> Self {
+    Self {
+      is_primary_circuit: true,
+      circuit_index,
+      initial_circuit_index,
+      num_augmented_circuits: 1,
+    }
+  }
+
+  /// Parameters of the secondary augmented circuit folding instances of `num_circuits` primary circuits
+  pub const fn secondary(num_circuits: usize) -> Self {
+    Self {
+      is_primary_circuit: false,
+      circuit_index: 0,
+      initial_circuit_index: 0,
+      num_augmented_circuits: num_circuits,
+    }
+  }
+}
+
+/// The augmented circuit F' in SuperNova that includes a step circuit F,
+/// the circuit for the verifier in Nova's non-interactive folding scheme,
+/// and the selection of the running instance to fold into
+pub struct SuperNovaAugmentedCircuit<'a, E: Engine, SC: StepCircuit<E::Base>> {
+  params: SuperNovaAugmentedCircuitParams,
+  ro_consts: ROConstantsCircuit<E>,
+  inputs: Option<SuperNovaAugmentedCircuitInputs<E>>,
+  step_circuit: &'a SC, // The function that is applied for each step
+}
+
+impl<'a, E: Engine, SC: StepCircuit<E::Base>> SuperNovaAugmentedCircuit<'a, E, SC> {
+  /// Create a new verification circuit for the input relaxed r1cs instances
+  pub const fn new(
+    params: SuperNovaAugmentedCircuitParams,
+    inputs: Option<SuperNovaAugmentedCircuitInputs<E>>,
+    step_circuit: &'a SC,
+    ro_consts: ROConstantsCircuit<E>,
+  ) -> Self {
+    Self {
+      params,
+      inputs,
+      step_circuit,
+      ro_consts,
+    }
+  }
+
+  /// Allocate all witnesses and return
+  fn alloc_witness<CS: ConstraintSystem<<E as Engine>::Base>>(
+    &self,
+    mut cs: CS,
+    arity: usize,
+  ) -> Result<
+    (
+      AllocatedNum<E::Base>,
+      AllocatedNum<E::Base>,
+      AllocatedNum<E::Base>,
+      Vec<AllocatedNum<E::Base>>,
+      Vec<AllocatedNum<E::Base>>,
+      Vec<AllocatedRelaxedR1CSInstance<E>>,
+      AllocatedNum<E::Base>,
+      AllocatedNum<E::Base>,
+      AllocatedR1CSInstance<E>,
+      AllocatedPoint<E>,
+      AllocatedNum<E::Base>,
+    ),
+    SynthesisError,
+  > {
+    // Allocate pp_digest
+    let pp_digest = alloc_scalar_as_base::<E, _>(
+      cs.namespace(|| "pp_digest"),
+      self.inputs.as_ref().map(|inputs| inputs.pp_digest),
+    )?;
+
+    // Allocate i
+    let i = AllocatedNum::alloc(cs.namespace(|| "i"), || Ok(self.inputs.get()?.i))?;
+
+    // Allocate the program counter
+    let program_counter = AllocatedNum::alloc(cs.namespace(|| "program_counter"), || {
+      Ok(self.inputs.get()?.program_counter)
+    })?;
+
+    // Allocate z0
+    let z_0 = (0..arity)
+      .map(|i| {
+        AllocatedNum::alloc(cs.namespace(|| format!("z0_{i}")), || {
+          Ok(self.inputs.get()?.z0[i])
+        })
+      })
+      .collect::<Result<Vec<AllocatedNum<E::Base>>, _>>()?;
+
+    // Allocate zi. If inputs.zi is not provided (base case) allocate default value 0
+    let zero = vec![E::Base::ZERO; arity];
+    let z_i = (0..arity)
+      .map(|i| {
+        AllocatedNum::alloc(cs.namespace(|| format!("zi_{i}")), || {
+          Ok(self.inputs.get()?.zi.as_ref().unwrap_or(&zero)[i])
+        })
+      })
+      .collect::<Result<Vec<AllocatedNum<E::Base>>, _>>()?;
+
+    // Allocate the running instances
+    let U = (0..self.params.num_augmented_circuits)
+      .map(|j| {
+        AllocatedRelaxedR1CSInstance::alloc(
+          cs.namespace(|| format!("Allocate U_{j}")),
+          self
+            .inputs
+            .as_ref()
+            .and_then(|inputs| inputs.U.as_ref())
+            .and_then(|U| U.get(j)),
+        )
+      })
+      .collect::<Result<Vec<AllocatedRelaxedR1CSInstance<E>>, _>>()?;
+
+    // Allocate ri
+    let r_i = AllocatedNum::alloc(cs.namespace(|| "ri"), || {
+      Ok(self.inputs.get()?.ri.unwrap_or(E::Base::ZERO))
+    })?;
+
+    // Allocate r_i+1
+    let r_next = AllocatedNum::alloc(cs.namespace(|| "r_i+1"), || Ok(self.inputs.get()?.r_next))?;
+
+    // Allocate the instance to be folded in
+    let u = AllocatedR1CSInstance::alloc(
+      cs.namespace(|| "allocate instance u to fold"),
+      self.inputs.as_ref().and_then(|inputs| inputs.u.as_ref()),
+    )?;
+
+    // Allocate T
+    let T = AllocatedPoint::alloc(
+      cs.namespace(|| "allocate T"),
+      self
+        .inputs
+        .as_ref()
+        .and_then(|inputs| inputs.T.map(|T| T.to_coordinates())),
+    )?;
+    T.check_on_curve(cs.namespace(|| "check T on curve"))?;
+
+    // Allocate the index of the running instance into which u is folded
+    let last_augmented_circuit_index =
+      AllocatedNum::alloc(cs.namespace(|| "last_augmented_circuit_index"), || {
+        Ok(self.inputs.get()?.last_augmented_circuit_index)
+      })?;
+
+    Ok((
+      pp_digest,
+      i,
+      program_counter,
+      z_0,
+      z_i,
+      U,
+      r_i,
+      r_next,
+      u,
+      T,
+      last_augmented_circuit_index,
+    ))
+  }
+
+  fn synthesize_hash_check<CS: ConstraintSystem<E::Base>>(
+    &self,
+    mut cs: CS,
+    pp_digest: &AllocatedNum<E::Base>,
+    i: &AllocatedNum<E::Base>,
+    program_counter: &AllocatedNum<E::Base>,
+    z_0: &[AllocatedNum<E::Base>],
+    z_i: &[AllocatedNum<E::Base>],
+    U: &[AllocatedRelaxedR1CSInstance<E>],
+    r_i: &AllocatedNum<E::Base>,
+  ) -> Result<AllocatedNum<E::Base>, SynthesisError> {
+    // Check that u.x[0] = Hash(pp_digest, i, pc, z_0, z_i, U[0..n], r_i)
+    let mut ro = E::ROCircuit::new(self.ro_consts.clone());
+    ro.absorb(pp_digest);
+    ro.absorb(i);
+    ro.absorb(program_counter);
+    for e in z_0 {
+      ro.absorb(e);
+    }
+    for e in z_i {
+      ro.absorb(e);
+    }
+    for (j, U) in U.iter().enumerate() {
+      U.absorb_in_ro(cs.namespace(|| format!("absorb U_{j}")), &mut ro)?;
+    }
+    ro.absorb(r_i);
+
+    let hash_bits = ro.squeeze(cs.namespace(|| "Input hash"), NUM_HASH_BITS)?;
+    let hash = le_bits_to_num(cs.namespace(|| "bits to hash"), &hash_bits)?;
+
+    Ok(hash)
+  }
+
+  /// Synthesizes base case and returns the new running instances
+  fn synthesize_base_case<CS: ConstraintSystem<E::Base>>(
+    &self,
+    mut cs: CS,
+    u: AllocatedR1CSInstance<E>,
+    selector: &[Boolean],
+  ) -> Result<Vec<AllocatedRelaxedR1CSInstance<E>>, SynthesisError> {
+    let U_default: AllocatedRelaxedR1CSInstance<E> =
+      AllocatedRelaxedR1CSInstance::default(cs.namespace(|| "Allocate U_default"))?;
+
+    if self.params.is_primary_circuit {
+      // The primary circuit just returns the default R1CS instance
+      Ok(vec![U_default; self.params.num_augmented_circuits])
+    } else {
+      // The secondary circuit places the incoming R1CS instance in the selected slot
+      let U_u =
+        AllocatedRelaxedR1CSInstance::from_r1cs_instance(cs.namespace(|| "Allocate U_u"), u)?;
+      selector
+        .iter()
+        .enumerate()
+        .map(|(j, b)| {
+          U_u.conditionally_select(
+            cs.namespace(|| format!("U_base_{j} = b_{j} ? U_u : U_default")),
+            &U_default,
+            b,
+          )
+        })
+        .collect()
+    }
+  }
+
+  /// Synthesizes non base case and returns the new running instances
+  fn synthesize_non_base_case<CS: ConstraintSystem<<E as Engine>::Base>>(
+    &self,
+    mut cs: CS,
+    pp_digest: &AllocatedNum<E::Base>,
+    U: &[AllocatedRelaxedR1CSInstance<E>],
+    u: &AllocatedR1CSInstance<E>,
+    T: &AllocatedPoint<E>,
+    selector: &[Boolean],
+  ) -> Result<Vec<AllocatedRelaxedR1CSInstance<E>>, SynthesisError> {
+    // Select the running instance to fold into
+    let mut U_selected = U[0].clone();
+    for (j, (U_j, b)) in U.iter().zip(selector.iter()).enumerate().skip(1) {
+      U_selected = U_j.conditionally_select(
+        cs.namespace(|| format!("U_selected = b_{j} ? U_{j} : U_selected")),
+        &U_selected,
+        b,
+      )?;
+    }
+
+    // Run NIFS Verifier
+    let U_fold = U_selected.fold_with_r1cs(
+      cs.namespace(|| "compute fold of U and u"),
+      pp_digest,
+      u,
+      T,
+      self.ro_consts.clone(),
+    )?;
+
+    // Write the folded instance back into the selected slot
+    U.iter()
+      .zip(selector.iter())
+      .enumerate()
+      .map(|(j, (U_j, b))| {
+        U_fold.conditionally_select(
+          cs.namespace(|| format!("U_non_base_{j} = b_{j} ? U_fold : U_{j}")),
+          U_j,
+          b,
+        )
+      })
+      .collect()
+  }
+
+  /// Returns bits indicating which of the running instances is selected by `index`,
+  /// and enforces that exactly one of them is selected
+  fn synthesize_selector<CS: ConstraintSystem<E::Base>>(
+    &self,
+    mut cs: CS,
+    index: &AllocatedNum<E::Base>,
+  ) -> Result<Vec<Boolean>, SynthesisError> {
+    let selector = (0..self.params.num_augmented_circuits)
+      .map(|j| {
+        alloc_num_equals_constant(
+          cs.namespace(|| format!("b_{j} = (index == {j})")),
+          index,
+          E::Base::from(j as u64),
+        )
+      })
+      .collect::<Result<Vec<AllocatedBit>, _>>()?;
+
+    cs.enforce(
+      || "exactly one running instance is selected",
+      |lc| selector.iter().fold(lc, |lc, b| lc + b.get_variable()),
+      |lc| lc + CS::one(),
+      |lc| lc + CS::one(),
+    );
+
+    Ok(selector.into_iter().map(Boolean::from).collect())
+  }
+}
+
+impl<E: Engine, SC: StepCircuit<E::Base>> SuperNovaAugmentedCircuit<'_, E, SC> {
+  /// synthesize circuit giving constraint system
+  pub fn synthesize<CS: ConstraintSystem<<E as Engine>::Base>>(
+    self,
+    cs: &mut CS,
+  ) -> Result<(AllocatedNum<E::Base>, Vec<AllocatedNum<E::Base>>), SynthesisError> {
+    let arity = self.step_circuit.arity();
+
+    // Allocate all witnesses
+    let (
+      pp_digest,
+      i,
+      program_counter,
+      z_0,
+      z_i,
+      U,
+      r_i,
+      r_next,
+      u,
+      T,
+      last_augmented_circuit_index,
+    ) = self.alloc_witness(cs.namespace(|| "allocate the circuit witness"), arity)?;
+
+    // Compute variable indicating if this is the base case
+    let zero = alloc_zero(cs.namespace(|| "zero"));
+    let is_base_case = alloc_num_equals(cs.namespace(|| "Check if base case"), &i.clone(), &zero)?;
+
+    // The program counter must select this circuit
+    cs.enforce(
+      || "program_counter = circuit_index",
+      |lc| {
+        lc + program_counter.get_variable()
+          - (E::Base::from(self.params.circuit_index as u64), CS::one())
+      },
+      |lc| lc + CS::one(),
+      |lc| lc,
+    );
+
+    // In the base case, the program counter must be the initial one
+    cs.enforce(
+      || "base_case * (program_counter - initial_circuit_index) = 0",
+      |lc| lc + is_base_case.get_variable(),
+      |lc| {
+        lc + program_counter.get_variable()
+          - (
+            E::Base::from(self.params.initial_circuit_index as u64),
+            CS::one(),
+          )
+      },
+      |lc| lc,
+    );
+
+    // compute hash of the non-deterministic inputs
+    let hash = self.synthesize_hash_check(
+      cs.namespace(|| "synthesize input hash check"),
+      &pp_digest,
+      &i,
+      &program_counter,
+      &z_0,
+      &z_i,
+      &U,
+      &r_i,
+    )?;
+
+    let check_non_base_pass = alloc_num_equals(
+      cs.namespace(|| "check consistency of u.X[0] with H(params, U, i, pc, z0, zi)"),
+      &u.X0,
+      &hash,
+    )?;
+
+    // Compute which of the running instances u is folded into
+    let selector = self.synthesize_selector(
+      cs.namespace(|| "select running instance"),
+      &last_augmented_circuit_index,
+    )?;
+
+    // Synthesize the circuit for the base case and get the new running instances
+    let Unew_base =
+      self.synthesize_base_case(cs.namespace(|| "base case"), u.clone(), &selector)?;
+
+    // Synthesize the circuit for the non-base case and get the new running instances
+    let Unew_non_base = self.synthesize_non_base_case(
+      cs.namespace(|| "synthesize non base case"),
+      &pp_digest,
+      &U,
+      &u,
+      &T,
+      &selector,
+    )?;
+
+    // Either check_non_base_pass=true or we are in the base case
+    let should_be_false = AllocatedBit::nor(
+      cs.namespace(|| "check_non_base_pass nor base_case"),
+      &check_non_base_pass,
+      &is_base_case,
+    )?;
+    cs.enforce(
+      || "check_non_base_pass nor base_case = false",
+      |lc| lc + should_be_false.get_variable(),
+      |lc| lc + CS::one(),
+      |lc| lc,
+    );
+
+    // Compute the U_new
+    let Unew = Unew_base
+      .iter()
+      .zip(Unew_non_base.iter())
+      .enumerate()
+      .map(|(j, (U_base, U_non_base))| {
+        U_base.conditionally_select(
+          cs.namespace(|| format!("compute U_new_{j}")),
+          U_non_base,
+          &Boolean::from(is_base_case.clone()),
+        )
+      })
+      .collect::<Result<Vec<AllocatedRelaxedR1CSInstance<E>>, _>>()?;
+
+    // Compute i + 1
+    let i_new = AllocatedNum::alloc(cs.namespace(|| "i + 1"), || {
+      Ok(*i.get_value().get()? + E::Base::ONE)
+    })?;
+    cs.enforce(
+      || "check i + 1",
+      |lc| lc,
+      |lc| lc,
+      |lc| lc + i_new.get_variable() - CS::one() - i.get_variable(),
+    );
+
+    // Compute z_{i+1}
+    let z_input = conditionally_select_vec(
+      cs.namespace(|| "select input to F"),
+      &z_0,
+      &z_i,
+      &Boolean::from(is_base_case),
+    )?;
+
+    let (program_counter_next, z_next) =
+      self
+        .step_circuit
+        .synthesize(&mut cs.namespace(|| "F"), &program_counter, &z_input)?;
+
+    if z_next.len() != arity {
+      return Err(SynthesisError::IncompatibleLengthVector(
+        "z_next".to_string(),
+      ));
+    }
+
+    // Compute the new hash H(pp_digest, i+1, pc_{i+1}, z0, z_{i+1}, Unew, r_next)
+    let hash = self.synthesize_hash_check(
+      cs.namespace(|| "synthesize output hash check"),
+      &pp_digest,
+      &i_new,
+      &program_counter_next,
+      &z_0,
+      &z_next,
+      &Unew,
+      &r_next,
+    )?;
+
+    // Outputs the computed hash and u.X[1] that corresponds to the hash of the other circuit
+    u.X1
+      .inputize(cs.namespace(|| "Output unmodified hash of the other circuit"))?;
+    hash.inputize(cs.namespace(|| "output new hash of this circuit"))?;
+
+    Ok((program_counter_next, z_next))
+  }
+}
+
+/// The step circuit of the secondary augmented circuit, which leaves the program counter and its input unchanged
+#[derive(Clone, Debug, Default)]
+pub struct TrivialSecondaryCircuit<F: PrimeField> {
+  _p: PhantomData<F>,
+}
+
+impl<F: PrimeField> StepCircuit<F> for TrivialSecondaryCircuit<F> {
+  fn arity(&self) -> usize {
+    1
+  }
+
+  fn circuit_index(&self) -> usize {
+    0
+  }
+
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    _cs: &mut CS,
+    pc: &AllocatedNum<F>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(AllocatedNum<F>, Vec<AllocatedNum<F>>), SynthesisError> {
+    Ok((pc.clone(), z.to_vec()))
+  }
+}
@@ -0,0 +1,888 @@
+//! This module implements SuperNova, a non-uniform IVC scheme built on Nova's folding scheme.
+//!
+//! A non-uniform computation consists of several step circuits, and the step circuit executed
+//! at each step is selected by a program counter that is output by the previous step.
+//! The prover keeps one running instance per step circuit, so each step only pays
+//! for the step circuit it actually executes.
+use crate::{
+  constants::NUM_HASH_BITS,
+  digest::{DigestComputer, SimpleDigestible},
+  errors::NovaError,
+  frontend::{
+    num::AllocatedNum,
+    r1cs::{NovaShape, NovaWitness},
+    shape_cs::ShapeCS,
+    solver::SatisfyingAssignment,
+    ConstraintSystem, SynthesisError,
+  },
+  gadgets::utils::scalar_as_base,
+  nova::nifs::NIFS,
+  r1cs::{
+    CommitmentKeyHint, R1CSInstance, R1CSShape, R1CSWitness, RelaxedR1CSInstance,
+    RelaxedR1CSWitness,
+  },
+  traits::{AbsorbInROTrait, Engine, ROConstants, ROConstantsCircuit, ROTrait},
+  CommitmentKey,
+};
+use core::marker::PhantomData;
+use ff::{Field, PrimeField};
+use once_cell::sync::OnceCell;
+use rand_core::OsRng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+mod circuit;
+
+use circuit::{
+  SuperNovaAugmentedCircuit, SuperNovaAugmentedCircuitInputs, SuperNovaAugmentedCircuitParams,
+  TrivialSecondaryCircuit,
+};
+
+/// A helper trait for a step of a non-uniform incremental computation
+pub trait StepCircuit<F: PrimeField>: Send + Sync + Clone {
+  /// Return the number of inputs or outputs of each step
+  /// (this method is called only at circuit synthesis time)
+  /// `synthesize` and `output` methods are expected to take as
+  /// input a vector of size equal to arity and output a vector of size equal to arity
+  fn arity(&self) -> usize;
+
+  /// Return the index of this circuit among the circuits of the non-uniform computation
+  fn circuit_index(&self) -> usize;
+
+  /// Synthesize the circuit for a computation step and return the program counter
+  /// that selects the circuit of the next step along with the output of the step `z_{i+1}`
+  fn synthesize<CS: ConstraintSystem<F>>(
+    &self,
+    cs: &mut CS,
+    pc: &AllocatedNum<F>,
+    z: &[AllocatedNum<F>],
+  ) -> Result<(AllocatedNum<F>, Vec<AllocatedNum<F>>), SynthesisError>;
+}
+
+/// A collection of step circuits that make up a non-uniform incremental computation
+pub trait NonUniformCircuit<E1: Engine> {
+  /// The type of the step circuits
+  type C1: StepCircuit<E1::Scalar>;
+
+  /// Return the number of step circuits
+  fn num_circuits(&self) -> usize;
+
+  /// Return the step circuit with the given index
+  fn primary_circuit(&self, circuit_index: usize) -> Self::C1;
+
+  /// Return the index of the step circuit that is executed first
+  fn initial_circuit_index(&self) -> usize {
+    0
+  }
+}
+
+/// A type that holds public parameters of SuperNova
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct PublicParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  F_arity: usize,
+  initial_circuit_index: usize,
+
+  ro_consts_primary: ROConstants<E1>,
+  ro_consts_circuit_primary: ROConstantsCircuit<E2>,
+
+  ro_consts_secondary: ROConstants<E2>,
+  ro_consts_circuit_secondary: ROConstantsCircuit<E1>,
+
+  ck_primary: CommitmentKey<E1>,
+  r1cs_shapes_primary: Vec<R1CSShape<E1>>,
+
+  ck_secondary: CommitmentKey<E2>,
+  r1cs_shape_secondary: R1CSShape<E2>,
+
+  #[serde(skip, default = "OnceCell::new")]
+  digest: OnceCell<E1::Scalar>,
+  _p: PhantomData<C>,
+}
+
+impl<E1, E2, C> SimpleDigestible for PublicParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+}
+
+impl<E1, E2, C> PublicParams<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Creates a new `PublicParams` for the step circuits of a non-uniform computation.
+  ///
+  /// All primary circuits share a single commitment key, which is large enough for the largest of them.
+  /// See `nova::PublicParams::setup` for the meaning of `ck_hint1` and `ck_hint2`;
+  /// `ck_hint1` is applied to each of the primary circuits.
+  pub fn setup<NC: NonUniformCircuit<E1, C1 = C>>(
+    non_uniform_circuit: &NC,
+    ck_hint1: &CommitmentKeyHint<E1>,
+    ck_hint2: &CommitmentKeyHint<E2>,
+  ) -> Result<Self, NovaError> {
+    let num_circuits = non_uniform_circuit.num_circuits();
+    let initial_circuit_index = non_uniform_circuit.initial_circuit_index();
+    if num_circuits == 0 || initial_circuit_index >= num_circuits {
+      return Err(NovaError::InvalidCircuitIndex);
+    }
+
+    let ro_consts_primary: ROConstants<E1> = ROConstants::<E1>::default();
+    let ro_consts_secondary: ROConstants<E2> = ROConstants::<E2>::default();
+
+    // ro_consts_circuit_primary are parameterized by E2 because the type alias uses E2::Base = E1::Scalar
+    let ro_consts_circuit_primary: ROConstantsCircuit<E2> = ROConstantsCircuit::<E2>::default();
+    let ro_consts_circuit_secondary: ROConstantsCircuit<E1> = ROConstantsCircuit::<E1>::default();
+
+    let F_arity = non_uniform_circuit.primary_circuit(0).arity();
+
+    // Initialize the shapes of the primary circuits
+    let r1cs_shapes_primary = (0..num_circuits)
+      .map(|circuit_index| {
+        let c = non_uniform_circuit.primary_circuit(circuit_index);
+        if c.circuit_index() != circuit_index {
+          return Err(NovaError::InvalidCircuitIndex);
+        }
+        if c.arity() != F_arity {
+          return Err(NovaError::InvalidStepCircuitIO);
+        }
+
+        let circuit_primary: SuperNovaAugmentedCircuit<'_, E2, C> = SuperNovaAugmentedCircuit::new(
+          SuperNovaAugmentedCircuitParams::primary(circuit_index, initial_circuit_index),
+          None,
+          &c,
+          ro_consts_circuit_primary.clone(),
+        );
+        let mut cs: ShapeCS<E1> = ShapeCS::new();
+        let _ = circuit_primary.synthesize(&mut cs);
+        Ok(cs.r1cs_shape_only())
+      })
+      .collect::<Result<Vec<R1CSShape<E1>>, NovaError>>()?;
+
+    // Initialize ck for the primary, which is shared by all primary circuits
+    let ck_primary = R1CSShape::commitment_key_for_shapes(
+      &r1cs_shapes_primary.iter().collect::<Vec<_>>(),
+      ck_hint1,
+    );
+
+    // Initialize ck for the secondary
+    let tc = TrivialSecondaryCircuit::<E2::Scalar>::default();
+    let circuit_secondary: SuperNovaAugmentedCircuit<'_, E1, _> = SuperNovaAugmentedCircuit::new(
+      SuperNovaAugmentedCircuitParams::secondary(num_circuits),
+      None,
+      &tc,
+      ro_consts_circuit_secondary.clone(),
+    );
+    let mut cs: ShapeCS<E2> = ShapeCS::new();
+    let _ = circuit_secondary.synthesize(&mut cs);
+    let (r1cs_shape_secondary, ck_secondary) = cs.r1cs_shape(ck_hint2);
+
+    if r1cs_shapes_primary.iter().any(|S| S.num_io != 2) || r1cs_shape_secondary.num_io != 2 {
+      return Err(NovaError::InvalidStepCircuitIO);
+    }
+
+    let pp = PublicParams {
+      F_arity,
+      initial_circuit_index,
+
+      ro_consts_primary,
+      ro_consts_circuit_primary,
+
+      ro_consts_secondary,
+      ro_consts_circuit_secondary,
+
+      ck_primary,
+      r1cs_shapes_primary,
+
+      ck_secondary,
+      r1cs_shape_secondary,
+
+      digest: OnceCell::new(),
+      _p: Default::default(),
+    };
+
+    // call pp.digest() so the digest is computed here rather than in RecursiveSNARK methods
+    let _ = pp.digest();
+
+    Ok(pp)
+  }
+
+  /// Retrieve the digest of the public parameters.
+  pub fn digest(&self) -> E1::Scalar {
+    self
+      .digest
+      .get_or_try_init(|| DigestComputer::new(self).digest())
+      .cloned()
+      .expect("Failure in retrieving digest")
+  }
+
+  /// Returns the number of step circuits
+  pub fn num_circuits(&self) -> usize {
+    self.r1cs_shapes_primary.len()
+  }
+
+  /// Returns the number of constraints in each of the primary circuits and in the secondary circuit
+  pub fn num_constraints(&self) -> (Vec<usize>, usize) {
+    (
+      self
+        .r1cs_shapes_primary
+        .iter()
+        .map(|S| S.num_cons)
+        .collect(),
+      self.r1cs_shape_secondary.num_cons,
+    )
+  }
+
+  /// Returns the number of variables in each of the primary circuits and in the secondary circuit
+  pub fn num_variables(&self) -> (Vec<usize>, usize) {
+    (
+      self
+        .r1cs_shapes_primary
+        .iter()
+        .map(|S| S.num_vars)
+        .collect(),
+      self.r1cs_shape_secondary.num_vars,
+    )
+  }
+}
+
+/// A SNARK that proves the correct execution of a non-uniform incremental computation
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct RecursiveSNARK<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  z0: Vec<E1::Scalar>,
+
+  r_W_primary: Vec<RelaxedR1CSWitness<E1>>,
+  r_U_primary: Vec<RelaxedR1CSInstance<E1>>,
+  ri_primary: E1::Scalar,
+
+  r_W_secondary: RelaxedR1CSWitness<E2>,
+  r_U_secondary: RelaxedR1CSInstance<E2>,
+  ri_secondary: E2::Scalar,
+
+  l_w_secondary: R1CSWitness<E2>,
+  l_u_secondary: R1CSInstance<E2>,
+
+  i: usize,
+
+  zi: Vec<E1::Scalar>,
+  program_counter: E1::Scalar,
+
+  _p: PhantomData<C>,
+}
+
+impl<E1, E2, C> RecursiveSNARK<E1, E2, C>
+where
+  E1: Engine<Base = <E2 as Engine>::Scalar>,
+  E2: Engine<Base = <E1 as Engine>::Scalar>,
+  C: StepCircuit<E1::Scalar>,
+{
+  /// Create new instance of recursive SNARK, where `c` is the initial step circuit
+  pub fn new(pp: &PublicParams<E1, E2, C>, c: &C, z0: &[E1::Scalar]) -> Result<Self, NovaError> {
+    if z0.len() != pp.F_arity {
+      return Err(NovaError::InvalidInitialInputLength);
+    }
+
+    let circuit_index = c.circuit_index();
+    if circuit_index != pp.initial_circuit_index {
+      return Err(NovaError::InvalidCircuitIndex);
+    }
+
+    let ri_primary = E1::Scalar::random(&mut OsRng);
+    let ri_secondary = E2::Scalar::random(&mut OsRng);
+
+    // base case for the primary
+    let mut cs_primary = SatisfyingAssignment::<E1>::new();
+    let inputs_primary: SuperNovaAugmentedCircuitInputs<E2> = SuperNovaAugmentedCircuitInputs::new(
+      scalar_as_base::<E1>(pp.digest()),
+      E1::Scalar::ZERO,
+      z0.to_vec(),
+      None,
+      None,
+      None,
+      ri_primary, // "r next"
+      None,
+      None,
+      E1::Scalar::from(circuit_index as u64),
+      E1::Scalar::ZERO,
+    );
+
+    let circuit_primary: SuperNovaAugmentedCircuit<'_, E2, C> = SuperNovaAugmentedCircuit::new(
+      SuperNovaAugmentedCircuitParams::primary(circuit_index, pp.initial_circuit_index),
+      Some(inputs_primary),
+      c,
+      pp.ro_consts_circuit_primary.clone(),
+    );
+    let (pc_next, zi_primary) = circuit_primary.synthesize(&mut cs_primary)?;
+    let (u_primary, w_primary) = cs_primary
+      .r1cs_instance_and_witness(&pp.r1cs_shapes_primary[circuit_index], &pp.ck_primary)?;
+
+    // base case for the secondary
+    let mut cs_secondary = SatisfyingAssignment::<E2>::new();
+    let inputs_secondary: SuperNovaAugmentedCircuitInputs<E1> =
+      SuperNovaAugmentedCircuitInputs::new(
+        pp.digest(),
+        E2::Scalar::ZERO,
+        vec![E2::Scalar::ZERO],
+        None,
+        None,
+        None,
+        ri_secondary, // "r next"
+        Some(u_primary.clone()),
+        None,
+        E2::Scalar::ZERO,
+        E2::Scalar::from(circuit_index as u64),
+      );
+    let tc = TrivialSecondaryCircuit::<E2::Scalar>::default();
+    let circuit_secondary: SuperNovaAugmentedCircuit<'_, E1, _> = SuperNovaAugmentedCircuit::new(
+      SuperNovaAugmentedCircuitParams::secondary(pp.num_circuits()),
+      Some(inputs_secondary),
+      &tc,
+      pp.ro_consts_circuit_secondary.clone(),
+    );
+    let _ = circuit_secondary.synthesize(&mut cs_secondary)?;
+    let (u_secondary, w_secondary) =
+      cs_secondary.r1cs_instance_and_witness(&pp.r1cs_shape_secondary, &pp.ck_secondary)?;
+
+    // IVC proof for the primary circuits: only the running instance of the executed circuit is non-trivial
+    let (r_W_primary, r_U_primary) = pp
+      .r1cs_shapes_primary
+      .iter()
+      .enumerate()
+      .map(|(j, S)| {
+        if j == circuit_index {
+          (
+            RelaxedR1CSWitness::from_r1cs_witness(S, &w_primary),
+            RelaxedR1CSInstance::from_r1cs_instance(&pp.ck_primary, S, &u_primary),
+          )
+        } else {
+          (
+            RelaxedR1CSWitness::default(S),
+            RelaxedR1CSInstance::default(&pp.ck_primary, S),
+          )
+        }
+      })
+      .unzip();
+
+    // IVC proof for the secondary circuit
+    let l_w_secondary = w_secondary;
+    let l_u_secondary = u_secondary;
+    let r_W_secondary = RelaxedR1CSWitness::<E2>::default(&pp.r1cs_shape_secondary);
+    let r_U_secondary =
+      RelaxedR1CSInstance::<E2>::default(&pp.ck_secondary, &pp.r1cs_shape_secondary);
+
+    if zi_primary.len() != pp.F_arity {
+      return Err(NovaError::InvalidStepOutputLength);
+    }
+
+    let zi_primary = zi_primary
+      .iter()
+      .map(|v| v.get_value().ok_or(SynthesisError::AssignmentMissing))
+      .collect::<Result<Vec<<E1 as Engine>::Scalar>, _>>()?;
+    let program_counter = pc_next
+      .get_value()
+      .ok_or(SynthesisError::AssignmentMissing)?;
+
+    Ok(Self {
+      z0: z0.to_vec(),
+
+      r_W_primary,
+      r_U_primary,
+      ri_primary,
+
+      r_W_secondary,
+      r_U_secondary,
+      ri_secondary,
+
+      l_w_secondary,
+      l_u_secondary,
+
+      i: 0,
+
+      zi: zi_primary,
+      program_counter,
+
+      _p: Default::default(),
+    })
+  }
+
+  /// Updates the provided `RecursiveSNARK` by executing a step of the incremental computation,
+  /// where `c` must be the step circuit selected by the current program counter
+  pub fn prove_step(&mut self, pp: &PublicParams<E1, E2, C>, c: &C) -> Result<(), NovaError> {
+    // first step was already done in the constructor
+    if self.i == 0 {
+      self.i = 1;
+      return Ok(());
+    }
+
+    let circuit_index = c.circuit_index();
+    if circuit_index >= pp.num_circuits()
+      || E1::Scalar::from(circuit_index as u64) != self.program_counter
+    {
+      return Err(NovaError::InvalidCircuitIndex);
+    }
+
+    // fold the secondary circuit's instance
+    let (nifs_secondary, (r_U_secondary, r_W_secondary)) = NIFS::prove(
+      &pp.ck_secondary,
+      &pp.ro_consts_secondary,
+      &scalar_as_base::<E1>(pp.digest()),
+      &pp.r1cs_shape_secondary,
+      &self.r_U_secondary,
+      &self.r_W_secondary,
+      &self.l_u_secondary,
+      &self.l_w_secondary,
+    )?;
+
+    let r_next_primary = E1::Scalar::random(&mut OsRng);
+
+    let mut cs_primary = SatisfyingAssignment::<E1>::new();
+    let inputs_primary: SuperNovaAugmentedCircuitInputs<E2> = SuperNovaAugmentedCircuitInputs::new(
+      scalar_as_base::<E1>(pp.digest()),
+      E1::Scalar::from(self.i as u64),
+      self.z0.to_vec(),
+      Some(self.zi.clone()),
+      Some(vec![self.r_U_secondary.clone()]),
+      Some(self.ri_primary),
+      r_next_primary,
+      Some(self.l_u_secondary.clone()),
+      Some(nifs_secondary.comm_T),
+      self.program_counter,
+      E1::Scalar::ZERO,
+    );
+
+    let circuit_primary: SuperNovaAugmentedCircuit<'_, E2, C> = SuperNovaAugmentedCircuit::new(
+      SuperNovaAugmentedCircuitParams::primary(circuit_index, pp.initial_circuit_index),
+      Some(inputs_primary),
+      c,
+      pp.ro_consts_circuit_primary.clone(),
+    );
+    let (pc_next, zi_primary) = circuit_primary.synthesize(&mut cs_primary)?;
+
+    let (l_u_primary, l_w_primary) = cs_primary
+      .r1cs_instance_and_witness(&pp.r1cs_shapes_primary[circuit_index], &pp.ck_primary)?;
+
+    // fold the primary circuit's instance into the running instance of the executed circuit
+    let (nifs_primary, (r_U_primary, r_W_primary)) = NIFS::prove(
+      &pp.ck_primary,
+      &pp.ro_consts_primary,
+      &pp.digest(),
+      &pp.r1cs_shapes_primary[circuit_index],
+      &self.r_U_primary[circuit_index],
+      &self.r_W_primary[circuit_index],
+      &l_u_primary,
+      &l_w_primary,
+    )?;
+
+    let r_next_secondary = E2::Scalar::random(&mut OsRng);
+
+    let mut cs_secondary = SatisfyingAssignment::<E2>::new();
+    let inputs_secondary: SuperNovaAugmentedCircuitInputs<E1> =
+      SuperNovaAugmentedCircuitInputs::new(
+        pp.digest(),
+        E2::Scalar::from(self.i as u64),
+        vec![E2::Scalar::ZERO],
+        Some(vec![E2::Scalar::ZERO]),
+        Some(self.r_U_primary.clone()),
+        Some(self.ri_secondary),
+        r_next_secondary,
+        Some(l_u_primary),
+        Some(nifs_primary.comm_T),
+        E2::Scalar::ZERO,
+        E2::Scalar::from(circuit_index as u64),
+      );
+
+    let tc = TrivialSecondaryCircuit::<E2::Scalar>::default();
+    let circuit_secondary: SuperNovaAugmentedCircuit<'_, E1, _> = SuperNovaAugmentedCircuit::new(
+      SuperNovaAugmentedCircuitParams::secondary(pp.num_circuits()),
+      Some(inputs_secondary),
+      &tc,
+      pp.ro_consts_circuit_secondary.clone(),
+    );
+    let _ = circuit_secondary.synthesize(&mut cs_secondary)?;
+
+    let (l_u_secondary, l_w_secondary) = cs_secondary
+      .r1cs_instance_and_witness(&pp.r1cs_shape_secondary, &pp.ck_secondary)
+      .map_err(|_e| NovaError::UnSat {
+        reason: "Unable to generate a satisfying witness on the secondary curve".to_string(),
+      })?;
+
+    // update the running instances and witnesses
+    self.zi = zi_primary
+      .iter()
+      .map(|v| v.get_value().ok_or(SynthesisError::AssignmentMissing))
+      .collect::<Result<Vec<<E1 as Engine>::Scalar>, _>>()?;
+    self.program_counter = pc_next
+      .get_value()
+      .ok_or(SynthesisError::AssignmentMissing)?;
+
+    self.l_u_secondary = l_u_secondary;
+    self.l_w_secondary = l_w_secondary;
+
+    self.r_U_primary[circuit_index] = r_U_primary;
+    self.r_W_primary[circuit_index] = r_W_primary;
+
+    self.i += 1;
+
+    self.r_U_secondary = r_U_secondary;
+    self.r_W_secondary = r_W_secondary;
+
+    self.ri_primary = r_next_primary;
+    self.ri_secondary = r_next_secondary;
+
+    Ok(())
+  }
+
+  /// Verify the correctness of the `RecursiveSNARK`
+  pub fn verify(
+    &self,
+    pp: &PublicParams<E1, E2, C>,
+    num_steps: usize,
+    z0: &[E1::Scalar],
+  ) -> Result<Vec<E1::Scalar>, NovaError> {
+    // number of steps cannot be zero
+    let is_num_steps_zero = num_steps == 0;
+
+    // check if the provided proof has executed num_steps
+    let is_num_steps_not_match = self.i != num_steps;
+
+    // check if the initial inputs match
+    let is_inputs_not_match = self.z0 != z0;
+
+    // check if there is one running instance per primary circuit
+    let is_num_instances_not_match =
+      self.r_U_primary.len() != pp.num_circuits() || self.r_W_primary.len() != pp.num_circuits();
+
+    // check if the (relaxed) R1CS instances have two public outputs
+    let is_instance_has_two_outputs = self.l_u_secondary.X.len() != 2
+      || self.r_U_primary.iter().any(|U| U.X.len() != 2)
+      || self.r_U_secondary.X.len() != 2;
+
+    if is_num_steps_zero
+      || is_num_steps_not_match
+      || is_inputs_not_match
+      || is_num_instances_not_match
+      || is_instance_has_two_outputs
+    {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Invalid number of steps or inputs".to_string(),
+      });
+    }
+
+    // check if the output hashes in R1CS instances point to the right running instances
+    let (hash_primary, hash_secondary) = {
+      let mut hasher = <E2 as Engine>::RO::new(pp.ro_consts_secondary.clone());
+      hasher.absorb(pp.digest());
+      hasher.absorb(E1::Scalar::from(num_steps as u64));
+      hasher.absorb(self.program_counter);
+      for e in z0 {
+        hasher.absorb(*e);
+      }
+      for e in &self.zi {
+        hasher.absorb(*e);
+      }
+      self.r_U_secondary.absorb_in_ro(&mut hasher);
+      hasher.absorb(self.ri_primary);
+
+      let mut hasher2 = <E1 as Engine>::RO::new(pp.ro_consts_primary.clone());
+      hasher2.absorb(scalar_as_base::<E1>(pp.digest()));
+      hasher2.absorb(E2::Scalar::from(num_steps as u64));
+      hasher2.absorb(E2::Scalar::ZERO);
+      hasher2.absorb(E2::Scalar::ZERO);
+      hasher2.absorb(E2::Scalar::ZERO);
+      for U in &self.r_U_primary {
+        U.absorb_in_ro(&mut hasher2);
+      }
+      hasher2.absorb(self.ri_secondary);
+
+      (
+        hasher.squeeze(NUM_HASH_BITS),
+        hasher2.squeeze(NUM_HASH_BITS),
+      )
+    };
+
+    if hash_primary != scalar_as_base::<E2>(self.l_u_secondary.X[0])
+      || hash_secondary != self.l_u_secondary.X[1]
+    {
+      return Err(NovaError::ProofVerifyError {
+        reason: "Invalid output hash in R1CS instances".to_string(),
+      });
+    }
+
+    // check the satisfiability of the provided instances
+    let (res_r_primary, (res_r_secondary, res_l_secondary)) = rayon::join(
+      || {
+        pp.r1cs_shapes_primary
+          .par_iter()
+          .zip(self.r_U_primary.par_iter())
+          .zip(self.r_W_primary.par_iter())
+          .try_for_each(|((S, U), W)| S.is_sat_relaxed(&pp.ck_primary, U, W))
+      },
+      || {
+        rayon::join(
+          || {
+            pp.r1cs_shape_secondary.is_sat_relaxed(
+              &pp.ck_secondary,
+              &self.r_U_secondary,
+              &self.r_W_secondary,
+            )
+          },
+          || {
+            pp.r1cs_shape_secondary.is_sat(
+              &pp.ck_secondary,
+              &self.l_u_secondary,
+              &self.l_w_secondary,
+            )
+          },
+        )
+      },
+    );
+
+    // check the returned res objects
+    res_r_primary?;
+    res_r_secondary?;
+    res_l_secondary?;
+
+    Ok(self.zi.clone())
+  }
+
+  /// Get the outputs after the last step of computation.
+  pub fn outputs(&self) -> &[E1::Scalar] {
+    &self.zi
+  }
+
+  /// The program counter that selects the step circuit of the next step.
+  pub fn program_counter(&self) -> E1::Scalar {
+    self.program_counter
+  }
+
+  /// The number of steps which have been executed thus far.
+  pub fn num_steps(&self) -> usize {
+    self.i
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    provider::{
+      Bn256EngineKZG, GrumpkinEngine, PallasEngine, Secp256k1Engine, Secq256k1Engine, VestaEngine,
+    },
+    traits::snark::default_ck_hint,
+  };
+
+  // A step circuit that computes `x^3 + x + 5` when its index is 0 and `x^2` when its index is 1,
+  // and then hands over to the other circuit
+  #[derive(Clone, Debug, Default)]
+  struct AlternatingCircuit<F: PrimeField> {
+    circuit_index: usize,
+    _p: PhantomData<F>,
+  }
+
+  impl<F: PrimeField> StepCircuit<F> for AlternatingCircuit<F> {
+    fn arity(&self) -> usize {
+      1
+    }
+
+    fn circuit_index(&self) -> usize {
+      self.circuit_index
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(
+      &self,
+      cs: &mut CS,
+      pc: &AllocatedNum<F>,
+      z: &[AllocatedNum<F>],
+    ) -> Result<(AllocatedNum<F>, Vec<AllocatedNum<F>>), SynthesisError> {
+      let x = &z[0];
+      let y = if self.circuit_index == 0 {
+        // Consider a cubic equation: `x^3 + x + 5 = y`, where `x` and `y` are respectively the input and output.
+        let x_sq = x.square(cs.namespace(|| "x_sq"))?;
+        let x_cu = x_sq.mul(cs.namespace(|| "x_cu"), x)?;
+        let y = AllocatedNum::alloc(cs.namespace(|| "y"), || {
+          Ok(x_cu.get_value().unwrap() + x.get_value().unwrap() + F::from(5u64))
+        })?;
+
+        cs.enforce(
+          || "y = x^3 + x + 5",
+          |lc| lc + x_cu.get_variable() + x.get_variable() + (F::from(5u64), CS::one()),
+          |lc| lc + CS::one(),
+          |lc| lc + y.get_variable(),
+        );
+        y
+      } else {
+        x.square(cs.namespace(|| "x_sq"))?
+      };
+
+      // pc_next = 1 - pc
+      let pc_next = AllocatedNum::alloc(cs.namespace(|| "pc_next"), || {
+        Ok(F::ONE - pc.get_value().unwrap())
+      })?;
+      cs.enforce(
+        || "pc_next = 1 - pc",
+        |lc| lc + pc_next.get_variable() + pc.get_variable(),
+        |lc| lc + CS::one(),
+        |lc| lc + CS::one(),
+      );
+
+      Ok((pc_next, vec![y]))
+    }
+  }
+
+  impl<F: PrimeField> AlternatingCircuit<F> {
+    fn output(&self, z: &[F]) -> Vec<F> {
+      if self.circuit_index == 0 {
+        vec![z[0] * z[0] * z[0] + z[0] + F::from(5u64)]
+      } else {
+        vec![z[0] * z[0]]
+      }
+    }
+  }
+
+  struct AlternatingProgram {
+    initial_circuit_index: usize,
+  }
+
+  impl<E1: Engine> NonUniformCircuit<E1> for AlternatingProgram {
+    type C1 = AlternatingCircuit<E1::Scalar>;
+
+    fn num_circuits(&self) -> usize {
+      2
+    }
+
+    fn primary_circuit(&self, circuit_index: usize) -> Self::C1 {
+      AlternatingCircuit {
+        circuit_index,
+        _p: PhantomData,
+      }
+    }
+
+    fn initial_circuit_index(&self) -> usize {
+      self.initial_circuit_index
+    }
+  }
+
+  fn test_ivc_nontrivial_with<E1, E2>(initial_circuit_index: usize)
+  where
+    E1: Engine<Base = <E2 as Engine>::Scalar>,
+    E2: Engine<Base = <E1 as Engine>::Scalar>,
+  {
+    let program = AlternatingProgram {
+      initial_circuit_index,
+    };
+
+    // produce public parameters
+    let pp = PublicParams::<E1, E2, AlternatingCircuit<E1::Scalar>>::setup(
+      &program,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+
+    // the two circuits have different shapes
+    let (num_cons_primary, _) = pp.num_constraints();
+    assert_eq!(num_cons_primary.len(), 2);
+    assert_ne!(num_cons_primary[0], num_cons_primary[1]);
+
+    let num_steps = 5;
+    let z0 = vec![E1::Scalar::from(2u64)];
+
+    // produce a recursive SNARK
+    let c = NonUniformCircuit::<E1>::primary_circuit(&program, initial_circuit_index);
+    let mut recursive_snark = RecursiveSNARK::new(&pp, &c, &z0).unwrap();
+    let mut zn_direct = c.output(&z0);
+
+    for i in 0..num_steps {
+      // pick the circuit selected by the program counter
+      let circuit_index = if recursive_snark.program_counter() == E1::Scalar::ZERO {
+        0
+      } else {
+        1
+      };
+      let c = NonUniformCircuit::<E1>::primary_circuit(&program, circuit_index);
+      let res = recursive_snark.prove_step(&pp, &c);
+      assert!(res.is_ok());
+      if i > 0 {
+        zn_direct = c.output(&zn_direct);
+      }
+
+      // verify the recursive snark at each step of recursion
+      let res = recursive_snark.verify(&pp, i + 1, &z0);
+      assert!(res.is_ok());
+      assert_eq!(res.unwrap(), zn_direct);
+    }
+
+    // a step with the circuit not selected by the program counter is rejected
+    let circuit_index = if recursive_snark.program_counter() == E1::Scalar::ZERO {
+      1
+    } else {
+      0
+    };
+    let c = NonUniformCircuit::<E1>::primary_circuit(&program, circuit_index);
+    let res = recursive_snark.prove_step(&pp, &c);
+    assert_eq!(res, Err(NovaError::InvalidCircuitIndex));
+
+    // verification fails for a different number of steps or initial inputs
+    assert!(recursive_snark.verify(&pp, num_steps + 1, &z0).is_err());
+    assert!(recursive_snark
+      .verify(&pp, num_steps, &[E1::Scalar::ONE])
+      .is_err());
+  }
+
+  #[test]
+  fn test_ivc_nontrivial() {
+    test_ivc_nontrivial_with::<PallasEngine, VestaEngine>(0);
+    test_ivc_nontrivial_with::<Bn256EngineKZG, GrumpkinEngine>(1);
+    test_ivc_nontrivial_with::<Secp256k1Engine, Secq256k1Engine>(0);
+  }
+
+  fn test_setup_with<E1, E2>()
+  where
+    E1: Engine<Base = <E2 as Engine>::Scalar>,
+    E2: Engine<Base = <E1 as Engine>::Scalar>,
+  {
+    // the initial circuit must be one of the circuits of the program
+    let program = AlternatingProgram {
+      initial_circuit_index: 2,
+    };
+    let pp = PublicParams::<E1, E2, AlternatingCircuit<E1::Scalar>>::setup(
+      &program,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    );
+    assert_eq!(pp.err(), Some(NovaError::InvalidCircuitIndex));
+
+    // the recursive SNARK must start with the initial circuit
+    let program = AlternatingProgram {
+      initial_circuit_index: 0,
+    };
+    let pp = PublicParams::<E1, E2, AlternatingCircuit<E1::Scalar>>::setup(
+      &program,
+      &*default_ck_hint(),
+      &*default_ck_hint(),
+    )
+    .unwrap();
+    let c = NonUniformCircuit::<E1>::primary_circuit(&program, 1);
+    let res = RecursiveSNARK::new(&pp, &c, &[E1::Scalar::ONE]);
+    assert_eq!(res.err(), Some(NovaError::InvalidCircuitIndex));
+  }
+
+  #[test]
+  fn test_setup() {
+    test_setup_with::<PallasEngine, VestaEngine>();
+  }
+}